@@ -0,0 +1,103 @@
+//! The same inputs matched with this crate and with the typed headers of
+//! hyperx, which parse the whole value into a list first. Both sides pick
+//! the best entry for the requested value the same way, an exact entry
+//! beating a wildcard and then the higher q-value.
+
+use std::time::Duration;
+
+use accept_encoding::{match_for_encoding, match_for_mime_type};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use hyperx::{
+    header::{Accept, AcceptEncoding, Encoding, Header, Quality, Raw},
+    mime::{self, Mime},
+};
+
+fn hyperx_match_encoding(raw: &Raw, want: &Encoding) -> Option<(bool, Quality)> {
+    let AcceptEncoding(items) = AcceptEncoding::parse_header(raw).ok()?;
+    items
+        .iter()
+        .filter_map(|item| match &item.item {
+            encoding if encoding == want => Some((true, item.quality)),
+            Encoding::EncodingExt(s) if s == "*" => Some((false, item.quality)),
+            _ => None,
+        })
+        .max()
+}
+
+fn hyperx_match_mime_type(raw: &Raw, want: &Mime) -> Option<(u8, Quality)> {
+    let Accept(items) = Accept::parse_header(raw).ok()?;
+    items
+        .iter()
+        .filter_map(|item| {
+            let rank = if item.item.type_() == mime::STAR && item.item.subtype() == mime::STAR {
+                1
+            } else if item.item.type_() != want.type_() {
+                return None;
+            } else if item.item.subtype() == mime::STAR {
+                2
+            } else if item.item.subtype() == want.subtype() {
+                3
+            } else {
+                return None;
+            };
+            Some((rank, item.quality))
+        })
+        .max()
+}
+
+fn accept_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("competitors_accept_encoding");
+    group.measurement_time(Duration::from_secs(5));
+    let long_list = (0..200)
+        .map(|i| format!("x-coding-{i};q=0.{}", i % 10))
+        .chain(["br;q=0.5".to_string()])
+        .collect::<Vec<_>>()
+        .join(", ");
+    let inputs: Vec<(&str, &[u8])> = vec![
+        ("chrome", b"gzip, deflate, br, zstd"),
+        ("curl", b"deflate, gzip, br, zstd"),
+        ("q_values", b"gzip;q=1.0, identity; q=0.5, *;q=0"),
+        ("long_list", long_list.as_bytes()),
+    ];
+    for (name, input) in inputs {
+        group.bench_with_input(BenchmarkId::new("accept_encoding", name), input, |b, i| {
+            b.iter(|| black_box(match_for_encoding(i, b"br")))
+        });
+        let raw = Raw::from(input);
+        group.bench_with_input(BenchmarkId::new("hyperx", name), &raw, |b, raw| {
+            b.iter(|| black_box(hyperx_match_encoding(raw, &Encoding::Brotli)))
+        });
+    }
+}
+
+fn accept(c: &mut Criterion) {
+    let mut group = c.benchmark_group("competitors_accept");
+    group.measurement_time(Duration::from_secs(5));
+    let long_list = (0..200)
+        .map(|i| format!("application/x-type-{i};q=0.{}", i % 10))
+        .chain(["image/*;q=0.5".to_string()])
+        .collect::<Vec<_>>()
+        .join(", ");
+    let inputs: Vec<(&str, &[u8])> = vec![
+        (
+            "chrome",
+            b"text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,\
+            image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+        ),
+        ("curl", b"*/*"),
+        ("long_list", long_list.as_bytes()),
+    ];
+    let want: Mime = "image/webp".parse().unwrap();
+    for (name, input) in inputs {
+        group.bench_with_input(BenchmarkId::new("accept_encoding", name), input, |b, i| {
+            b.iter(|| black_box(match_for_mime_type(i, b"image/webp")))
+        });
+        let raw = Raw::from(input);
+        group.bench_with_input(BenchmarkId::new("hyperx", name), &raw, |b, raw| {
+            b.iter(|| black_box(hyperx_match_mime_type(raw, &want)))
+        });
+    }
+}
+
+criterion_group!(benches, accept_encoding, accept);
+criterion_main!(benches);
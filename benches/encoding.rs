@@ -1,19 +1,66 @@
 use std::time::Duration;
 
-use accept_encoding::match_for_encoding;
+use accept_encoding::{
+    match_for_encoding, match_for_encoding_with_options, EncodingBatchMatcher,
+    EncodingBatchResults, MatchOptions,
+};
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
 fn encoding(c: &mut Criterion) {
     let mut group = c.benchmark_group("encoding");
     group.sample_size(500);
     group.measurement_time(Duration::from_secs(10));
-    let input_values: Vec<&[u8]> = vec![b"gzip, deflate, br", b"gzip, deflate"];
+    let input_values: Vec<&[u8]> = vec![
+        b"gzip, deflate, br",
+        b"gzip, deflate",
+        b"gzip, deflate, br, zstd",
+        b"gzip,deflate,br",
+    ];
     let encoding = b"br";
+    let parser = if cfg!(feature = "parser-const") {
+        "straight_line"
+    } else {
+        "lexer_combinator"
+    };
     for i in 0..input_values.len() {
-        group.bench_with_input(BenchmarkId::new("lexer_combinator", i), &i, |b, i| {
+        group.bench_with_input(BenchmarkId::new(parser, i), &i, |b, i| {
             b.iter(|| black_box(match_for_encoding(input_values[*i], encoding)))
         });
     }
+
+    let long_input = b"compress;q=0.5;foo=\"a, b\", deflate;q=0.5;level=9, identity;q=0.1, \
+        x-custom;ext=1;other=\"long quoted value\", gzip;q=0.8, br";
+    let lenient = MatchOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let q_values = b"gzip;q=0.875, br;q=0.999, deflate;q=0.5, zstd;q=1.000";
+    group.bench_function("q_values", |b| {
+        b.iter(|| black_box(match_for_encoding(q_values, b"zstd")))
+    });
+    let long_names = b"X-Custom-Compression, Pack200-GZip;q=0.5, x-experimental-zstd-dictionary";
+    group.bench_function("long_names", |b| {
+        b.iter(|| black_box(match_for_encoding(long_names, b"pack200-gzip")))
+    });
+    let batch: Vec<&[u8]> = input_values.iter().copied().cycle().take(1000).collect();
+    let batch_matcher = EncodingBatchMatcher::new(encoding, MatchOptions::default());
+    let mut batch_results = EncodingBatchResults::default();
+    group.bench_function("batch_1000", |b| {
+        b.iter(|| {
+            batch_matcher.match_into(batch.iter().copied(), &mut batch_results);
+            black_box(batch_results.len())
+        })
+    });
+    group.bench_function("long_strict", |b| {
+        b.iter(|| black_box(match_for_encoding(long_input, encoding)))
+    });
+    group.bench_function("long_lenient", |b| {
+        b.iter(|| {
+            black_box(match_for_encoding_with_options(
+                long_input, encoding, &lenient,
+            ))
+        })
+    });
 }
 
 criterion_group!(benches, encoding);
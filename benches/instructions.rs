@@ -0,0 +1,74 @@
+//! Instruction counts measured under Cachegrind, which stay the same from
+//! run to run unlike the wall-clock times in `encoding.rs`. Needs valgrind.
+
+use accept_encoding::{
+    match_for_encoding, match_for_encoding_with_options, match_for_mime_type, MatchOptions,
+};
+use iai::black_box;
+
+const LONG_INPUT: &[u8] = b"compress;q=0.5;foo=\"a, b\", deflate;q=0.5;level=9, identity;q=0.1, \
+    x-custom;ext=1;other=\"long quoted value\", gzip;q=0.8, br";
+
+const BROWSER_ACCEPT: &[u8] = b"text/html,application/xhtml+xml,application/xml;q=0.9,\
+    image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+
+fn encoding_common() {
+    black_box(match_for_encoding(
+        black_box(b"gzip, deflate, br"),
+        black_box(b"br"),
+    ));
+}
+
+fn encoding_uncommon() {
+    black_box(match_for_encoding(
+        black_box(b"gzip,deflate,br"),
+        black_box(b"br"),
+    ));
+}
+
+fn encoding_q_values() {
+    black_box(match_for_encoding(
+        black_box(b"gzip;q=0.875, br;q=0.999, deflate;q=0.5, zstd;q=1.000"),
+        black_box(b"zstd"),
+    ));
+}
+
+fn encoding_long_strict() {
+    black_box(match_for_encoding(black_box(LONG_INPUT), black_box(b"br")));
+}
+
+fn encoding_long_lenient() {
+    let lenient = MatchOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    black_box(match_for_encoding_with_options(
+        black_box(LONG_INPUT),
+        black_box(b"br"),
+        &lenient,
+    ));
+}
+
+fn mime_type_exact() {
+    black_box(match_for_mime_type(
+        black_box(BROWSER_ACCEPT),
+        black_box(b"image/webp"),
+    ));
+}
+
+fn mime_type_wildcard() {
+    black_box(match_for_mime_type(
+        black_box(BROWSER_ACCEPT),
+        black_box(b"application/json"),
+    ));
+}
+
+iai::main!(
+    encoding_common,
+    encoding_uncommon,
+    encoding_q_values,
+    encoding_long_strict,
+    encoding_long_lenient,
+    mime_type_exact,
+    mime_type_wildcard,
+);
@@ -0,0 +1,68 @@
+const DEFAULT_SYMBOL_PREFIX: &str = "c_";
+
+fn main() {
+    let prefix = symbol_prefix();
+    println!("cargo:rustc-env=ACCEPT_ENCODING_SYMBOL_PREFIX={prefix}");
+    #[cfg(feature = "cbindgen")]
+    generate_header(&prefix);
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
+
+/// The prefix of the exported C function names, taken from
+/// ACCEPT_ENCODING_SYMBOL_PREFIX so that a C project can avoid clashes
+/// with its own `c_` names.
+fn symbol_prefix() -> String {
+    println!("cargo:rerun-if-env-changed=ACCEPT_ENCODING_SYMBOL_PREFIX");
+    let prefix = match std::env::var("ACCEPT_ENCODING_SYMBOL_PREFIX") {
+        Ok(prefix) => prefix,
+        Err(std::env::VarError::NotPresent) => return DEFAULT_SYMBOL_PREFIX.to_string(),
+        Err(e) => panic!("ACCEPT_ENCODING_SYMBOL_PREFIX: {e}"),
+    };
+    let is_identifier = prefix
+        .bytes()
+        .next()
+        .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+        && prefix
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    assert!(
+        is_identifier,
+        "ACCEPT_ENCODING_SYMBOL_PREFIX must start a C identifier, got {prefix:?}"
+    );
+    prefix
+}
+
+/// Regenerates include/accept_encoding.h from the `c` module. The header is
+/// checked in so C users do not need this feature or cbindgen.
+#[cfg(feature = "cbindgen")]
+fn generate_header(prefix: &str) {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/c.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    // cbindgen skips functions whose export_name is a macro call, so give it
+    // a copy of the module with the names spelled out.
+    let source = std::fs::read_to_string(format!("{crate_dir}/src/c.rs")).unwrap();
+    let mut expanded = String::with_capacity(source.len());
+    let mut rest = source.as_str();
+    while let Some(start) = rest.find("symbol!(\"") {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + "symbol!(\"".len()..];
+        let end = rest.find("\")").expect("unterminated symbol!");
+        expanded.push_str(&format!("\"{prefix}{}\"", &rest[..end]));
+        rest = &rest[end + "\")".len()..];
+    }
+    expanded.push_str(rest);
+    let expanded_path = format!("{out_dir}/c.rs");
+    std::fs::write(&expanded_path, expanded).unwrap();
+
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).unwrap();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(expanded_path)
+        .generate()
+        .expect("generating the C header")
+        .write_to_file(format!("{crate_dir}/include/accept_encoding.h"));
+}
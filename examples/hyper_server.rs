@@ -0,0 +1,102 @@
+//! Serves the files in a directory, picking a precompressed `.br` or `.gz`
+//! sibling by Accept-Encoding, and answers 406 Not Acceptable when no
+//! variant is acceptable:
+//!
+//! ```sh
+//! cargo run --example hyper_server --features http -- public
+//! curl -H 'Accept-Encoding: br, gzip;q=0.5' http://127.0.0.1:3000/app.js
+//! ```
+
+use std::{convert::Infallible, net::SocketAddr, path::PathBuf, sync::Arc};
+
+use accept_encoding::SupportedEncodings;
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{CONTENT_ENCODING, VARY},
+    server::conn::http1,
+    service::service_fn,
+    Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+
+/// The codings files can be stored in, in order of preference, and the
+/// suffix of each variant.
+const VARIANTS: [(&str, &str); 3] = [("br", ".br"), ("gzip", ".gz"), ("identity", "")];
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let root = Arc::new(PathBuf::from(
+        std::env::args().nth(1).unwrap_or_else(|| ".".into()),
+    ));
+    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
+    let listener = TcpListener::bind(addr).await?;
+    println!("serving {} on http://{addr}", root.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let root = root.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| serve(root.clone(), request));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("connection error: {e}");
+            }
+        });
+    }
+}
+
+async fn serve(
+    root: Arc<PathBuf>,
+    request: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_start_matches('/');
+    if path.is_empty() || path.split('/').any(|segment| segment == "..") {
+        return Ok(status(StatusCode::NOT_FOUND));
+    }
+    let base = root.join(path);
+    let mut available = Vec::new();
+    for (coding, suffix) in VARIANTS {
+        let mut file = base.clone().into_os_string();
+        file.push(suffix);
+        if tokio::fs::metadata(&file).await.is_ok_and(|m| m.is_file()) {
+            available.push((coding, file));
+        }
+    }
+    if available.is_empty() {
+        return Ok(status(StatusCode::NOT_FOUND));
+    }
+
+    let supported = SupportedEncodings::new(available.iter().map(|(coding, _)| coding))
+        .expect("the variant codings are tokens");
+    let Some(coding) = supported.negotiate_from_headers(&request) else {
+        let mut response = status(StatusCode::NOT_ACCEPTABLE);
+        response
+            .headers_mut()
+            .insert(VARY, "Accept-Encoding".parse().unwrap());
+        return Ok(response);
+    };
+    let (coding, file) = available
+        .into_iter()
+        .find(|(c, _)| c.as_bytes() == coding)
+        .expect("negotiated from the available codings");
+    let Ok(body) = tokio::fs::read(&file).await else {
+        return Ok(status(StatusCode::INTERNAL_SERVER_ERROR));
+    };
+
+    let mut response = Response::builder().header(VARY, "Accept-Encoding");
+    if coding != "identity" {
+        response = response.header(CONTENT_ENCODING, coding);
+    }
+    Ok(response.body(Full::new(Bytes::from(body))).unwrap())
+}
+
+fn status(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(
+        status.canonical_reason().unwrap_or_default(),
+    )));
+    *response.status_mut() = status;
+    response
+}
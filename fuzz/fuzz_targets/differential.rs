@@ -0,0 +1,51 @@
+#![no_main]
+
+//! Feeds the same input to every implementation of Accept-Encoding matching
+//! and checks that they agree with `match_for_encoding_with_options`.
+
+use accept_encoding::{
+    match_for_encoding_const, match_for_encoding_with_options, EncodingBatchMatcher,
+    EncodingBatchResults, Feed, MatchOptions, StreamingEncodingMatcher,
+};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, arbitrary::Arbitrary)]
+struct Input<'a> {
+    header_value: &'a [u8],
+    encoding: &'a [u8],
+    options: MatchOptions,
+    /// Lengths of the chunks given to the streaming matcher.
+    chunk_lens: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+    let want = match_for_encoding_with_options(input.header_value, input.encoding, &input.options);
+
+    if input.options == MatchOptions::default() {
+        assert_eq!(
+            want,
+            match_for_encoding_const(input.header_value, input.encoding)
+        );
+    }
+
+    let mut results = EncodingBatchResults::default();
+    EncodingBatchMatcher::new(input.encoding, input.options)
+        .match_into([input.header_value], &mut results);
+    assert_eq!(want, results.get(0));
+
+    let mut matcher = StreamingEncodingMatcher::new(input.encoding, input.options);
+    let mut rest = input.header_value;
+    let mut lens = input.chunk_lens.iter().map(|&len| len as usize);
+    let streamed = loop {
+        let len = lens.next().unwrap_or(rest.len()).min(rest.len());
+        let (chunk, tail) = rest.split_at(len);
+        rest = tail;
+        if let Feed::Done(result) = matcher.feed(chunk) {
+            break result;
+        }
+        if rest.is_empty() {
+            break matcher.finish();
+        }
+    };
+    assert_eq!(want, streamed);
+});
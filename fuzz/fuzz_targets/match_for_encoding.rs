@@ -0,0 +1,29 @@
+#![no_main]
+
+use accept_encoding::{explain_encoding, match_for_encoding_with_options, MatchOptions};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, arbitrary::Arbitrary)]
+struct Input<'a> {
+    header_value: &'a [u8],
+    encoding: &'a [u8],
+    options: MatchOptions,
+}
+
+fuzz_target!(|input: Input| {
+    let result =
+        match_for_encoding_with_options(input.header_value, input.encoding, &input.options);
+    let explanation = explain_encoding(input.header_value, input.encoding, &input.options);
+    assert_eq!(result, explanation.result);
+
+    // Entries are reported in order, without overlapping, inside the input.
+    let mut end = 0;
+    for entry in &explanation.entries {
+        assert!(entry.offset >= end);
+        end = entry.offset + entry.text.len();
+        assert!(end <= input.header_value.len());
+    }
+    if let Some(offset) = explanation.error_offset {
+        assert!(offset <= input.header_value.len());
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use accept_encoding::QValue;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let strict = QValue::try_from(data);
+    let truncating = QValue::try_from_bytes_truncating(data);
+    if let Ok(q) = strict {
+        assert_eq!(Ok(q), truncating);
+    }
+    if let Ok(q) = truncating {
+        assert!(q <= QValue::ONE);
+        // Three fraction digits always parse back to the same value.
+        let millis = q.as_millis();
+        let text = format!("{}.{:03}", millis / 1000, millis % 1000);
+        assert_eq!(Ok(q), QValue::try_from(text.as_str()));
+    }
+});
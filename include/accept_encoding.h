@@ -0,0 +1,609 @@
+#ifndef ACCEPT_ENCODING_H
+#define ACCEPT_ENCODING_H
+
+/* Generated by cbindgen from src/c.rs. Do not edit; build with --features cbindgen to update. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The version of the C functions and structs, raised on every incompatible
+ * change to them. Compare it with `c_abi_version()` when loading the
+ * library at run time.
+ */
+#define ACCEPT_ENCODING_ABI_VERSION 1
+
+/**
+ * Values of `COptions::registry_check`, for how tokens are checked against
+ * the IANA registry.
+ */
+#define C_REGISTRY_CHECK_OFF 0
+
+/**
+ * Report tokens missing from the registry as warnings.
+ */
+#define C_REGISTRY_CHECK_WARN 1
+
+/**
+ * Treat tokens missing from the registry as malformed.
+ */
+#define C_REGISTRY_CHECK_REJECT 2
+
+/**
+ * Values of `COptions::tie_break`, for which entry applies when several
+ * match equally specifically. The highest q-value wins, then the
+ * earliest.
+ */
+#define C_TIE_BREAK_HIGHEST_Q 0
+
+#define C_TIE_BREAK_FIRST_WINS 1
+
+/**
+ * The latest wins, as if each entry overrode the earlier ones.
+ */
+#define C_TIE_BREAK_LAST_WINS 2
+
+/**
+ * Returned by the `c_cmp_` functions when a match type is out of range.
+ */
+#define C_CMP_INVALID -2
+
+/**
+ * Returned by `c_negotiate_encoding` when no supported coding is acceptable.
+ */
+#define C_NEGOTIATE_NO_MATCH -1
+
+/**
+ * Returned by `c_negotiate_encoding` when a pointer argument is null or
+ * matching panicked.
+ */
+#define C_NEGOTIATE_ERROR -2
+
+/**
+ * Returned by `c_vmod_negotiate_encoding` when the chosen coding does not
+ * fit in the buffer.
+ */
+#define C_NEGOTIATE_BUFFER_TOO_SMALL -3
+
+/**
+ * Returned by `c_remove_encoding` and `c_add_encoding` when the result
+ * does not fit in `out_cap` bytes. `*out_len` is then the size needed.
+ */
+#define C_REWRITE_BUFFER_TOO_SMALL -1
+
+/**
+ * Returned by `c_remove_encoding` and `c_add_encoding` when a pointer
+ * argument is null, the header value is malformed or rewriting panicked.
+ */
+#define C_REWRITE_ERROR -2
+
+/**
+ * What went wrong in a C function that takes an `out_error` argument.
+ */
+typedef enum {
+  C_PARSE_ERROR_CODE_NONE = 0,
+  /**
+   * A pointer argument is null or a length is too large.
+   */
+  C_PARSE_ERROR_CODE_INVALID_ARGUMENT = 1,
+  /**
+   * The header value is malformed at `offset`.
+   */
+  C_PARSE_ERROR_CODE_MALFORMED_HEADER = 2,
+  /**
+   * Matching panicked.
+   */
+  C_PARSE_ERROR_CODE_PANIC = 3,
+} CParseErrorCode;
+
+/**
+ * How `c_match_encoding` matched, ranked by value.
+ */
+typedef enum {
+  /**
+   * Returned when a pointer argument is null or matching panicked.
+   */
+  C_ENCODING_MATCH_TYPE_ERROR = -1,
+  C_ENCODING_MATCH_TYPE_NO_MATCH = 0,
+  C_ENCODING_MATCH_TYPE_WILDCARD = 1,
+  C_ENCODING_MATCH_TYPE_EXACT = 2,
+} CEncodingMatchType;
+
+/**
+ * How `c_match_mime_type` matched, ranked by value.
+ */
+typedef enum {
+  /**
+   * Returned when a pointer argument is null or matching panicked.
+   */
+  C_MIME_TYPE_MATCH_TYPE_ERROR = -1,
+  C_MIME_TYPE_MATCH_TYPE_NO_MATCH = 0,
+  C_MIME_TYPE_MATCH_TYPE_MAIN_TYPE_WILDCARD = 1,
+  C_MIME_TYPE_MATCH_TYPE_SUB_TYPE_WILDCARD = 2,
+  C_MIME_TYPE_MATCH_TYPE_EXACT = 3,
+} CMimeTypeMatchType;
+
+/**
+ * How `c_match_language` matched, ranked by value.
+ */
+typedef enum {
+  /**
+   * Returned when a pointer argument is null or matching panicked.
+   */
+  C_LANGUAGE_MATCH_TYPE_ERROR = -1,
+  C_LANGUAGE_MATCH_TYPE_NO_MATCH = 0,
+  C_LANGUAGE_MATCH_TYPE_WILDCARD = 1,
+  /**
+   * The range is a prefix of the tag, like "en" for "en-US".
+   */
+  C_LANGUAGE_MATCH_TYPE_PREFIX = 2,
+  C_LANGUAGE_MATCH_TYPE_EXACT = 3,
+} CLanguageMatchType;
+
+/**
+ * An Accept-Encoding value parsed by `c_accept_encoding_parse`.
+ */
+typedef struct CAcceptEncoding CAcceptEncoding;
+
+/**
+ * The result of `c_match_encoding`.
+ */
+typedef struct {
+  /**
+   * A `CEncodingMatchType` value. It is an int since `c_cmp_encoding_match`
+   * takes it back from the caller, who may pass any value.
+   */
+  int match_type;
+  /**
+   * The q-value of the matched entry, or 0 without a match.
+   */
+  double q;
+} CEncodingMatch;
+
+/**
+ * `MatchOptions`, `TieBreak` and `Limits` for the `_with_options`
+ * functions. A NULL pointer, or a zeroed struct, means the default strict
+ * parsing without limits. Functions given a `registry_check` or
+ * `tie_break` they do not know fail as they do for a null argument.
+ */
+typedef struct {
+  /**
+   * Nonzero to tolerate what browsers get away with, like
+   * `MatchOptions::lenient`.
+   */
+  int lenient;
+  /**
+   * One of the `C_REGISTRY_CHECK_` values.
+   */
+  int registry_check;
+  /**
+   * One of the `C_TIE_BREAK_` values. It does not apply to
+   * `c_negotiate_encoding_with_options`, which ranks by its own rules.
+   */
+  int tie_break;
+  /**
+   * The longest header value in bytes, or 0 for no limit. Longer values
+   * are treated as malformed.
+   */
+  size_t max_len;
+  /**
+   * The most list entries, or 0 for no limit.
+   */
+  size_t max_entries;
+} COptions;
+
+/**
+ * The result of `c_match_encoding_v2`: `CEncodingMatch` with where the
+ * matching entry is, for rewriting the header value in place.
+ */
+typedef struct {
+  /**
+   * A `CEncodingMatchType` value.
+   */
+  int match_type;
+  double q;
+  /**
+   * The index of the matching entry in the list, or -1 without a match.
+   */
+  ptrdiff_t entry_index;
+  /**
+   * The offset and length of the coding name of the matching entry in
+   * the header value, or 0 without a match.
+   */
+  size_t name_offset;
+  size_t name_len;
+} CEncodingMatchV2;
+
+/**
+ * Filled in through the `out_error` argument of C functions, which may be
+ * NULL when the details are not needed.
+ */
+typedef struct {
+  CParseErrorCode code;
+  /**
+   * Where in the header value parsing failed, or 0 for other codes.
+   */
+  size_t offset;
+} CParseError;
+
+/**
+ * An entry of a parsed Accept-Encoding value, filled in by
+ * `c_accept_encoding_entry_at`.
+ */
+typedef struct {
+  /**
+   * The coding name as written, not NUL-terminated. It points into the
+   * parsed value and is valid until that is freed.
+   */
+  const char *name;
+  size_t name_len;
+  /**
+   * The q-value in thousandths, from 0 to 1000.
+   */
+  uint16_t q_millis;
+  double q;
+  /**
+   * The number of parameters other than q.
+   */
+  size_t param_count;
+} CAcceptEncodingEntry;
+
+/**
+ * The result of `c_match_mime_type`.
+ */
+typedef struct {
+  /**
+   * A `CMimeTypeMatchType` value. It is an int since `c_cmp_mime_type_match`
+   * takes it back from the caller, who may pass any value.
+   */
+  int match_type;
+  /**
+   * The q-value of the matched entry, or 0 without a match.
+   */
+  double q;
+} CMimeTypeMatch;
+
+/**
+ * The result of `c_match_mime_type_v2`: `CMimeTypeMatch` with where the
+ * matching entry is, for rewriting the header value in place.
+ */
+typedef struct {
+  /**
+   * A `CMimeTypeMatchType` value.
+   */
+  int match_type;
+  double q;
+  /**
+   * The index of the matching entry in the list, or -1 without a match.
+   */
+  ptrdiff_t entry_index;
+  /**
+   * The offset and length of the media range of the matching entry,
+   * like "image/*", in the header value, or 0 without a match.
+   */
+  size_t name_offset;
+  size_t name_len;
+} CMimeTypeMatchV2;
+
+/**
+ * The result of `c_match_language`.
+ */
+typedef struct {
+  /**
+   * A `CLanguageMatchType` value, an int like the match types of the
+   * other results.
+   */
+  int match_type;
+  /**
+   * The q-value of the matched entry, or 0 without a match.
+   */
+  double q;
+} CLanguageMatch;
+
+/**
+ * A string laid out like nginx's `ngx_str_t`, so a module can pass its
+ * arrays of them, like a list of supported codings, with a cast.
+ */
+typedef struct {
+  size_t len;
+  const uint8_t *data;
+} CNgxStr;
+
+/**
+ * Returns `ACCEPT_ENCODING_ABI_VERSION` as built into the library.
+ */
+uint32_t c_abi_version(void);
+
+/**
+ * Returns the crate version, like "0.2.0", as a static NUL-terminated
+ * string.
+ */
+const char *c_version(void);
+
+/**
+ * Returns 1 if a panic inside the library cannot unwind into the caller:
+ * either the library was built with `panic = "abort"`, or a panic is
+ * caught at the C boundary, which this checks by raising one. A build
+ * where unwinding is broken aborts here instead of returning.
+ */
+int c_is_panic_safe(void);
+
+/**
+ * Parses a q-value like "0.5" as it appears after "q=" and stores it in
+ * thousandths in `*out_millis`. Returns 1 on success, or 0 if the value is
+ * invalid or a pointer is NULL.
+ */
+int c_qvalue_from_string(const char *value, size_t value_len, uint16_t *out_millis);
+
+/**
+ * Converts a q-value in thousandths to a double, the way the `q` fields of
+ * the match results are filled in. Returns NaN for more than 1000.
+ */
+double c_qvalue_to_double(uint16_t millis);
+
+/**
+ * Matches an Accept-Encoding header value against a content coding.
+ */
+CEncodingMatch c_match_encoding(const char *header_value,
+                                size_t header_value_len,
+                                const char *encoding,
+                                size_t encoding_len);
+
+/**
+ * Like `c_match_encoding`, with options; NULL `options` means the defaults.
+ */
+CEncodingMatch c_match_encoding_with_options(const char *header_value,
+                                             size_t header_value_len,
+                                             const char *encoding,
+                                             size_t encoding_len,
+                                             const COptions *options);
+
+/**
+ * Like `c_match_encoding`, but takes NUL-terminated strings.
+ */
+CEncodingMatch c_match_encoding_cstr(const char *header_value, const char *encoding);
+
+/**
+ * Like `c_match_encoding`, but tolerates what browsers get away with, as
+ * `COptions` with `lenient` set does.
+ */
+CEncodingMatch c_match_encoding_lenient(const char *header_value,
+                                        size_t header_value_len,
+                                        const char *encoding,
+                                        size_t encoding_len);
+
+/**
+ * Like `c_match_encoding_with_options`, but also tells which entry
+ * matched, and where the header value is malformed through `out_error`.
+ */
+CEncodingMatchV2 c_match_encoding_v2(const char *header_value,
+                                     size_t header_value_len,
+                                     const char *encoding,
+                                     size_t encoding_len,
+                                     const COptions *options,
+                                     CParseError *out_error);
+
+/**
+ * Returns -1, 0 or 1 as `m1` ranks below, equal to or above `m2`, or
+ * C_CMP_INVALID if a match type is not a `CEncodingMatchType` value.
+ */
+int c_cmp_encoding_match(CEncodingMatch m1, CEncodingMatch m2);
+
+/**
+ * Returns the quality the header value gives `encoding`, as
+ * `effective_q` does, in thousandths: the q-value of the entry that
+ * applies, or 1000 for "identity" when nothing excludes it. Returns -1 if
+ * `encoding` is not acceptable or the header value is malformed, and -2 if
+ * a pointer argument is null or matching panicked.
+ */
+int c_effective_encoding_q(const char *header_value,
+                           size_t header_value_len,
+                           const char *encoding,
+                           size_t encoding_len);
+
+/**
+ * Picks the coding to respond with from the `n` codings the server
+ * supports, in its order of preference, and returns its index. The rules
+ * are those of `SupportedEncodings::best_match`: the highest q-value wins,
+ * then a coding the header names over one covered by "*", then the
+ * earlier one.
+ */
+ptrdiff_t c_negotiate_encoding(const char *header_value,
+                               size_t header_value_len,
+                               const char *const *supported,
+                               const size_t *supported_lens,
+                               size_t n);
+
+/**
+ * Like `c_negotiate_encoding`, with options; NULL `options` means the
+ * defaults.
+ */
+ptrdiff_t c_negotiate_encoding_with_options(const char *header_value,
+                                            size_t header_value_len,
+                                            const char *const *supported,
+                                            const size_t *supported_lens,
+                                            size_t n,
+                                            const COptions *options);
+
+/**
+ * Checks an Accept-Encoding value against the strict grammar. Returns 0
+ * if it is well-formed, or the `CParseErrorCode` stored in `out_error`,
+ * with the offset where parsing failed for a malformed value.
+ */
+int c_validate_accept_encoding(const char *header_value,
+                               size_t header_value_len,
+                               CParseError *out_error);
+
+/**
+ * Parses an Accept-Encoding value once for use with
+ * `c_accept_encoding_match`. Returns NULL if the value is malformed or a
+ * pointer is null. The result must be released with
+ * `c_accept_encoding_free`.
+ */
+CAcceptEncoding *c_accept_encoding_parse(const char *header_value, size_t header_value_len);
+
+/**
+ * Like `c_accept_encoding_parse`, with options; NULL `options` means the
+ * defaults.
+ */
+CAcceptEncoding *c_accept_encoding_parse_with_options(const char *header_value,
+                                                      size_t header_value_len,
+                                                      const COptions *options);
+
+/**
+ * Like `c_match_encoding` on the value `parsed` was created from.
+ */
+CEncodingMatch c_accept_encoding_match(const CAcceptEncoding *parsed,
+                                       const char *encoding,
+                                       size_t encoding_len);
+
+/**
+ * Returns the number of entries in `parsed`, or 0 if it is NULL.
+ */
+size_t c_accept_encoding_entry_count(const CAcceptEncoding *parsed);
+
+/**
+ * Fills in `out_entry` with the entry at `index` and returns 1, or returns
+ * 0 if `index` is out of range or a pointer is NULL.
+ */
+int c_accept_encoding_entry_at(const CAcceptEncoding *parsed,
+                               size_t index,
+                               CAcceptEncodingEntry *out_entry);
+
+/**
+ * Releases a value returned by `c_accept_encoding_parse`. NULL is ignored.
+ */
+void c_accept_encoding_free(CAcceptEncoding *parsed);
+
+/**
+ * Writes the Accept-Encoding value rewritten by `remove_encoding` to no
+ * longer accept `encoding` to `out_buf`, not NUL-terminated, and its
+ * length to `*out_len`. Returns 0 on success. With `out_cap` 0, `out_buf`
+ * may be NULL to get the size.
+ */
+int c_remove_encoding(const char *header_value,
+                      size_t header_value_len,
+                      const char *encoding,
+                      size_t encoding_len,
+                      char *out_buf,
+                      size_t out_cap,
+                      size_t *out_len);
+
+/**
+ * Like `c_remove_encoding`, but appends `encoding` unless an entry already
+ * names it.
+ */
+int c_add_encoding(const char *header_value,
+                   size_t header_value_len,
+                   const char *encoding,
+                   size_t encoding_len,
+                   char *out_buf,
+                   size_t out_cap,
+                   size_t *out_len);
+
+/**
+ * Matches an Accept header value against a media type like "image/webp".
+ */
+CMimeTypeMatch c_match_mime_type(const char *header_value,
+                                 size_t header_value_len,
+                                 const char *mime_type,
+                                 size_t mime_type_len);
+
+/**
+ * Like `c_match_mime_type`, with options; NULL `options` means the
+ * defaults.
+ */
+CMimeTypeMatch c_match_mime_type_with_options(const char *header_value,
+                                              size_t header_value_len,
+                                              const char *mime_type,
+                                              size_t mime_type_len,
+                                              const COptions *options);
+
+/**
+ * Like `c_match_mime_type`, but takes NUL-terminated strings.
+ */
+CMimeTypeMatch c_match_mime_type_cstr(const char *header_value, const char *mime_type);
+
+/**
+ * Like `c_match_mime_type`, but tolerates what browsers get away with, as
+ * `COptions` with `lenient` set does.
+ */
+CMimeTypeMatch c_match_mime_type_lenient(const char *header_value,
+                                         size_t header_value_len,
+                                         const char *mime_type,
+                                         size_t mime_type_len);
+
+/**
+ * Like `c_match_mime_type_with_options`, but also tells which entry
+ * matched, and where the header value is malformed through `out_error`.
+ */
+CMimeTypeMatchV2 c_match_mime_type_v2(const char *header_value,
+                                      size_t header_value_len,
+                                      const char *mime_type,
+                                      size_t mime_type_len,
+                                      const COptions *options,
+                                      CParseError *out_error);
+
+/**
+ * Returns -1, 0 or 1 as `m1` ranks below, equal to or above `m2`, or
+ * C_CMP_INVALID if a match type is not a `CMimeTypeMatchType` value.
+ */
+int c_cmp_mime_type_match(CMimeTypeMatch m1, CMimeTypeMatch m2);
+
+/**
+ * Matches an Accept-Language header value against a language tag like
+ * "en-US".
+ */
+CLanguageMatch c_match_language(const char *header_value,
+                                size_t header_value_len,
+                                const char *language,
+                                size_t language_len);
+
+/**
+ * Like `c_match_encoding`, but takes the `data` and `len` of `ngx_str_t`
+ * values and stores the q-value in thousandths in `*out_q_millis`, 0
+ * without a match. Nothing is allocated.
+ */
+CEncodingMatchType c_ngx_match_encoding(const uint8_t *header_value,
+                                        size_t header_value_len,
+                                        const uint8_t *encoding,
+                                        size_t encoding_len,
+                                        uint16_t *out_q_millis);
+
+/**
+ * Like `c_negotiate_encoding`, but takes the `data` and `len` of an
+ * `ngx_str_t` header value and `n` supported codings as `ngx_str_t`
+ * values, and stores the q-value of the chosen coding in thousandths in
+ * `*out_q_millis`, 0 without a match. Nothing is allocated.
+ */
+ptrdiff_t c_ngx_negotiate_encoding(const uint8_t *header_value,
+                                   size_t header_value_len,
+                                   const CNgxStr *supported,
+                                   size_t n,
+                                   uint16_t *out_q_millis);
+
+/**
+ * Like `c_negotiate_encoding`, but shaped for a Varnish VMOD: it takes
+ * NUL-terminated strings, with NULL `header_value` for an unset header,
+ * which accepts nothing, and copies the chosen coding with a NUL into
+ * `out_buf`, such as workspace reserved by the VMOD. `out_buf` may be NULL
+ * to get the index alone.
+ */
+ptrdiff_t c_vmod_negotiate_encoding(const char *header_value,
+                                    const char *const *supported,
+                                    size_t n,
+                                    char *out_buf,
+                                    size_t out_cap);
+
+/**
+ * Like `c_match_mime_type`, but takes the `data` and `len` of `ngx_str_t`
+ * values and stores the q-value in thousandths in `*out_q_millis`, 0
+ * without a match. Nothing is allocated.
+ */
+CMimeTypeMatchType c_ngx_match_mime_type(const uint8_t *header_value,
+                                         size_t header_value_len,
+                                         const uint8_t *mime_type,
+                                         size_t mime_type_len,
+                                         uint16_t *out_q_millis);
+
+#endif  /* ACCEPT_ENCODING_H */
@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use crate::{
+    explain::{finish_entry, EntryVerdict, Observer},
+    lexer::{self, Cursor, ParseError},
+    mime_type_matcher::{
+        get_mime_type_match_type, match_mime_type, split_mime_type, MimeTypeMatch,
+    },
+    options::MatchOptions,
+    q_value::QValue,
+    weighted_list::WeightedList,
+};
+
+/// An Accept value parsed once, for checking several media types against
+/// it without parsing it again.
+///
+/// Matching gives the same results as `match_for_mime_type_with_options`
+/// with the options used to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accept {
+    value: Box<[u8]>,
+    entries: WeightedList<Entry>,
+}
+
+/// Where an entry's media range and its slash are in `value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    start: usize,
+    slash: usize,
+    end: usize,
+}
+
+impl Accept {
+    /// Returns None if `value` is malformed.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        Self::parse_with_options(value, &MatchOptions::default())
+    }
+
+    /// Returns None if `value` is malformed. Lenient options drop malformed
+    /// entries instead.
+    pub fn parse_with_options(value: &[u8], options: &MatchOptions) -> Option<Self> {
+        let mut collector = EntryCollector {
+            input: value,
+            entries: WeightedList::new(),
+        };
+        match_mime_type(value, None, options, &mut Cursor(0), &mut collector).ok()?;
+        Some(Self {
+            value: value.into(),
+            entries: collector.entries,
+        })
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the media ranges as written, like `text/*`, and their
+    /// q-values, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], QValue)> {
+        self.entries
+            .iter()
+            .map(|(e, q)| (&self.value[e.start..e.end], q))
+    }
+
+    pub fn match_mime_type(&self, mime_type: &[u8]) -> Option<MimeTypeMatch> {
+        let (want_main_type, want_subtype) = split_mime_type(mime_type)?;
+        let mut best = None;
+        for (e, q) in self.entries.iter() {
+            let mut cur = get_mime_type_match_type(
+                &self.value[e.start..e.slash],
+                &self.value[e.slash + 1..e.end],
+                want_main_type,
+                want_subtype,
+            )
+            .map(|match_type| MimeTypeMatch { match_type, q });
+            finish_entry(&mut cur, &mut best, 0, 0, &mut ());
+        }
+        best
+    }
+}
+
+impl FromStr for Accept {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Self::parse(s.as_bytes()).ok_or(ParseError)
+    }
+}
+
+/// Records the media range and q-value of each entry the parser finishes.
+struct EntryCollector<'a> {
+    input: &'a [u8],
+    entries: WeightedList<Entry>,
+}
+
+impl Observer<MimeTypeMatch> for EntryCollector<'_> {
+    fn entry(&mut self, start: usize, end: usize, matched: Option<MimeTypeMatch>, _: EntryVerdict) {
+        let input = &self.input[..end];
+        let mut c = Cursor(start);
+        // The parser has already read these tokens.
+        let _ = lexer::token(input, &mut c);
+        let slash = c.0;
+        c.advance(1);
+        let _ = lexer::token(input, &mut c);
+        let entry = Entry {
+            start,
+            slash,
+            end: c.0,
+        };
+        self.entries
+            .push(entry, matched.map_or(QValue::ONE, |m| m.q));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mime_type_matcher::match_for_mime_type;
+
+    #[test]
+    fn test_accept() {
+        let value = b"text/html, application/xhtml+xml, application/xml;q=0.9, image/*;level=1;q=0.8, */*;q=0.1";
+        let accept = Accept::parse(value).unwrap();
+        assert_eq!(5, accept.len());
+        assert_eq!(
+            Ok(&accept),
+            std::str::from_utf8(value)
+                .unwrap()
+                .parse::<Accept>()
+                .as_ref()
+        );
+        assert_eq!(
+            Some((&b"image/*"[..], QValue::from_millis(800).unwrap())),
+            accept.iter().nth(3)
+        );
+        for mime_type in [
+            &b"text/html"[..],
+            b"TEXT/HTML",
+            b"application/xml",
+            b"image/webp",
+            b"video/mp4",
+            b"invalid",
+        ] {
+            assert_eq!(
+                match_for_mime_type(value, mime_type),
+                accept.match_mime_type(mime_type),
+                "{:?}",
+                mime_type
+            );
+        }
+        assert_eq!(None, Accept::parse(b"text/html text/plain"));
+        assert!(Accept::parse(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_accept_lenient() {
+        let options = MatchOptions {
+            lenient: true,
+            ..MatchOptions::default()
+        };
+        let accept =
+            Accept::parse_with_options(b"text/html, bogus, image/png;q=0.5", &options).unwrap();
+        assert_eq!(2, accept.len());
+        assert_eq!(
+            500,
+            accept.match_mime_type(b"image/png").unwrap().q.as_millis()
+        );
+    }
+}
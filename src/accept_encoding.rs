@@ -0,0 +1,314 @@
+use std::{ops::Range, str::FromStr};
+
+use crate::{
+    byte_slice::bytes_eq_ignore_case,
+    encoding_matcher::{match_target, EncodingMatch, EncodingMatchType, Target},
+    explain::{finish_entry, EntryVerdict, Observer},
+    lexer::{self, Cursor, ParseError},
+    options::MatchOptions,
+    q_value::QValue,
+    weighted_list::WeightedList,
+};
+
+/// An Accept-Encoding value parsed once, for checking several codings
+/// against it without parsing it again.
+///
+/// Matching gives the same results as `match_for_encoding_with_options`
+/// with the options used to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptEncoding {
+    value: Box<[u8]>,
+    entries: WeightedList<Entry>,
+}
+
+/// Where an entry's coding name is in `value`, and how many parameters
+/// other than q follow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    start: usize,
+    end: usize,
+    params: usize,
+}
+
+impl AcceptEncoding {
+    /// Returns None if `value` is malformed.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        Self::parse_with_options(value, &MatchOptions::default())
+    }
+
+    /// Returns None if `value` is malformed. Lenient options drop malformed
+    /// entries instead.
+    pub fn parse_with_options(value: &[u8], options: &MatchOptions) -> Option<Self> {
+        let mut collector = EntryCollector {
+            input: value,
+            entries: WeightedList::new(),
+        };
+        match_target(
+            value,
+            Target::any(),
+            options,
+            &mut Cursor(0),
+            &mut collector,
+        )
+        .ok()?;
+        Some(Self {
+            value: value.into(),
+            entries: collector.entries,
+        })
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the coding names as written and their q-values, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], QValue)> {
+        self.entries
+            .iter()
+            .map(|(e, q)| (&self.value[e.start..e.end], q))
+    }
+
+    /// Returns where each coding name is in the parsed value, with its
+    /// q-value, for borrowing the names from the caller's copy.
+    pub(crate) fn spans(&self) -> impl Iterator<Item = (Range<usize>, QValue)> + '_ {
+        self.entries.iter().map(|(e, q)| (e.start..e.end, q))
+    }
+
+    /// Returns the coding name, q-value and number of parameters other
+    /// than q of the `i`th entry.
+    pub fn entry(&self, i: usize) -> Option<(&[u8], QValue, usize)> {
+        let (e, q) = self.entries.get(i)?;
+        Some((&self.value[e.start..e.end], q, e.params))
+    }
+
+    pub fn match_encoding(&self, encoding: &[u8]) -> Option<EncodingMatch> {
+        let target = Target::new(encoding);
+        let mut best = None;
+        for (name, q) in self.iter() {
+            let match_type = if target.matches(name) {
+                EncodingMatchType::Exact
+            } else if name == b"*" {
+                EncodingMatchType::Wildcard
+            } else {
+                continue;
+            };
+            let mut cur = Some(EncodingMatch { match_type, q });
+            finish_entry(&mut cur, &mut best, 0, 0, &mut ());
+        }
+        best
+    }
+}
+
+impl FromStr for AcceptEncoding {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Self::parse(s.as_bytes()).ok_or(ParseError)
+    }
+}
+
+/// Decodes every Accept-Encoding field, joined with ", ", and encodes the
+/// value as parsed.
+#[cfg(feature = "headers")]
+impl headers::Header for AcceptEncoding {
+    fn name() -> &'static http::HeaderName {
+        &http::header::ACCEPT_ENCODING
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    where
+        I: Iterator<Item = &'i http::HeaderValue>,
+    {
+        let mut joined = Vec::new();
+        for value in values {
+            if !joined.is_empty() {
+                joined.extend_from_slice(b", ");
+            }
+            joined.extend_from_slice(value.as_bytes());
+        }
+        Self::parse(&joined).ok_or_else(headers::Error::invalid)
+    }
+
+    fn encode<E: Extend<http::HeaderValue>>(&self, values: &mut E) {
+        // A lenient parse keeps the malformed entries it skipped, which may
+        // hold bytes a header value cannot, so rebuild the value from the
+        // entries then.
+        let value = http::HeaderValue::from_bytes(&self.value).unwrap_or_else(|_| {
+            let value = self.iter().collect::<WeightedList<_>>().to_header_value();
+            http::HeaderValue::from_bytes(&value).expect("tokens and q-values are valid")
+        });
+        values.extend(std::iter::once(value));
+    }
+}
+
+/// Records the name and q-value of each entry the parser finishes.
+struct EntryCollector<'a> {
+    input: &'a [u8],
+    entries: WeightedList<Entry>,
+}
+
+impl Observer<EncodingMatch> for EntryCollector<'_> {
+    fn entry(&mut self, start: usize, end: usize, matched: Option<EncodingMatch>, _: EntryVerdict) {
+        let input = &self.input[..end];
+        let mut c = Cursor(start);
+        // The parser has already read this token.
+        let _ = lexer::token(input, &mut c);
+        let name_end = c.0;
+        let entry = Entry {
+            start,
+            end: name_end,
+            params: count_params(input, &mut c),
+        };
+        self.entries
+            .push(entry, matched.map_or(QValue::ONE, |m| m.q));
+    }
+}
+
+/// Counts the parameters other than q from `c` on, in an entry the parser
+/// has already accepted.
+fn count_params(input: &[u8], c: &mut Cursor) -> usize {
+    let mut n = 0;
+    loop {
+        lexer::ows(input, c);
+        if lexer::byte(b';')(input, c).is_err() {
+            return n;
+        }
+        lexer::ows(input, c);
+        let name_start = c.0;
+        if lexer::token(input, c).is_err() {
+            return n;
+        }
+        if !bytes_eq_ignore_case(&input[name_start..c.0], b"q") {
+            n += 1;
+        }
+        if lexer::byte(b'=')(input, c).is_ok() && lexer::token(input, c).is_err() {
+            let _ = lexer::quoted_string(input, c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encoding_matcher::match_for_encoding_with_options, test_util::for_each_input};
+
+    #[test]
+    fn test_accept_encoding_parse() {
+        let parsed = AcceptEncoding::parse(b"gzip;q=0.5, BR, *;foo=\"a, b\";q=0").unwrap();
+        assert_eq!(
+            vec![
+                (&b"gzip"[..], QValue::from_millis(500).unwrap()),
+                (b"BR", QValue::ONE),
+                (b"*", QValue::ZERO),
+            ],
+            parsed.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::ONE,
+            }),
+            parsed.match_encoding(b"br")
+        );
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Wildcard,
+                q: QValue::ZERO,
+            }),
+            parsed.match_encoding(b"zstd")
+        );
+
+        assert_eq!(Some((&b"*"[..], QValue::ZERO, 1)), parsed.entry(2));
+        assert_eq!(
+            Ok(&parsed),
+            "gzip;q=0.5, BR, *;foo=\"a, b\";q=0"
+                .parse::<AcceptEncoding>()
+                .as_ref()
+        );
+        assert_eq!(Err(ParseError), "gzip;q=2".parse::<AcceptEncoding>());
+        assert_eq!(
+            Some((&b"gzip"[..], QValue::from_millis(500).unwrap(), 0)),
+            parsed.entry(0)
+        );
+        assert_eq!(None, parsed.entry(3));
+
+        assert_eq!(None, AcceptEncoding::parse(b"gzip;q=2"));
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let parsed = AcceptEncoding::parse_with_options(b"gzip;q=2, br", &lenient).unwrap();
+        assert_eq!(1, parsed.len());
+    }
+
+    #[test]
+    fn test_accept_encoding_matches_match_for_encoding() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        for_each_input(b"gzip*;,=q01. \t\"", 0..=4, |input| {
+            for options in [MatchOptions::default(), lenient] {
+                let parsed = AcceptEncoding::parse_with_options(input, &options);
+                for encoding in [&b"gzip"[..], b"x-gzip", b"i"] {
+                    assert_eq!(
+                        match_for_encoding_with_options(input, encoding, &options),
+                        parsed.as_ref().and_then(|p| p.match_encoding(encoding)),
+                        "input={:?}, encoding={:?}, options={options:?}",
+                        String::from_utf8_lossy(input),
+                        String::from_utf8_lossy(encoding)
+                    );
+                }
+            }
+        });
+    }
+
+    #[cfg(feature = "headers")]
+    #[test]
+    fn test_accept_encoding_header() {
+        use headers::{Header, HeaderMapExt};
+
+        let mut headers = http::HeaderMap::new();
+        headers.append(
+            http::header::ACCEPT_ENCODING,
+            http::HeaderValue::from_static("gzip;q=0.5"),
+        );
+        headers.append(
+            http::header::ACCEPT_ENCODING,
+            http::HeaderValue::from_static("br"),
+        );
+        let parsed = headers.typed_get::<AcceptEncoding>().unwrap();
+        assert_eq!(2, parsed.len());
+        assert_eq!(Some((&b"br"[..], QValue::ONE, 0)), parsed.entry(1));
+
+        let mut encoded = Vec::new();
+        parsed.encode(&mut encoded);
+        assert_eq!(
+            vec![http::HeaderValue::from_static("gzip;q=0.5, br")],
+            encoded
+        );
+
+        let lenient = MatchOptions {
+            lenient: true,
+            ..MatchOptions::default()
+        };
+        let parsed = AcceptEncoding::parse_with_options(b"gzip;q=0.5, \x01, br", &lenient).unwrap();
+        let mut encoded = Vec::new();
+        parsed.encode(&mut encoded);
+        assert_eq!(
+            vec![http::HeaderValue::from_static("gzip;q=0.5, br")],
+            encoded
+        );
+
+        headers.insert(
+            http::header::ACCEPT_ENCODING,
+            http::HeaderValue::from_static("gzip;q=2"),
+        );
+        assert!(headers.typed_try_get::<AcceptEncoding>().is_err());
+    }
+}
@@ -0,0 +1,76 @@
+//! The client side of Accept-Post and Accept-Patch (RFC 5789), which list
+//! the media types a server takes in request bodies.
+
+use crate::{mime_type_matcher::match_for_mime_type, q_value::QValue};
+
+/// Picks the media type to send a body in from those the client can
+/// produce, each with its preference, given the server's Accept-Post or
+/// Accept-Patch value. The client's q-value times any the server gives
+/// wins, then the media type the server lists most specifically, then the
+/// earlier one. Returns None if the server takes none of them or the value
+/// is malformed.
+pub fn choose_request_media_type<'a>(
+    accept_post: &[u8],
+    producible: &[(&'a [u8], QValue)],
+) -> Option<&'a [u8]> {
+    let mut best = None;
+    for &(mime_type, preference) in producible {
+        let Some(m) = match_for_mime_type(accept_post, mime_type) else {
+            continue;
+        };
+        let q = preference.saturating_mul(m.q);
+        if q.is_zero() {
+            continue;
+        }
+        if best.is_none_or(|(_, b_q, b_type)| (q, m.match_type) > (b_q, b_type)) {
+            best = Some((mime_type, q, m.match_type));
+        }
+    }
+    best.map(|(mime_type, _, _)| mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(millis: u16) -> QValue {
+        QValue::from_millis(millis).unwrap()
+    }
+
+    #[test]
+    fn test_choose_request_media_type() {
+        let producible: &[(&[u8], QValue)] = &[
+            (b"application/merge-patch+json", q(1000)),
+            (b"application/json-patch+json", q(900)),
+            (b"text/plain", q(100)),
+        ];
+        let choose = |value: &[u8]| choose_request_media_type(value, producible);
+        assert_eq!(
+            Some(&b"application/json-patch+json"[..]),
+            choose(b"application/json-patch+json, text/*")
+        );
+        assert_eq!(
+            Some(&b"application/merge-patch+json"[..]),
+            choose(b"application/merge-patch+json, application/json-patch+json")
+        );
+        assert_eq!(Some(&b"text/plain"[..]), choose(b"text/plain"));
+        assert_eq!(None, choose(b"image/png"));
+        assert_eq!(None, choose(b"text/plain;q=0"));
+        assert_eq!(None, choose(b"text/plain text/html"));
+    }
+
+    #[test]
+    fn test_choose_request_media_type_tie() {
+        let producible: &[(&[u8], QValue)] =
+            &[(b"text/csv", q(1000)), (b"application/json", q(1000))];
+        assert_eq!(
+            Some(&b"application/json"[..]),
+            choose_request_media_type(b"text/*, application/json", producible)
+        );
+        assert_eq!(
+            Some(&b"text/csv"[..]),
+            choose_request_media_type(b"*/*", producible)
+        );
+        assert_eq!(None, choose_request_media_type(b"*/*", &[]));
+    }
+}
@@ -0,0 +1,154 @@
+//! actix-web integration: a guard for routes that need a coding to be
+//! acceptable, and a middleware that negotiates the response coding.
+
+use std::{
+    borrow::Cow,
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    guard::{Guard, GuardContext},
+    http::header::{HeaderMap, ACCEPT_ENCODING},
+    HttpMessage,
+};
+
+use crate::{
+    byte_slice::join_field_values,
+    encoding_matcher::match_for_encoding,
+    supported_encodings::{NegotiatedEncoding, SupportedEncodings},
+};
+
+/// A guard that passes requests whose Accept-Encoding accepts the coding,
+/// with a q-value above 0, e.g. `web::get().guard(EncodingAccepted("br"))`.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodingAccepted(pub &'static str);
+
+impl Guard for EncodingAccepted {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        let header_value = accept_encoding(ctx.head().headers());
+        match_for_encoding(&header_value, self.0.as_bytes()).is_some_and(|m| !m.q.is_zero())
+    }
+}
+
+/// A middleware that stores a `NegotiatedEncoding` in each request's
+/// extensions, for handlers to read with `web::ReqData<NegotiatedEncoding>`.
+#[derive(Debug, Clone)]
+pub struct NegotiateEncoding {
+    supported: Rc<SupportedEncodings>,
+}
+
+impl NegotiateEncoding {
+    pub fn new(supported: SupportedEncodings) -> Self {
+        Self {
+            supported: Rc::new(supported),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for NegotiateEncoding
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = NegotiateEncodingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(NegotiateEncodingMiddleware {
+            service,
+            supported: self.supported.clone(),
+        }))
+    }
+}
+
+/// The service `NegotiateEncoding` wraps others in.
+pub struct NegotiateEncodingMiddleware<S> {
+    service: S,
+    supported: Rc<SupportedEncodings>,
+}
+
+impl<S, B> Service<ServiceRequest> for NegotiateEncodingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let encoding = self
+            .supported
+            .best_match(&accept_encoding(request.headers()))
+            .map(|(e, _)| String::from_utf8_lossy(e).into_owned());
+        request
+            .extensions_mut()
+            .insert(NegotiatedEncoding { encoding });
+        self.service.call(request)
+    }
+}
+
+fn accept_encoding(headers: &HeaderMap) -> Cow<'_, [u8]> {
+    join_field_values(headers.get_all(ACCEPT_ENCODING).map(|v| v.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        rt::System,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_encoding_accepted() {
+        let accepts = |header_values: &[&str]| {
+            let mut request = TestRequest::default();
+            for &value in header_values {
+                request = request.append_header((ACCEPT_ENCODING, value));
+            }
+            EncodingAccepted("br").check(&request.to_srv_request().guard_ctx())
+        };
+        assert!(accepts(&["gzip", "br;q=0.5"]));
+        assert!(accepts(&["*"]));
+        assert!(!accepts(&["gzip"]));
+        assert!(!accepts(&["br;q=0"]));
+        assert!(!accepts(&[]));
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        System::new().block_on(async {
+            let app = init_service(
+                App::new()
+                    .wrap(NegotiateEncoding::new(
+                        SupportedEncodings::new(["br", "gzip"]).unwrap(),
+                    ))
+                    .route(
+                        "/",
+                        web::get().to(|negotiated: web::ReqData<NegotiatedEncoding>| async move {
+                            HttpResponse::Ok().body(negotiated.encoding.clone().unwrap_or_default())
+                        }),
+                    ),
+            )
+            .await;
+            for (header_value, expected) in [("gzip, br;q=0.5", "gzip"), ("zstd", "")] {
+                let request = TestRequest::get()
+                    .uri("/")
+                    .insert_header((ACCEPT_ENCODING, header_value))
+                    .to_request();
+                let response = call_service(&app, request).await;
+                assert_eq!(StatusCode::OK, response.status());
+                assert_eq!(expected.as_bytes(), read_body(response).await);
+            }
+        });
+    }
+}
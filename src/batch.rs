@@ -0,0 +1,134 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    encoding_matcher::{match_target, EncodingMatch, EncodingMatchType, Target},
+    lexer::Cursor,
+    options::MatchOptions,
+    q_value::QValue,
+};
+
+/// Matches every header value against `encoding` on the rayon thread pool
+/// and returns the results in input order.
+#[cfg(feature = "rayon")]
+pub fn match_for_encoding_batch_par(
+    headers: &[&[u8]],
+    encoding: &[u8],
+) -> Vec<Option<EncodingMatch>> {
+    headers
+        .par_iter()
+        .map(|input| crate::match_for_encoding(input, encoding))
+        .collect()
+}
+
+/// Matches many header values against one encoding, set up once.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodingBatchMatcher<'e> {
+    target: Target<'e>,
+    options: MatchOptions,
+}
+
+/// Results of `EncodingBatchMatcher`, one column per field so that
+/// aggregating a single field reads contiguous memory.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EncodingBatchResults {
+    /// `None` where the header value does not accept the encoding or is
+    /// malformed.
+    pub match_types: Vec<Option<EncodingMatchType>>,
+    /// The q-value where `match_types` is `Some`, zero elsewhere.
+    pub q_values: Vec<QValue>,
+}
+
+impl<'e> EncodingBatchMatcher<'e> {
+    pub fn new(encoding: &'e [u8], options: MatchOptions) -> Self {
+        Self {
+            target: Target::new(encoding),
+            options,
+        }
+    }
+
+    /// Replaces the contents of `results` with one result per header
+    /// value, reusing its allocations.
+    pub fn match_into<'h, I>(&self, headers: I, results: &mut EncodingBatchResults)
+    where
+        I: IntoIterator<Item = &'h [u8]>,
+    {
+        results.clear();
+        for input in headers {
+            let m = match_target(input, self.target, &self.options, &mut Cursor(0), &mut ())
+                .ok()
+                .flatten();
+            results.match_types.push(m.map(|m| m.match_type));
+            results.q_values.push(m.map_or(QValue::ZERO, |m| m.q));
+        }
+    }
+}
+
+impl EncodingBatchResults {
+    pub fn len(&self) -> usize {
+        self.match_types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.match_types.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<EncodingMatch> {
+        Some(EncodingMatch {
+            match_type: (*self.match_types.get(index)?)?,
+            q: self.q_values[index],
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.match_types.clear();
+        self.q_values.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::match_for_encoding;
+
+    fn headers() -> Vec<&'static [u8]> {
+        (0..1000)
+            .map(|i| match i % 4 {
+                0 => &b"gzip, br"[..],
+                1 => b"gzip;q=0.5",
+                2 => b"*;q=0.1",
+                _ => b"identity",
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_match_for_encoding_batch_par() {
+        let headers = headers();
+        assert_eq!(
+            headers
+                .iter()
+                .map(|input| match_for_encoding(input, b"gzip"))
+                .collect::<Vec<_>>(),
+            match_for_encoding_batch_par(&headers, b"gzip")
+        );
+    }
+
+    #[test]
+    fn test_encoding_batch_matcher() {
+        let headers = headers();
+        let matcher = EncodingBatchMatcher::new(b"gzip", MatchOptions::default());
+        let mut results = EncodingBatchResults::default();
+        matcher.match_into(headers.iter().copied(), &mut results);
+        assert_eq!(headers.len(), results.len());
+        for (i, input) in headers.iter().enumerate() {
+            assert_eq!(match_for_encoding(input, b"gzip"), results.get(i));
+        }
+
+        matcher.match_into([&b"br"[..]], &mut results);
+        assert_eq!(1, results.len());
+        assert_eq!(None, results.get(0));
+        assert_eq!(None, results.get(1));
+    }
+}
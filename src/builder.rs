@@ -0,0 +1,209 @@
+//! Building Accept-Encoding and Accept values for requests. The names and
+//! media types are validated when their `EncodingName` or `MimeTypeRef` is
+//! made, so the value built is always a valid header value.
+
+#[cfg(feature = "mime")]
+use crate::mime_type_matcher::MimeTypeRef;
+use crate::q_value::QValue;
+#[cfg(feature = "encoding")]
+use crate::{
+    accept_encoding::AcceptEncoding, content_coding::ContentCoding, encoding_matcher::EncodingName,
+};
+
+/// Builds an Accept-Encoding value, leaving out q when it is 1:
+/// `gzip, br;q=0.5`.
+#[cfg(feature = "encoding")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcceptEncodingBuilder {
+    value: Vec<u8>,
+}
+
+#[cfg(feature = "encoding")]
+impl AcceptEncoding {
+    pub fn builder() -> AcceptEncodingBuilder {
+        AcceptEncodingBuilder::default()
+    }
+
+    /// What current Chrome and Firefox send: `gzip, deflate, br, zstd`.
+    pub fn browser_default() -> AcceptEncodingBuilder {
+        Self::from_supported_decoders(&[
+            ContentCoding::Gzip,
+            ContentCoding::Deflate,
+            ContentCoding::Brotli,
+            ContentCoding::Zstd,
+        ])
+    }
+
+    /// What `curl --compressed` sends when built with every decoder:
+    /// `deflate, gzip, br, zstd`.
+    pub fn curl_like() -> AcceptEncodingBuilder {
+        Self::from_supported_decoders(&[
+            ContentCoding::Deflate,
+            ContentCoding::Gzip,
+            ContentCoding::Brotli,
+            ContentCoding::Zstd,
+        ])
+    }
+
+    /// Lists the codings a client can decode, in order, each with q=1.
+    /// Identity is left out, as it is acceptable unless excluded, and so
+    /// are repeats.
+    pub fn from_supported_decoders(codings: &[ContentCoding]) -> AcceptEncodingBuilder {
+        let mut builder = Self::builder();
+        for (i, &coding) in codings.iter().enumerate() {
+            if coding != ContentCoding::Identity && !codings[..i].contains(&coding) {
+                builder = builder.content_coding(coding, QValue::ONE);
+            }
+        }
+        builder
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl AcceptEncodingBuilder {
+    pub fn encoding(mut self, encoding: EncodingName<'_>, q: QValue) -> Self {
+        push_entry(&mut self.value, encoding.as_bytes(), q);
+        self
+    }
+
+    pub fn content_coding(mut self, coding: ContentCoding, q: QValue) -> Self {
+        push_entry(&mut self.value, coding.name().as_bytes(), q);
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.value
+    }
+
+    /// Like `build`, without copying or checking the bytes again.
+    #[cfg(feature = "http")]
+    pub fn build_header_value(self) -> http::HeaderValue {
+        into_header_value(self.value)
+    }
+}
+
+/// Builds an Accept value, leaving out q when it is 1:
+/// `text/html, image/*;q=0.8`.
+#[cfg(feature = "mime")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcceptBuilder {
+    value: Vec<u8>,
+}
+
+#[cfg(feature = "mime")]
+impl AcceptBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mime_type(mut self, mime_type: MimeTypeRef<'_>, q: QValue) -> Self {
+        push_entry(&mut self.value, mime_type.as_bytes(), q);
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.value
+    }
+
+    /// Like `build`, without copying or checking the bytes again.
+    #[cfg(feature = "http")]
+    pub fn build_header_value(self) -> http::HeaderValue {
+        into_header_value(self.value)
+    }
+}
+
+fn push_entry(value: &mut Vec<u8>, name: &[u8], q: QValue) {
+    if !value.is_empty() {
+        value.extend_from_slice(b", ");
+    }
+    value.extend_from_slice(name);
+    if !q.is_max() {
+        value.extend_from_slice(b";q=");
+        q.write_to(value);
+    }
+}
+
+#[cfg(feature = "http")]
+fn into_header_value(value: Vec<u8>) -> http::HeaderValue {
+    // SAFETY: the value only holds tokens, "/", ", ", ";q=" and q-values,
+    // all of which are visible ASCII or spaces.
+    unsafe { http::HeaderValue::from_maybe_shared_unchecked(bytes::Bytes::from(value)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_accept_encoding_builder() {
+        let value = AcceptEncoding::builder()
+            .encoding(EncodingName::new(b"gzip").unwrap(), QValue::ONE)
+            .encoding(
+                EncodingName::new(b"br").unwrap(),
+                QValue::from_millis(500).unwrap(),
+            )
+            .encoding(EncodingName::new(b"*").unwrap(), QValue::ZERO)
+            .build();
+        assert_eq!(&b"gzip, br;q=0.5, *;q=0"[..], &value[..]);
+        let parsed = AcceptEncoding::parse(&value).unwrap();
+        assert_eq!(500, parsed.match_encoding(b"br").unwrap().q.as_millis());
+        assert!(AcceptEncoding::builder().build().is_empty());
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_accept_encoding_presets() {
+        assert_eq!(
+            &b"gzip, deflate, br, zstd"[..],
+            &AcceptEncoding::browser_default().build()[..]
+        );
+        assert_eq!(
+            &b"deflate, gzip, br, zstd"[..],
+            &AcceptEncoding::curl_like().build()[..]
+        );
+        let value = AcceptEncoding::from_supported_decoders(&[
+            ContentCoding::Zstd,
+            ContentCoding::Identity,
+            ContentCoding::Gzip,
+            ContentCoding::Zstd,
+        ])
+        .content_coding(ContentCoding::Brotli, QValue::ZERO)
+        .build();
+        assert_eq!(&b"zstd, gzip, br;q=0"[..], &value[..]);
+        assert!(
+            AcceptEncoding::from_supported_decoders(&[ContentCoding::Identity])
+                .build()
+                .is_empty()
+        );
+    }
+
+    #[cfg(all(feature = "encoding", feature = "http"))]
+    #[test]
+    fn test_accept_encoding_builder_header_value() {
+        let value = AcceptEncoding::builder()
+            .encoding(
+                EncodingName::new(b"zstd").unwrap(),
+                QValue::from_millis(125).unwrap(),
+            )
+            .build_header_value();
+        assert_eq!(http::HeaderValue::from_static("zstd;q=0.125"), value);
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_accept_builder() {
+        let builder = AcceptBuilder::new()
+            .mime_type(MimeTypeRef::new(b"text/html").unwrap(), QValue::ONE)
+            .mime_type(
+                MimeTypeRef::new(b"image/*").unwrap(),
+                QValue::from_millis(800).unwrap(),
+            );
+        #[cfg(feature = "http")]
+        assert_eq!(
+            http::HeaderValue::from_static("text/html, image/*;q=0.8"),
+            builder.clone().build_header_value()
+        );
+        assert_eq!(&b"text/html, image/*;q=0.8"[..], &builder.build()[..]);
+    }
+}
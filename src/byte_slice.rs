@@ -1,16 +1,91 @@
+#[cfg(any(
+    feature = "http",
+    feature = "actix",
+    feature = "poem",
+    feature = "warp"
+))]
+use std::borrow::Cow;
+
 pub(crate) fn bytes_eq_ignore_case(bytes1: &[u8], bytes2: &[u8]) -> bool {
     if bytes1.len() != bytes2.len() {
         return false;
     }
-    for i in 0..bytes1.len() {
+    let mut chunks1 = bytes1.chunks_exact(8);
+    let mut chunks2 = bytes2.chunks_exact(8);
+    for (c1, c2) in (&mut chunks1).zip(&mut chunks2) {
+        let w1 = u64::from_le_bytes(c1.try_into().unwrap());
+        let w2 = u64::from_le_bytes(c2.try_into().unwrap());
+        if !word_eq_ignore_case(w1, w2) {
+            return false;
+        }
+    }
+    chunks1
+        .remainder()
+        .iter()
+        .zip(chunks2.remainder())
+        .all(|(b1, b2)| byte_eq_ignore_case(*b1, *b2))
+}
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const CASE_BITS: u64 = ONES * 0x20;
+const HIGH_BITS: u64 = ONES * 0x80;
+
+/// Compares eight bytes at once. Bytes may only differ in the case bit
+/// 0x20, and only where the byte is an ASCII letter.
+#[inline]
+fn word_eq_ignore_case(w1: u64, w2: u64) -> bool {
+    let diff = w1 ^ w2;
+    if diff == 0 {
+        return true;
+    }
+    if diff & !CASE_BITS != 0 {
+        return false;
+    }
+    // With the high bits cleared, adding 0x1f or 0x05 cannot carry into the
+    // next byte, so each high bit tells whether that byte is >= 'a' or > 'z'.
+    let lower = (w1 | CASE_BITS) & !HIGH_BITS;
+    let is_letter = (lower + ONES * 0x1f) & !(lower + ONES * 0x05) & !w1 & HIGH_BITS;
+    (diff << 2) & !is_letter == 0
+}
+
+/// The byte-at-a-time comparison, usable in const contexts.
+#[cfg(feature = "encoding")]
+pub(crate) const fn bytes_eq_ignore_case_const(bytes1: &[u8], bytes2: &[u8]) -> bool {
+    if bytes1.len() != bytes2.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < bytes1.len() {
         if !byte_eq_ignore_case(bytes1[i], bytes2[i]) {
             return false;
         }
+        i += 1;
     }
     true
 }
 
-fn byte_eq_ignore_case(b1: u8, b2: u8) -> bool {
+/// Joins the values of a repeated list field with ", ", which RFC 9110
+/// makes equivalent. Borrows a lone value, and gives empty bytes for none.
+#[cfg(any(
+    feature = "http",
+    feature = "actix",
+    feature = "poem",
+    feature = "warp"
+))]
+pub(crate) fn join_field_values<'a>(mut values: impl Iterator<Item = &'a [u8]>) -> Cow<'a, [u8]> {
+    let Some(first) = values.next() else {
+        return Cow::Borrowed(b"");
+    };
+    let mut joined = Cow::Borrowed(first);
+    for value in values {
+        let joined = joined.to_mut();
+        joined.extend_from_slice(b", ");
+        joined.extend_from_slice(value);
+    }
+    joined
+}
+
+const fn byte_eq_ignore_case(b1: u8, b2: u8) -> bool {
     // Apapted from https://docs.rs/ascii/1.1.0/src/ascii/ascii_char.rs.html#726-732
     b1 == b2 || {
         let b1_not_upper = b1 | 0b010_0000;
@@ -31,5 +106,29 @@ mod tests {
 
         assert!(!bytes_eq_ignore_case(b"gzip", b"zip"));
         assert!(!bytes_eq_ignore_case(b"gzip", b"gzi2"));
+
+        assert!(bytes_eq_ignore_case(b"pack200-gzip", b"Pack200-GZIP"));
+        assert!(bytes_eq_ignore_case(b"x-compress", b"X-COMPRESS"));
+        assert!(!bytes_eq_ignore_case(b"pack200-gzip", b"pack200\rgzip"));
+        assert!(!bytes_eq_ignore_case(b"x-compres@", b"x-compres`"));
+    }
+
+    #[test]
+    fn test_bytes_eq_ignore_case_matches_bytewise() {
+        for pos in 0..10 {
+            for b1 in 0..=255u8 {
+                for b2 in [b1, b1 ^ 0x20, b1 ^ 0x01, b1 ^ 0xa0] {
+                    let mut s1 = *b"abcdefghij";
+                    let mut s2 = *b"ABCDEFGHIJ";
+                    s1[pos] = b1;
+                    s2[pos] = b2;
+                    assert_eq!(
+                        s1.iter().zip(&s2).all(|(x, y)| byte_eq_ignore_case(*x, *y)),
+                        bytes_eq_ignore_case(&s1, &s2),
+                        "pos={pos}, b1={b1:#x}, b2={b2:#x}"
+                    );
+                }
+            }
+        }
     }
 }
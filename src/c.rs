@@ -1,129 +1,1512 @@
+// These functions are meant to be called from C. They check what they can,
+// null pointers, and otherwise trust the caller like any C function would.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
 use std::{
-    ffi::{c_char, c_int},
-    slice,
+    cmp::Ordering,
+    ffi::{c_char, c_int, CStr},
+    panic::{self, UnwindSafe},
+    ptr, slice,
 };
 
 use crate::{
-    encoding_matcher::{match_for_encoding, EncodingMatchType},
-    mime_type_matcher::{match_for_mime_type, MimeTypeMatchType},
+    explain::Explanation,
+    matched::Match,
+    options::{MatchOptions, RegistryCheck},
+    q_value::QValue,
+};
+
+#[cfg(any(feature = "encoding", feature = "mime"))]
+use crate::matcher::{Limits, Matcher, TieBreak};
+
+#[cfg(feature = "language")]
+use crate::language_matcher::{match_for_language, LanguageMatch, LanguageMatchType};
+#[cfg(feature = "mime")]
+use crate::mime_type_matcher::{
+    explain_mime_type, match_for_mime_type_with_options, MimeTypeMatch, MimeTypeMatchType,
+};
+#[cfg(feature = "encoding")]
+use crate::{
+    accept_encoding::AcceptEncoding,
+    encoding_matcher::{
+        effective_q, explain_encoding, match_for_encoding_with_options, validate_accept_encoding,
+        EncodingMatch, EncodingMatchType, Target,
+    },
+    rewrite::{add_encoding, remove_encoding},
+    supported_encodings::negotiate,
 };
 
-pub const C_ENCODING_MATCH_TYPE_NO_MATCH: i32 = 0;
-pub const C_ENCODING_MATCH_TYPE_WILDCARD: i32 = 1;
-pub const C_ENCODING_MATCH_TYPE_EXACT: i32 = 2;
+/// The exported name of a C function: `name` after the symbol prefix, which
+/// is `c_` unless ACCEPT_ENCODING_SYMBOL_PREFIX is set when building.
+macro_rules! symbol {
+    ($name:literal) => {
+        concat!(env!("ACCEPT_ENCODING_SYMBOL_PREFIX"), $name)
+    };
+}
+
+/// The version of the C functions and structs, raised on every incompatible
+/// change to them. Compare it with `c_abi_version()` when loading the
+/// library at run time.
+pub const ACCEPT_ENCODING_ABI_VERSION: u32 = 1;
+
+/// Returns `ACCEPT_ENCODING_ABI_VERSION` as built into the library.
+#[export_name = symbol!("abi_version")]
+pub extern "C" fn c_abi_version() -> u32 {
+    ACCEPT_ENCODING_ABI_VERSION
+}
+
+/// Returns the crate version, like "0.2.0", as a static NUL-terminated
+/// string.
+#[export_name = symbol!("version")]
+pub extern "C" fn c_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Returns 1 if a panic inside the library cannot unwind into the caller:
+/// either the library was built with `panic = "abort"`, or a panic is
+/// caught at the C boundary, which this checks by raising one. A build
+/// where unwinding is broken aborts here instead of returning.
+#[export_name = symbol!("is_panic_safe")]
+pub extern "C" fn c_is_panic_safe() -> c_int {
+    if cfg!(panic = "abort") {
+        return 1;
+    }
+    // resume_unwind skips the panic hook, so nothing is printed.
+    catch_panic(1, || panic::resume_unwind(Box::new(())))
+}
+
+/// Parses a q-value like "0.5" as it appears after "q=" and stores it in
+/// thousandths in `*out_millis`. Returns 1 on success, or 0 if the value is
+/// invalid or a pointer is NULL.
+#[export_name = symbol!("qvalue_from_string")]
+pub extern "C" fn c_qvalue_from_string(
+    value: *const c_char,
+    value_len: usize,
+    out_millis: *mut u16,
+) -> c_int {
+    catch_panic(0, || {
+        let (Some(value), Some(out_millis)) =
+            (unsafe { (bytes_from_raw(value, value_len), out_millis.as_mut()) })
+        else {
+            return 0;
+        };
+        match QValue::try_from(value) {
+            Ok(q) => {
+                *out_millis = q.as_millis();
+                1
+            }
+            Err(_) => 0,
+        }
+    })
+}
+
+/// Converts a q-value in thousandths to a double, the way the `q` fields of
+/// the match results are filled in. Returns NaN for more than 1000.
+#[export_name = symbol!("qvalue_to_double")]
+pub extern "C" fn c_qvalue_to_double(millis: u16) -> f64 {
+    QValue::from_millis(millis).map_or(f64::NAN, f64::from)
+}
+
+/// Returns the bytes at `ptr`, or None if `ptr` is null or `len` is too
+/// large for a slice.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to `len` readable bytes that outlive `'a`.
+unsafe fn bytes_from_raw<'a>(ptr: *const c_char, len: usize) -> Option<&'a [u8]> {
+    if ptr.is_null() || len > isize::MAX as usize {
+        return None;
+    }
+    Some(slice::from_raw_parts(ptr as *const u8, len))
+}
+
+/// Returns the length of a NUL-terminated string, or 0 for a null pointer,
+/// which `bytes_from_raw` then rejects.
+fn cstr_len(ptr: *const c_char) -> usize {
+    if ptr.is_null() {
+        return 0;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_bytes().len()
+}
+
+/// Values of `COptions::registry_check`, for how tokens are checked against
+/// the IANA registry.
+pub const C_REGISTRY_CHECK_OFF: c_int = 0;
+/// Report tokens missing from the registry as warnings.
+pub const C_REGISTRY_CHECK_WARN: c_int = 1;
+/// Treat tokens missing from the registry as malformed.
+pub const C_REGISTRY_CHECK_REJECT: c_int = 2;
+
+/// Values of `COptions::tie_break`, for which entry applies when several
+/// match equally specifically. The highest q-value wins, then the
+/// earliest.
+pub const C_TIE_BREAK_HIGHEST_Q: c_int = 0;
+pub const C_TIE_BREAK_FIRST_WINS: c_int = 1;
+/// The latest wins, as if each entry overrode the earlier ones.
+pub const C_TIE_BREAK_LAST_WINS: c_int = 2;
+
+/// `MatchOptions`, `TieBreak` and `Limits` for the `_with_options`
+/// functions. A NULL pointer, or a zeroed struct, means the default strict
+/// parsing without limits. Functions given a `registry_check` or
+/// `tie_break` they do not know fail as they do for a null argument.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COptions {
+    /// Nonzero to tolerate what browsers get away with, like
+    /// `MatchOptions::lenient`.
+    lenient: c_int,
+    /// One of the `C_REGISTRY_CHECK_` values.
+    registry_check: c_int,
+    /// One of the `C_TIE_BREAK_` values. It does not apply to
+    /// `c_negotiate_encoding_with_options`, which ranks by its own rules.
+    tie_break: c_int,
+    /// The longest header value in bytes, or 0 for no limit. Longer values
+    /// are treated as malformed.
+    max_len: usize,
+    /// The most list entries, or 0 for no limit.
+    max_entries: usize,
+}
+
+impl COptions {
+    const DEFAULT: Self = Self {
+        lenient: 0,
+        registry_check: C_REGISTRY_CHECK_OFF,
+        tie_break: C_TIE_BREAK_HIGHEST_Q,
+        max_len: 0,
+        max_entries: 0,
+    };
+    const LENIENT: Self = Self {
+        lenient: 1,
+        ..Self::DEFAULT
+    };
+}
+
+/// `COptions` checked and converted.
+#[cfg(any(feature = "encoding", feature = "mime"))]
+#[derive(Default)]
+struct Options {
+    match_options: MatchOptions,
+    tie_break: TieBreak,
+    limits: Limits,
+}
+
+#[cfg(any(feature = "encoding", feature = "mime"))]
+impl Options {
+    /// A `Matcher` for a tie break or limits that the `match_for_*`
+    /// functions do not support, or None if those functions will do. They
+    /// match as they parse, without allocating.
+    fn matcher(&self) -> Option<Matcher> {
+        if self.tie_break == TieBreak::HighestQ && self.limits == Limits::default() {
+            return None;
+        }
+        Some(
+            Matcher::builder()
+                .options(self.match_options)
+                .tie_break(self.tie_break)
+                .limits(self.limits)
+                .build(),
+        )
+    }
+}
+
+/// Returns the options at `options`, the defaults if it is NULL, or None if
+/// a field has a value this library does not know.
+#[cfg(any(feature = "encoding", feature = "mime"))]
+fn options_from_raw(options: *const COptions) -> Option<Options> {
+    let Some(options) = (unsafe { options.as_ref() }) else {
+        return Some(Options::default());
+    };
+    let registry_check = match options.registry_check {
+        C_REGISTRY_CHECK_OFF => RegistryCheck::Off,
+        C_REGISTRY_CHECK_WARN => RegistryCheck::Warn,
+        C_REGISTRY_CHECK_REJECT => RegistryCheck::Reject,
+        _ => return None,
+    };
+    let tie_break = match options.tie_break {
+        C_TIE_BREAK_HIGHEST_Q => TieBreak::HighestQ,
+        C_TIE_BREAK_FIRST_WINS => TieBreak::FirstWins,
+        C_TIE_BREAK_LAST_WINS => TieBreak::LastWins,
+        _ => return None,
+    };
+    let limit = |n| if n == 0 { usize::MAX } else { n };
+    Some(Options {
+        match_options: MatchOptions {
+            lenient: options.lenient != 0,
+            registry_check,
+        },
+        tie_break,
+        limits: Limits {
+            max_len: limit(options.max_len),
+            max_entries: limit(options.max_entries),
+        },
+    })
+}
+
+/// What went wrong in a C function that takes an `out_error` argument.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CParseErrorCode {
+    None = 0,
+    /// A pointer argument is null or a length is too large.
+    InvalidArgument = 1,
+    /// The header value is malformed at `offset`.
+    MalformedHeader = 2,
+    /// Matching panicked.
+    Panic = 3,
+}
+
+/// Filled in through the `out_error` argument of C functions, which may be
+/// NULL when the details are not needed.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CParseError {
+    code: CParseErrorCode,
+    /// Where in the header value parsing failed, or 0 for other codes.
+    offset: usize,
+}
+
+impl CParseError {
+    const NONE: Self = Self::new(CParseErrorCode::None);
+    const INVALID_ARGUMENT: Self = Self::new(CParseErrorCode::InvalidArgument);
+    const PANIC: Self = Self::new(CParseErrorCode::Panic);
+
+    const fn new(code: CParseErrorCode) -> Self {
+        Self { code, offset: 0 }
+    }
+
+    fn from_error_offset(error_offset: Option<usize>) -> Self {
+        match error_offset {
+            Some(offset) => Self {
+                code: CParseErrorCode::MalformedHeader,
+                offset,
+            },
+            None => Self::NONE,
+        }
+    }
+}
+
+/// Stores `error` in `out_error` unless it is NULL.
+fn set_error(out_error: *mut CParseError, error: CParseError) {
+    if let Some(out_error) = unsafe { out_error.as_mut() } {
+        *out_error = error;
+    }
+}
+
+/// Returns the index of the winning entry and the offset and length of its
+/// name in the header value, or (-1, 0, 0) without a winner.
+fn winner_span<M>(explanation: &Explanation<'_, M>) -> (isize, usize, usize) {
+    let Some(i) = explanation.winner else {
+        return (-1, 0, 0);
+    };
+    let entry = &explanation.entries[i];
+    let name_len = entry
+        .text
+        .iter()
+        .position(|b| matches!(b, b' ' | b'\t' | b';'))
+        .unwrap_or(entry.text.len());
+    (i as isize, entry.offset, name_len)
+}
+
+/// Explains how `header_value` matches with `explain`, then applies the
+/// limits and tie break of `options` the way `Matcher` does. A value over
+/// the limits is reported as malformed where it goes over them.
+#[cfg(any(feature = "encoding", feature = "mime"))]
+fn explain_with_options<'a, K: Ord + Copy>(
+    header_value: &'a [u8],
+    options: &Options,
+    explain: impl FnOnce(&'a [u8], &MatchOptions) -> Explanation<'a, Match<K>>,
+) -> Explanation<'a, Match<K>> {
+    if header_value.len() > options.limits.max_len {
+        return Explanation {
+            entries: Vec::new(),
+            result: None,
+            winner: None,
+            error_offset: Some(options.limits.max_len),
+            warnings: Vec::new(),
+        };
+    }
+    let mut explanation = explain(header_value, &options.match_options);
+    if explanation.error_offset.is_some() {
+        return explanation;
+    }
+    if let Some(entry) = explanation.entries.get(options.limits.max_entries) {
+        explanation.error_offset = Some(entry.offset);
+        explanation.result = None;
+        explanation.winner = None;
+    } else if options.tie_break != TieBreak::HighestQ {
+        let mut winner: Option<(usize, Match<K>)> = None;
+        for (i, entry) in explanation.entries.iter().enumerate() {
+            let Some(m) = entry.matched else {
+                continue;
+            };
+            if winner.is_none_or(|(_, b)| options.tie_break.prefers(&m, &b)) {
+                winner = Some((i, m));
+            }
+        }
+        explanation.winner = winner.map(|(i, _)| i);
+        explanation.result = winner.map(|(_, m)| m);
+    }
+    explanation
+}
+
+/// Runs `f` and returns `on_panic` if it panics, since unwinding into the
+/// C caller is undefined behavior.
+fn catch_panic<T>(on_panic: T, f: impl FnOnce() -> T + UnwindSafe) -> T {
+    panic::catch_unwind(f).unwrap_or(on_panic)
+}
+
+/// The C match type and q-value of `m`, or `no_match` and 0 without a
+/// match.
+fn c_match_parts<K, T: From<K>>(m: Option<Match<K>>, no_match: T) -> (T, f64) {
+    m.map_or((no_match, 0.0), |m| (m.match_type.into(), m.q.into()))
+}
+
+/// Returned by the `c_cmp_` functions when a match type is out of range.
+#[cfg(any(feature = "encoding", feature = "mime"))]
+pub const C_CMP_INVALID: c_int = -2;
+
+/// Compares two C matches by match type, then by q-value unless neither
+/// matched, as -1, 0 or 1.
+#[cfg(any(feature = "encoding", feature = "mime"))]
+fn cmp_c_match<T: Ord>((t1, q1): (T, f64), (t2, q2): (T, f64), no_match: T) -> c_int {
+    match t1.cmp(&t2) {
+        Ordering::Less => -1,
+        Ordering::Greater => 1,
+        Ordering::Equal if t1 == no_match => 0,
+        Ordering::Equal => match q1.partial_cmp(&q2) {
+            Some(Ordering::Less) => -1,
+            Some(Ordering::Greater) => 1,
+            _ => 0,
+        },
+    }
+}
+
+/// How `c_match_encoding` matched, ranked by value.
+#[cfg(feature = "encoding")]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CEncodingMatchType {
+    /// Returned when a pointer argument is null or matching panicked.
+    Error = -1,
+    NoMatch = 0,
+    Wildcard = 1,
+    Exact = 2,
+}
+
+/// The result of `c_match_encoding`.
+#[cfg(feature = "encoding")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CEncodingMatch {
+    /// A `CEncodingMatchType` value. It is an int since `c_cmp_encoding_match`
+    /// takes it back from the caller, who may pass any value.
+    match_type: c_int,
+    /// The q-value of the matched entry, or 0 without a match.
+    q: f64,
+}
+
+#[cfg(feature = "encoding")]
+impl CEncodingMatch {
+    const ERROR: Self = Self {
+        match_type: CEncodingMatchType::Error as c_int,
+        q: 0.0,
+    };
+}
+
+#[cfg(feature = "encoding")]
+impl CEncodingMatchType {
+    /// Returns the match type whose value is `match_type`, or None.
+    fn from_raw(match_type: c_int) -> Option<Self> {
+        [Self::Error, Self::NoMatch, Self::Wildcard, Self::Exact]
+            .into_iter()
+            .find(|&t| t as c_int == match_type)
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl From<EncodingMatchType> for CEncodingMatchType {
+    fn from(match_type: EncodingMatchType) -> Self {
+        match match_type {
+            EncodingMatchType::Wildcard => CEncodingMatchType::Wildcard,
+            EncodingMatchType::Exact => CEncodingMatchType::Exact,
+        }
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl From<Option<EncodingMatch>> for CEncodingMatch {
+    fn from(m: Option<EncodingMatch>) -> Self {
+        let (match_type, q) = c_match_parts(m, CEncodingMatchType::NoMatch);
+        CEncodingMatch {
+            match_type: match_type as c_int,
+            q,
+        }
+    }
+}
+
+/// Matches an Accept-Encoding header value against a content coding.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("match_encoding")]
+pub extern "C" fn c_match_encoding(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+) -> CEncodingMatch {
+    c_match_encoding_with_options(
+        header_value,
+        header_value_len,
+        encoding,
+        encoding_len,
+        ptr::null(),
+    )
+}
+
+/// Like `c_match_encoding`, with options; NULL `options` means the defaults.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("match_encoding_with_options")]
+pub extern "C" fn c_match_encoding_with_options(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+    options: *const COptions,
+) -> CEncodingMatch {
+    catch_panic(CEncodingMatch::ERROR, || {
+        let (Some(header_value), Some(encoding), Some(options)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(encoding, encoding_len),
+                options_from_raw(options),
+            )
+        }) else {
+            return CEncodingMatch::ERROR;
+        };
+        match options.matcher() {
+            Some(matcher) => matcher.match_encoding(header_value, encoding),
+            None => match_for_encoding_with_options(header_value, encoding, &options.match_options),
+        }
+        .into()
+    })
+}
+
+/// Like `c_match_encoding`, but takes NUL-terminated strings.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("match_encoding_cstr")]
+pub extern "C" fn c_match_encoding_cstr(
+    header_value: *const c_char,
+    encoding: *const c_char,
+) -> CEncodingMatch {
+    c_match_encoding(
+        header_value,
+        cstr_len(header_value),
+        encoding,
+        cstr_len(encoding),
+    )
+}
+
+/// Like `c_match_encoding`, but tolerates what browsers get away with, as
+/// `COptions` with `lenient` set does.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("match_encoding_lenient")]
+pub extern "C" fn c_match_encoding_lenient(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+) -> CEncodingMatch {
+    c_match_encoding_with_options(
+        header_value,
+        header_value_len,
+        encoding,
+        encoding_len,
+        &COptions::LENIENT,
+    )
+}
+
+/// The result of `c_match_encoding_v2`: `CEncodingMatch` with where the
+/// matching entry is, for rewriting the header value in place.
+#[cfg(feature = "encoding")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CEncodingMatchV2 {
+    /// A `CEncodingMatchType` value.
+    match_type: c_int,
+    q: f64,
+    /// The index of the matching entry in the list, or -1 without a match.
+    entry_index: isize,
+    /// The offset and length of the coding name of the matching entry in
+    /// the header value, or 0 without a match.
+    name_offset: usize,
+    name_len: usize,
+}
+
+/// Like `c_match_encoding_with_options`, but also tells which entry
+/// matched, and where the header value is malformed through `out_error`.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("match_encoding_v2")]
+pub extern "C" fn c_match_encoding_v2(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+    options: *const COptions,
+    out_error: *mut CParseError,
+) -> CEncodingMatchV2 {
+    let error = CEncodingMatchV2 {
+        match_type: CEncodingMatchType::Error as c_int,
+        q: 0.0,
+        entry_index: -1,
+        name_offset: 0,
+        name_len: 0,
+    };
+    let (result, e) = catch_panic((error, CParseError::PANIC), || {
+        let (Some(header_value), Some(encoding), Some(options)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(encoding, encoding_len),
+                options_from_raw(options),
+            )
+        }) else {
+            return (error, CParseError::INVALID_ARGUMENT);
+        };
+        let explanation = explain_with_options(header_value, &options, |header_value, options| {
+            explain_encoding(header_value, encoding, options)
+        });
+        let CEncodingMatch { match_type, q } = explanation.result.into();
+        let (entry_index, name_offset, name_len) = winner_span(&explanation);
+        let result = CEncodingMatchV2 {
+            match_type,
+            q,
+            entry_index,
+            name_offset,
+            name_len,
+        };
+        (
+            result,
+            CParseError::from_error_offset(explanation.error_offset),
+        )
+    });
+    set_error(out_error, e);
+    result
+}
+
+/// Returns -1, 0 or 1 as `m1` ranks below, equal to or above `m2`, or
+/// C_CMP_INVALID if a match type is not a `CEncodingMatchType` value.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("cmp_encoding_match")]
+pub extern "C" fn c_cmp_encoding_match(m1: CEncodingMatch, m2: CEncodingMatch) -> c_int {
+    catch_panic(C_CMP_INVALID, || {
+        let (Some(t1), Some(t2)) = (
+            CEncodingMatchType::from_raw(m1.match_type),
+            CEncodingMatchType::from_raw(m2.match_type),
+        ) else {
+            return C_CMP_INVALID;
+        };
+        cmp_c_match((t1, m1.q), (t2, m2.q), CEncodingMatchType::NoMatch)
+    })
+}
+
+/// Returns the quality the header value gives `encoding`, as
+/// `effective_q` does, in thousandths: the q-value of the entry that
+/// applies, or 1000 for "identity" when nothing excludes it. Returns -1 if
+/// `encoding` is not acceptable or the header value is malformed, and -2 if
+/// a pointer argument is null or matching panicked.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("effective_encoding_q")]
+pub extern "C" fn c_effective_encoding_q(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+) -> c_int {
+    catch_panic(-2, || {
+        let (Some(header_value), Some(encoding)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(encoding, encoding_len),
+            )
+        }) else {
+            return -2;
+        };
+        effective_q(header_value, encoding).map_or(-1, |q| c_int::from(q.as_millis()))
+    })
+}
+
+/// Returned by `c_negotiate_encoding` when no supported coding is acceptable.
+#[cfg(feature = "encoding")]
+pub const C_NEGOTIATE_NO_MATCH: isize = -1;
+/// Returned by `c_negotiate_encoding` when a pointer argument is null or
+/// matching panicked.
+#[cfg(feature = "encoding")]
+pub const C_NEGOTIATE_ERROR: isize = -2;
+/// Returned by `c_vmod_negotiate_encoding` when the chosen coding does not
+/// fit in the buffer.
+#[cfg(feature = "encoding")]
+pub const C_NEGOTIATE_BUFFER_TOO_SMALL: isize = -3;
+
+/// Picks the coding to respond with from the `n` codings the server
+/// supports, in its order of preference, and returns its index. The rules
+/// are those of `SupportedEncodings::best_match`: the highest q-value wins,
+/// then a coding the header names over one covered by "*", then the
+/// earlier one.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("negotiate_encoding")]
+pub extern "C" fn c_negotiate_encoding(
+    header_value: *const c_char,
+    header_value_len: usize,
+    supported: *const *const c_char,
+    supported_lens: *const usize,
+    n: usize,
+) -> isize {
+    c_negotiate_encoding_with_options(
+        header_value,
+        header_value_len,
+        supported,
+        supported_lens,
+        n,
+        ptr::null(),
+    )
+}
+
+/// Like `c_negotiate_encoding`, with options; NULL `options` means the
+/// defaults.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("negotiate_encoding_with_options")]
+pub extern "C" fn c_negotiate_encoding_with_options(
+    header_value: *const c_char,
+    header_value_len: usize,
+    supported: *const *const c_char,
+    supported_lens: *const usize,
+    n: usize,
+    options: *const COptions,
+) -> isize {
+    catch_panic(C_NEGOTIATE_ERROR, || {
+        let (Some(header_value), Some(options)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                options_from_raw(options),
+            )
+        }) else {
+            return C_NEGOTIATE_ERROR;
+        };
+        let (supported, supported_lens) = if n == 0 {
+            (&[][..], &[][..])
+        } else if supported.is_null() || supported_lens.is_null() {
+            return C_NEGOTIATE_ERROR;
+        } else {
+            unsafe {
+                (
+                    slice::from_raw_parts(supported, n),
+                    slice::from_raw_parts(supported_lens, n),
+                )
+            }
+        };
+        if supported.iter().any(|p| p.is_null()) {
+            return C_NEGOTIATE_ERROR;
+        }
+        let targets = supported
+            .iter()
+            .zip(supported_lens)
+            .map(|(&p, &len)| Target::new(unsafe { bytes_from_raw(p, len) }.unwrap_or_default()));
+        let Options {
+            match_options,
+            limits,
+            ..
+        } = options;
+        if header_value.len() > limits.max_len
+            || limits.max_entries < usize::MAX
+                && AcceptEncoding::parse_with_options(header_value, &match_options)
+                    .is_some_and(|parsed| parsed.len() > limits.max_entries)
+        {
+            return C_NEGOTIATE_NO_MATCH;
+        }
+        match negotiate(header_value, targets, &match_options) {
+            Some((i, _)) => i as isize,
+            None => C_NEGOTIATE_NO_MATCH,
+        }
+    })
+}
+
+/// Checks an Accept-Encoding value against the strict grammar. Returns 0
+/// if it is well-formed, or the `CParseErrorCode` stored in `out_error`,
+/// with the offset where parsing failed for a malformed value.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("validate_accept_encoding")]
+pub extern "C" fn c_validate_accept_encoding(
+    header_value: *const c_char,
+    header_value_len: usize,
+    out_error: *mut CParseError,
+) -> c_int {
+    let e = catch_panic(CParseError::PANIC, || {
+        let Some(header_value) = (unsafe { bytes_from_raw(header_value, header_value_len) }) else {
+            return CParseError::INVALID_ARGUMENT;
+        };
+        CParseError::from_error_offset(validate_accept_encoding(header_value).err())
+    });
+    set_error(out_error, e);
+    e.code as c_int
+}
+
+/// An Accept-Encoding value parsed by `c_accept_encoding_parse`.
+#[cfg(feature = "encoding")]
+pub struct CAcceptEncoding(AcceptEncoding);
+
+/// Parses an Accept-Encoding value once for use with
+/// `c_accept_encoding_match`. Returns NULL if the value is malformed or a
+/// pointer is null. The result must be released with
+/// `c_accept_encoding_free`.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("accept_encoding_parse")]
+pub extern "C" fn c_accept_encoding_parse(
+    header_value: *const c_char,
+    header_value_len: usize,
+) -> *mut CAcceptEncoding {
+    c_accept_encoding_parse_with_options(header_value, header_value_len, ptr::null())
+}
+
+/// Like `c_accept_encoding_parse`, with options; NULL `options` means the
+/// defaults.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("accept_encoding_parse_with_options")]
+pub extern "C" fn c_accept_encoding_parse_with_options(
+    header_value: *const c_char,
+    header_value_len: usize,
+    options: *const COptions,
+) -> *mut CAcceptEncoding {
+    catch_panic(ptr::null_mut(), || {
+        let (Some(header_value), Some(options)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                options_from_raw(options),
+            )
+        }) else {
+            return ptr::null_mut();
+        };
+        let Options {
+            match_options,
+            limits,
+            ..
+        } = options;
+        if header_value.len() > limits.max_len {
+            return ptr::null_mut();
+        }
+        match AcceptEncoding::parse_with_options(header_value, &match_options) {
+            Some(parsed) if parsed.len() <= limits.max_entries => {
+                Box::into_raw(Box::new(CAcceptEncoding(parsed)))
+            }
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Like `c_match_encoding` on the value `parsed` was created from.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("accept_encoding_match")]
+pub extern "C" fn c_accept_encoding_match(
+    parsed: *const CAcceptEncoding,
+    encoding: *const c_char,
+    encoding_len: usize,
+) -> CEncodingMatch {
+    catch_panic(CEncodingMatch::ERROR, || {
+        let (Some(parsed), Some(encoding)) =
+            (unsafe { (parsed.as_ref(), bytes_from_raw(encoding, encoding_len)) })
+        else {
+            return CEncodingMatch::ERROR;
+        };
+        parsed.0.match_encoding(encoding).into()
+    })
+}
+
+/// An entry of a parsed Accept-Encoding value, filled in by
+/// `c_accept_encoding_entry_at`.
+#[cfg(feature = "encoding")]
+#[repr(C)]
+pub struct CAcceptEncodingEntry {
+    /// The coding name as written, not NUL-terminated. It points into the
+    /// parsed value and is valid until that is freed.
+    name: *const c_char,
+    name_len: usize,
+    /// The q-value in thousandths, from 0 to 1000.
+    q_millis: u16,
+    q: f64,
+    /// The number of parameters other than q.
+    param_count: usize,
+}
+
+/// Returns the number of entries in `parsed`, or 0 if it is NULL.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("accept_encoding_entry_count")]
+pub extern "C" fn c_accept_encoding_entry_count(parsed: *const CAcceptEncoding) -> usize {
+    unsafe { parsed.as_ref() }.map_or(0, |parsed| parsed.0.len())
+}
+
+/// Fills in `out_entry` with the entry at `index` and returns 1, or returns
+/// 0 if `index` is out of range or a pointer is NULL.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("accept_encoding_entry_at")]
+pub extern "C" fn c_accept_encoding_entry_at(
+    parsed: *const CAcceptEncoding,
+    index: usize,
+    out_entry: *mut CAcceptEncodingEntry,
+) -> c_int {
+    catch_panic(0, || {
+        let (Some(parsed), Some(out_entry)) = (unsafe { (parsed.as_ref(), out_entry.as_mut()) })
+        else {
+            return 0;
+        };
+        let Some((name, q, param_count)) = parsed.0.entry(index) else {
+            return 0;
+        };
+        *out_entry = CAcceptEncodingEntry {
+            name: name.as_ptr() as *const c_char,
+            name_len: name.len(),
+            q_millis: q.as_millis(),
+            q: q.into(),
+            param_count,
+        };
+        1
+    })
+}
+
+/// Releases a value returned by `c_accept_encoding_parse`. NULL is ignored.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("accept_encoding_free")]
+pub extern "C" fn c_accept_encoding_free(parsed: *mut CAcceptEncoding) {
+    if !parsed.is_null() {
+        drop(unsafe { Box::from_raw(parsed) });
+    }
+}
+
+/// Returned by `c_remove_encoding` and `c_add_encoding` when the result
+/// does not fit in `out_cap` bytes. `*out_len` is then the size needed.
+#[cfg(feature = "encoding")]
+pub const C_REWRITE_BUFFER_TOO_SMALL: c_int = -1;
+/// Returned by `c_remove_encoding` and `c_add_encoding` when a pointer
+/// argument is null, the header value is malformed or rewriting panicked.
+#[cfg(feature = "encoding")]
+pub const C_REWRITE_ERROR: c_int = -2;
+
+/// Writes the Accept-Encoding value rewritten by `remove_encoding` to no
+/// longer accept `encoding` to `out_buf`, not NUL-terminated, and its
+/// length to `*out_len`. Returns 0 on success. With `out_cap` 0, `out_buf`
+/// may be NULL to get the size.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("remove_encoding")]
+pub extern "C" fn c_remove_encoding(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+    out_buf: *mut c_char,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> c_int {
+    rewrite_into(
+        remove_encoding,
+        (header_value, header_value_len),
+        (encoding, encoding_len),
+        (out_buf, out_cap, out_len),
+    )
+}
+
+/// Like `c_remove_encoding`, but appends `encoding` unless an entry already
+/// names it.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("add_encoding")]
+pub extern "C" fn c_add_encoding(
+    header_value: *const c_char,
+    header_value_len: usize,
+    encoding: *const c_char,
+    encoding_len: usize,
+    out_buf: *mut c_char,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> c_int {
+    rewrite_into(
+        add_encoding,
+        (header_value, header_value_len),
+        (encoding, encoding_len),
+        (out_buf, out_cap, out_len),
+    )
+}
+
+#[cfg(feature = "encoding")]
+fn rewrite_into(
+    rewrite: fn(&[u8], &[u8]) -> Option<Vec<u8>>,
+    (header_value, header_value_len): (*const c_char, usize),
+    (encoding, encoding_len): (*const c_char, usize),
+    (out_buf, out_cap, out_len): (*mut c_char, usize, *mut usize),
+) -> c_int {
+    catch_panic(C_REWRITE_ERROR, || {
+        let (Some(header_value), Some(encoding), Some(out_len)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(encoding, encoding_len),
+                out_len.as_mut(),
+            )
+        }) else {
+            return C_REWRITE_ERROR;
+        };
+        let Some(rewritten) = rewrite(header_value, encoding) else {
+            return C_REWRITE_ERROR;
+        };
+        *out_len = rewritten.len();
+        if rewritten.len() > out_cap {
+            return C_REWRITE_BUFFER_TOO_SMALL;
+        }
+        if !rewritten.is_empty() {
+            if out_buf.is_null() {
+                return C_REWRITE_ERROR;
+            }
+            unsafe {
+                ptr::copy_nonoverlapping(rewritten.as_ptr(), out_buf as *mut u8, rewritten.len())
+            };
+        }
+        0
+    })
+}
+
+/// How `c_match_mime_type` matched, ranked by value.
+#[cfg(feature = "mime")]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CMimeTypeMatchType {
+    /// Returned when a pointer argument is null or matching panicked.
+    Error = -1,
+    NoMatch = 0,
+    MainTypeWildcard = 1,
+    SubTypeWildcard = 2,
+    Exact = 3,
+}
+
+/// The result of `c_match_mime_type`.
+#[cfg(feature = "mime")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CMimeTypeMatch {
+    /// A `CMimeTypeMatchType` value. It is an int since `c_cmp_mime_type_match`
+    /// takes it back from the caller, who may pass any value.
+    match_type: c_int,
+    /// The q-value of the matched entry, or 0 without a match.
+    q: f64,
+}
+
+#[cfg(feature = "mime")]
+impl CMimeTypeMatch {
+    const ERROR: Self = Self {
+        match_type: CMimeTypeMatchType::Error as c_int,
+        q: 0.0,
+    };
+}
+
+#[cfg(feature = "mime")]
+impl CMimeTypeMatchType {
+    /// Returns the match type whose value is `match_type`, or None.
+    fn from_raw(match_type: c_int) -> Option<Self> {
+        [
+            Self::Error,
+            Self::NoMatch,
+            Self::MainTypeWildcard,
+            Self::SubTypeWildcard,
+            Self::Exact,
+        ]
+        .into_iter()
+        .find(|&t| t as c_int == match_type)
+    }
+}
+
+#[cfg(feature = "mime")]
+impl From<MimeTypeMatchType> for CMimeTypeMatchType {
+    fn from(match_type: MimeTypeMatchType) -> Self {
+        match match_type {
+            MimeTypeMatchType::MainTypeWildcard => CMimeTypeMatchType::MainTypeWildcard,
+            MimeTypeMatchType::SubTypeWildcard => CMimeTypeMatchType::SubTypeWildcard,
+            MimeTypeMatchType::Exact => CMimeTypeMatchType::Exact,
+        }
+    }
+}
+
+#[cfg(feature = "mime")]
+impl From<Option<MimeTypeMatch>> for CMimeTypeMatch {
+    fn from(m: Option<MimeTypeMatch>) -> Self {
+        let (match_type, q) = c_match_parts(m, CMimeTypeMatchType::NoMatch);
+        CMimeTypeMatch {
+            match_type: match_type as c_int,
+            q,
+        }
+    }
+}
+
+/// Matches an Accept header value against a media type like "image/webp".
+#[cfg(feature = "mime")]
+#[export_name = symbol!("match_mime_type")]
+pub extern "C" fn c_match_mime_type(
+    header_value: *const c_char,
+    header_value_len: usize,
+    mime_type: *const c_char,
+    mime_type_len: usize,
+) -> CMimeTypeMatch {
+    c_match_mime_type_with_options(
+        header_value,
+        header_value_len,
+        mime_type,
+        mime_type_len,
+        ptr::null(),
+    )
+}
+
+/// Like `c_match_mime_type`, with options; NULL `options` means the
+/// defaults.
+#[cfg(feature = "mime")]
+#[export_name = symbol!("match_mime_type_with_options")]
+pub extern "C" fn c_match_mime_type_with_options(
+    header_value: *const c_char,
+    header_value_len: usize,
+    mime_type: *const c_char,
+    mime_type_len: usize,
+    options: *const COptions,
+) -> CMimeTypeMatch {
+    catch_panic(CMimeTypeMatch::ERROR, || {
+        let (Some(header_value), Some(mime_type), Some(options)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(mime_type, mime_type_len),
+                options_from_raw(options),
+            )
+        }) else {
+            return CMimeTypeMatch::ERROR;
+        };
+        match options.matcher() {
+            Some(matcher) => matcher.match_mime_type(header_value, mime_type),
+            None => {
+                match_for_mime_type_with_options(header_value, mime_type, &options.match_options)
+            }
+        }
+        .into()
+    })
+}
+
+/// Like `c_match_mime_type`, but takes NUL-terminated strings.
+#[cfg(feature = "mime")]
+#[export_name = symbol!("match_mime_type_cstr")]
+pub extern "C" fn c_match_mime_type_cstr(
+    header_value: *const c_char,
+    mime_type: *const c_char,
+) -> CMimeTypeMatch {
+    c_match_mime_type(
+        header_value,
+        cstr_len(header_value),
+        mime_type,
+        cstr_len(mime_type),
+    )
+}
+
+/// Like `c_match_mime_type`, but tolerates what browsers get away with, as
+/// `COptions` with `lenient` set does.
+#[cfg(feature = "mime")]
+#[export_name = symbol!("match_mime_type_lenient")]
+pub extern "C" fn c_match_mime_type_lenient(
+    header_value: *const c_char,
+    header_value_len: usize,
+    mime_type: *const c_char,
+    mime_type_len: usize,
+) -> CMimeTypeMatch {
+    c_match_mime_type_with_options(
+        header_value,
+        header_value_len,
+        mime_type,
+        mime_type_len,
+        &COptions::LENIENT,
+    )
+}
+
+/// The result of `c_match_mime_type_v2`: `CMimeTypeMatch` with where the
+/// matching entry is, for rewriting the header value in place.
+#[cfg(feature = "mime")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CMimeTypeMatchV2 {
+    /// A `CMimeTypeMatchType` value.
+    match_type: c_int,
+    q: f64,
+    /// The index of the matching entry in the list, or -1 without a match.
+    entry_index: isize,
+    /// The offset and length of the media range of the matching entry,
+    /// like "image/*", in the header value, or 0 without a match.
+    name_offset: usize,
+    name_len: usize,
+}
+
+/// Like `c_match_mime_type_with_options`, but also tells which entry
+/// matched, and where the header value is malformed through `out_error`.
+#[cfg(feature = "mime")]
+#[export_name = symbol!("match_mime_type_v2")]
+pub extern "C" fn c_match_mime_type_v2(
+    header_value: *const c_char,
+    header_value_len: usize,
+    mime_type: *const c_char,
+    mime_type_len: usize,
+    options: *const COptions,
+    out_error: *mut CParseError,
+) -> CMimeTypeMatchV2 {
+    let error = CMimeTypeMatchV2 {
+        match_type: CMimeTypeMatchType::Error as c_int,
+        q: 0.0,
+        entry_index: -1,
+        name_offset: 0,
+        name_len: 0,
+    };
+    let (result, e) = catch_panic((error, CParseError::PANIC), || {
+        let (Some(header_value), Some(mime_type), Some(options)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(mime_type, mime_type_len),
+                options_from_raw(options),
+            )
+        }) else {
+            return (error, CParseError::INVALID_ARGUMENT);
+        };
+        let explanation = explain_with_options(header_value, &options, |header_value, options| {
+            explain_mime_type(header_value, mime_type, options)
+        });
+        let CMimeTypeMatch { match_type, q } = explanation.result.into();
+        let (entry_index, name_offset, name_len) = winner_span(&explanation);
+        let result = CMimeTypeMatchV2 {
+            match_type,
+            q,
+            entry_index,
+            name_offset,
+            name_len,
+        };
+        (
+            result,
+            CParseError::from_error_offset(explanation.error_offset),
+        )
+    });
+    set_error(out_error, e);
+    result
+}
+
+/// Returns -1, 0 or 1 as `m1` ranks below, equal to or above `m2`, or
+/// C_CMP_INVALID if a match type is not a `CMimeTypeMatchType` value.
+#[cfg(feature = "mime")]
+#[export_name = symbol!("cmp_mime_type_match")]
+pub extern "C" fn c_cmp_mime_type_match(m1: CMimeTypeMatch, m2: CMimeTypeMatch) -> c_int {
+    catch_panic(C_CMP_INVALID, || {
+        let (Some(t1), Some(t2)) = (
+            CMimeTypeMatchType::from_raw(m1.match_type),
+            CMimeTypeMatchType::from_raw(m2.match_type),
+        ) else {
+            return C_CMP_INVALID;
+        };
+        cmp_c_match((t1, m1.q), (t2, m2.q), CMimeTypeMatchType::NoMatch)
+    })
+}
+
+/// How `c_match_language` matched, ranked by value.
+#[cfg(feature = "language")]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CLanguageMatchType {
+    /// Returned when a pointer argument is null or matching panicked.
+    Error = -1,
+    NoMatch = 0,
+    Wildcard = 1,
+    /// The range is a prefix of the tag, like "en" for "en-US".
+    Prefix = 2,
+    Exact = 3,
+}
+
+/// The result of `c_match_language`.
+#[cfg(feature = "language")]
+#[repr(C)]
+pub struct CLanguageMatch {
+    /// A `CLanguageMatchType` value, an int like the match types of the
+    /// other results.
+    match_type: c_int,
+    /// The q-value of the matched entry, or 0 without a match.
+    q: f64,
+}
+
+#[cfg(feature = "language")]
+impl CLanguageMatch {
+    const ERROR: Self = Self {
+        match_type: CLanguageMatchType::Error as c_int,
+        q: 0.0,
+    };
+}
+
+#[cfg(feature = "language")]
+impl From<LanguageMatchType> for CLanguageMatchType {
+    fn from(match_type: LanguageMatchType) -> Self {
+        match match_type {
+            LanguageMatchType::Wildcard => CLanguageMatchType::Wildcard,
+            LanguageMatchType::Prefix => CLanguageMatchType::Prefix,
+            LanguageMatchType::Exact => CLanguageMatchType::Exact,
+        }
+    }
+}
+
+#[cfg(feature = "language")]
+impl From<Option<LanguageMatch>> for CLanguageMatch {
+    fn from(m: Option<LanguageMatch>) -> Self {
+        let (match_type, q) = c_match_parts(m, CLanguageMatchType::NoMatch);
+        CLanguageMatch {
+            match_type: match_type as c_int,
+            q,
+        }
+    }
+}
+
+/// Matches an Accept-Language header value against a language tag like
+/// "en-US".
+#[cfg(feature = "language")]
+#[export_name = symbol!("match_language")]
+pub extern "C" fn c_match_language(
+    header_value: *const c_char,
+    header_value_len: usize,
+    language: *const c_char,
+    language_len: usize,
+) -> CLanguageMatch {
+    catch_panic(CLanguageMatch::ERROR, || {
+        let (Some(header_value), Some(language)) = (unsafe {
+            (
+                bytes_from_raw(header_value, header_value_len),
+                bytes_from_raw(language, language_len),
+            )
+        }) else {
+            return CLanguageMatch::ERROR;
+        };
+        match_for_language(header_value, language).into()
+    })
+}
 
+/// A string laid out like nginx's `ngx_str_t`, so a module can pass its
+/// arrays of them, like a list of supported codings, with a cast.
+#[cfg(feature = "encoding")]
 #[repr(C)]
-pub struct CEncodingMatch {
-    match_type: i32,
-    q: f64,
+#[derive(Clone, Copy)]
+pub struct CNgxStr {
+    len: usize,
+    data: *const u8,
 }
 
-#[no_mangle]
-pub extern "C" fn c_match_encoding(
-    header_value: *const c_char,
+/// Stores `q` in thousandths in `*out_q_millis` unless it is NULL.
+#[cfg(any(feature = "encoding", feature = "mime"))]
+fn set_q_millis(out_q_millis: *mut u16, q: QValue) {
+    if let Some(out_q_millis) = unsafe { out_q_millis.as_mut() } {
+        *out_q_millis = q.as_millis();
+    }
+}
+
+/// Like `c_match_encoding`, but takes the `data` and `len` of `ngx_str_t`
+/// values and stores the q-value in thousandths in `*out_q_millis`, 0
+/// without a match. Nothing is allocated.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("ngx_match_encoding")]
+pub extern "C" fn c_ngx_match_encoding(
+    header_value: *const u8,
     header_value_len: usize,
-    encoding: *const c_char,
+    encoding: *const u8,
     encoding_len: usize,
-) -> CEncodingMatch {
-    let header_value =
-        unsafe { slice::from_raw_parts(header_value as *const u8, header_value_len) };
-    let encoding = unsafe { slice::from_raw_parts(encoding as *const u8, encoding_len) };
-    match match_for_encoding(header_value, encoding) {
-        Some(r) => CEncodingMatch {
-            match_type: match r.match_type {
-                EncodingMatchType::Wildcard => C_ENCODING_MATCH_TYPE_WILDCARD,
-                EncodingMatchType::Exact => C_ENCODING_MATCH_TYPE_EXACT,
-            },
-            q: r.q.into(),
-        },
-        None => CEncodingMatch {
-            match_type: C_ENCODING_MATCH_TYPE_NO_MATCH,
-            q: 0.0,
-        },
-    }
+    out_q_millis: *mut u16,
+) -> CEncodingMatchType {
+    set_q_millis(out_q_millis, QValue::ZERO);
+    catch_panic(CEncodingMatchType::Error, || {
+        let (Some(header_value), Some(encoding)) = (unsafe {
+            (
+                bytes_from_raw(header_value as *const c_char, header_value_len),
+                bytes_from_raw(encoding as *const c_char, encoding_len),
+            )
+        }) else {
+            return CEncodingMatchType::Error;
+        };
+        let m = match_for_encoding_with_options(header_value, encoding, &MatchOptions::default());
+        if let Some(m) = m {
+            set_q_millis(out_q_millis, m.q);
+        }
+        c_match_parts(m, CEncodingMatchType::NoMatch).0
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn c_cmp_encoding_match(m1: CEncodingMatch, m2: CEncodingMatch) -> c_int {
-    if m1.match_type < m2.match_type {
-        -1
-    } else if m1.match_type > m2.match_type {
-        1
-    } else if m1.match_type != C_ENCODING_MATCH_TYPE_NO_MATCH {
-        if m1.q < m2.q {
-            -1
-        } else if m1.q > m2.q {
-            1
+/// Like `c_negotiate_encoding`, but takes the `data` and `len` of an
+/// `ngx_str_t` header value and `n` supported codings as `ngx_str_t`
+/// values, and stores the q-value of the chosen coding in thousandths in
+/// `*out_q_millis`, 0 without a match. Nothing is allocated.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("ngx_negotiate_encoding")]
+pub extern "C" fn c_ngx_negotiate_encoding(
+    header_value: *const u8,
+    header_value_len: usize,
+    supported: *const CNgxStr,
+    n: usize,
+    out_q_millis: *mut u16,
+) -> isize {
+    set_q_millis(out_q_millis, QValue::ZERO);
+    catch_panic(C_NEGOTIATE_ERROR, || {
+        let Some(header_value) =
+            (unsafe { bytes_from_raw(header_value as *const c_char, header_value_len) })
+        else {
+            return C_NEGOTIATE_ERROR;
+        };
+        let supported = if n == 0 {
+            &[][..]
+        } else if supported.is_null() {
+            return C_NEGOTIATE_ERROR;
         } else {
-            0
+            unsafe { slice::from_raw_parts(supported, n) }
+        };
+        if supported.iter().any(|s| s.data.is_null()) {
+            return C_NEGOTIATE_ERROR;
         }
-    } else {
-        0
-    }
+        let targets = supported.iter().map(|s| {
+            Target::new(
+                unsafe { bytes_from_raw(s.data as *const c_char, s.len) }.unwrap_or_default(),
+            )
+        });
+        match negotiate(header_value, targets, &MatchOptions::default()) {
+            Some((i, m)) => {
+                set_q_millis(out_q_millis, m.q);
+                i as isize
+            }
+            None => C_NEGOTIATE_NO_MATCH,
+        }
+    })
 }
 
-pub const C_MIME_TYPE_MATCH_TYPE_NO_MATCH: i32 = 0;
-pub const C_MIME_TYPE_MATCH_TYPE_MAIN_TYPE_WILDCARD: i32 = 1;
-pub const C_MIME_TYPE_MATCH_TYPE_SUB_TYPE_WILDCARD: i32 = 2;
-pub const C_MIME_TYPE_MATCH_TYPE_EXACT: i32 = 3;
-
-#[repr(C)]
-pub struct CMimeTypeMatch {
-    match_type: i32,
-    q: f64,
+/// Like `c_negotiate_encoding`, but shaped for a Varnish VMOD: it takes
+/// NUL-terminated strings, with NULL `header_value` for an unset header,
+/// which accepts nothing, and copies the chosen coding with a NUL into
+/// `out_buf`, such as workspace reserved by the VMOD. `out_buf` may be NULL
+/// to get the index alone.
+#[cfg(feature = "encoding")]
+#[export_name = symbol!("vmod_negotiate_encoding")]
+pub extern "C" fn c_vmod_negotiate_encoding(
+    header_value: *const c_char,
+    supported: *const *const c_char,
+    n: usize,
+    out_buf: *mut c_char,
+    out_cap: usize,
+) -> isize {
+    catch_panic(C_NEGOTIATE_ERROR, || {
+        if header_value.is_null() {
+            return C_NEGOTIATE_NO_MATCH;
+        }
+        let header_value = unsafe { CStr::from_ptr(header_value) }.to_bytes();
+        let supported = if n == 0 {
+            &[][..]
+        } else if supported.is_null() {
+            return C_NEGOTIATE_ERROR;
+        } else {
+            unsafe { slice::from_raw_parts(supported, n) }
+        };
+        if supported.iter().any(|p| p.is_null()) {
+            return C_NEGOTIATE_ERROR;
+        }
+        let coding = |p: &*const c_char| unsafe { CStr::from_ptr(*p) }.to_bytes();
+        let targets = supported.iter().map(|p| Target::new(coding(p)));
+        let Some((i, _)) = negotiate(header_value, targets, &MatchOptions::default()) else {
+            return C_NEGOTIATE_NO_MATCH;
+        };
+        if !out_buf.is_null() {
+            let chosen = coding(&supported[i]);
+            if chosen.len() >= out_cap {
+                return C_NEGOTIATE_BUFFER_TOO_SMALL;
+            }
+            unsafe {
+                ptr::copy_nonoverlapping(chosen.as_ptr(), out_buf as *mut u8, chosen.len());
+                *out_buf.add(chosen.len()) = 0;
+            }
+        }
+        i as isize
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn c_match_mime_type(
-    header_value: *const c_char,
+/// Like `c_match_mime_type`, but takes the `data` and `len` of `ngx_str_t`
+/// values and stores the q-value in thousandths in `*out_q_millis`, 0
+/// without a match. Nothing is allocated.
+#[cfg(feature = "mime")]
+#[export_name = symbol!("ngx_match_mime_type")]
+pub extern "C" fn c_ngx_match_mime_type(
+    header_value: *const u8,
     header_value_len: usize,
-    mime_type: *const c_char,
+    mime_type: *const u8,
     mime_type_len: usize,
-) -> CMimeTypeMatch {
-    let header_value =
-        unsafe { slice::from_raw_parts(header_value as *const u8, header_value_len) };
-    let mime_type = unsafe { slice::from_raw_parts(mime_type as *const u8, mime_type_len) };
-    match match_for_mime_type(header_value, mime_type) {
-        Some(r) => CMimeTypeMatch {
-            match_type: match r.match_type {
-                MimeTypeMatchType::MainTypeWildcard => C_MIME_TYPE_MATCH_TYPE_MAIN_TYPE_WILDCARD,
-                MimeTypeMatchType::SubTypeWildcard => C_MIME_TYPE_MATCH_TYPE_SUB_TYPE_WILDCARD,
-                MimeTypeMatchType::Exact => C_MIME_TYPE_MATCH_TYPE_EXACT,
-            },
-            q: r.q.into(),
-        },
-        None => CMimeTypeMatch {
-            match_type: C_MIME_TYPE_MATCH_TYPE_NO_MATCH,
-            q: 0.0,
-        },
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn c_cmp_mime_type_match(m1: CMimeTypeMatch, m2: CMimeTypeMatch) -> c_int {
-    if m1.match_type < m2.match_type {
-        -1
-    } else if m1.match_type > m2.match_type {
-        1
-    } else if m1.match_type != C_MIME_TYPE_MATCH_TYPE_NO_MATCH {
-        if m1.q < m2.q {
-            -1
-        } else if m1.q > m2.q {
-            1
-        } else {
-            0
+    out_q_millis: *mut u16,
+) -> CMimeTypeMatchType {
+    set_q_millis(out_q_millis, QValue::ZERO);
+    catch_panic(CMimeTypeMatchType::Error, || {
+        let (Some(header_value), Some(mime_type)) = (unsafe {
+            (
+                bytes_from_raw(header_value as *const c_char, header_value_len),
+                bytes_from_raw(mime_type as *const c_char, mime_type_len),
+            )
+        }) else {
+            return CMimeTypeMatchType::Error;
+        };
+        let m = match_for_mime_type_with_options(header_value, mime_type, &MatchOptions::default());
+        if let Some(m) = m {
+            set_q_millis(out_q_millis, m.q);
         }
-    } else {
-        0
-    }
+        c_match_parts(m, CMimeTypeMatchType::NoMatch).0
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use std::ffi::CString;
+    use std::{ffi::CString, ptr};
 
     use super::*;
 
+    #[test]
+    fn test_c_is_panic_safe() {
+        assert_eq!(1, c_is_panic_safe());
+    }
+
+    #[test]
+    fn test_c_qvalue() {
+        let mut millis = 0;
+        assert_eq!(
+            1,
+            c_qvalue_from_string(b"0.5".as_ptr() as *const c_char, 3, &mut millis)
+        );
+        assert_eq!(500, millis);
+        assert_eq!(
+            1,
+            c_qvalue_from_string(b"1.000".as_ptr() as *const c_char, 5, &mut millis)
+        );
+        assert_eq!(1000, millis);
+        assert_eq!(
+            0,
+            c_qvalue_from_string(b"1.5".as_ptr() as *const c_char, 3, &mut millis)
+        );
+        assert_eq!(
+            0,
+            c_qvalue_from_string(b"0.1234".as_ptr() as *const c_char, 6, &mut millis)
+        );
+        assert_eq!(0, c_qvalue_from_string(ptr::null(), 0, &mut millis));
+        assert_eq!(1000, millis);
+
+        assert_eq!(0.5, c_qvalue_to_double(500));
+        assert_eq!(1.0, c_qvalue_to_double(1000));
+        assert!(c_qvalue_to_double(1001).is_nan());
+    }
+
+    #[test]
+    fn test_c_version() {
+        assert_eq!(ACCEPT_ENCODING_ABI_VERSION, c_abi_version());
+        let version = unsafe { CStr::from_ptr(c_version()) };
+        assert_eq!(env!("CARGO_PKG_VERSION"), version.to_str().unwrap());
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(1, catch_panic(-1, || 1));
+        assert_eq!(-1, catch_panic(-1, || panic!("in matcher")));
+    }
+
+    #[cfg(feature = "encoding")]
     #[test]
     fn test_c_match_encoding() {
         {
@@ -135,7 +1518,7 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_ENCODING_MATCH_TYPE_EXACT, m.match_type);
+            assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
             assert_eq!(1.0, m.q);
         }
         {
@@ -147,7 +1530,7 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_ENCODING_MATCH_TYPE_WILDCARD, m.match_type);
+            assert_eq!(CEncodingMatchType::Wildcard as c_int, m.match_type);
             assert_eq!(1.0, m.q);
         }
         {
@@ -159,22 +1542,544 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_ENCODING_MATCH_TYPE_NO_MATCH, m.match_type);
+            assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
             assert_eq!(0.0, m.q);
         }
+        {
+            let encoding = CString::new("br").unwrap();
+            let m = c_match_encoding(ptr::null(), 0, encoding.as_ptr(), 2);
+            assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+            let m = c_match_encoding(encoding.as_ptr(), 2, ptr::null(), 2);
+            assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+        }
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_match_encoding_cstr() {
+        let header_value = CString::new("gzip;q=0.5, br").unwrap();
+        let encoding = CString::new("gzip").unwrap();
+        let m = c_match_encoding_cstr(header_value.as_ptr(), encoding.as_ptr());
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+
+        let m = c_match_encoding_cstr(header_value.as_ptr(), ptr::null());
+        assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_effective_encoding_q() {
+        let q = |header_value: &str, encoding: &str| {
+            c_effective_encoding_q(
+                header_value.as_ptr() as *const c_char,
+                header_value.len(),
+                encoding.as_ptr() as *const c_char,
+                encoding.len(),
+            )
+        };
+        assert_eq!(500, q("gzip;q=0.5, br", "gzip"));
+        assert_eq!(1000, q("gzip;q=0.5, br", "identity"));
+        assert_eq!(-1, q("gzip, *;q=0", "identity"));
+        assert_eq!(-1, q("gzip;q=0", "gzip"));
+        assert_eq!(-1, q("gzip;q=2", "gzip"));
+        assert_eq!(-2, c_effective_encoding_q(ptr::null(), 0, ptr::null(), 0));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_negotiate_encoding() {
+        let supported: [&[u8]; 3] = [b"zstd", b"br", b"gzip"];
+        let ptrs = supported.map(|s| s.as_ptr() as *const c_char);
+        let lens = supported.map(|s| s.len());
+        let negotiate = |header_value: &str| {
+            c_negotiate_encoding(
+                header_value.as_ptr() as *const c_char,
+                header_value.len(),
+                ptrs.as_ptr(),
+                lens.as_ptr(),
+                ptrs.len(),
+            )
+        };
+        assert_eq!(1, negotiate("gzip, deflate, br"));
+        assert_eq!(0, negotiate("*"));
+        assert_eq!(2, negotiate("x-gzip, br;q=0.5"));
+        assert_eq!(C_NEGOTIATE_NO_MATCH, negotiate("deflate"));
+        assert_eq!(C_NEGOTIATE_NO_MATCH, negotiate("br;q=0, gzip;q=0, *;q=0"));
+        assert_eq!(C_NEGOTIATE_NO_MATCH, negotiate("gzip;q=2"));
+
+        assert_eq!(
+            C_NEGOTIATE_NO_MATCH,
+            c_negotiate_encoding(
+                b"br".as_ptr() as *const c_char,
+                2,
+                ptr::null(),
+                ptr::null(),
+                0
+            )
+        );
+        assert_eq!(
+            C_NEGOTIATE_ERROR,
+            c_negotiate_encoding(
+                b"br".as_ptr() as *const c_char,
+                2,
+                ptr::null(),
+                ptr::null(),
+                1
+            )
+        );
+        let with_null = [ptrs[0], ptr::null()];
+        assert_eq!(
+            C_NEGOTIATE_ERROR,
+            c_negotiate_encoding(
+                b"br".as_ptr() as *const c_char,
+                2,
+                with_null.as_ptr(),
+                lens.as_ptr(),
+                2
+            )
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_accept_encoding_parse() {
+        let header_value = "gzip;q=0.5, br";
+        let parsed =
+            c_accept_encoding_parse(header_value.as_ptr() as *const c_char, header_value.len());
+        assert!(!parsed.is_null());
+        let m = c_accept_encoding_match(parsed, b"gzip".as_ptr() as *const c_char, 4);
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+        let m = c_accept_encoding_match(parsed, b"zstd".as_ptr() as *const c_char, 4);
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        c_accept_encoding_free(parsed);
+
+        let header_value = "gzip;q=2";
+        assert!(c_accept_encoding_parse(
+            header_value.as_ptr() as *const c_char,
+            header_value.len()
+        )
+        .is_null());
+        assert!(c_accept_encoding_parse(ptr::null(), 0).is_null());
+        let m = c_accept_encoding_match(ptr::null(), b"gzip".as_ptr() as *const c_char, 4);
+        assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+        c_accept_encoding_free(ptr::null_mut());
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_accept_encoding_entries() {
+        let header_value = "gzip;q=0.5, br;a=\"b;c\"";
+        let parsed =
+            c_accept_encoding_parse(header_value.as_ptr() as *const c_char, header_value.len());
+        assert_eq!(2, c_accept_encoding_entry_count(parsed));
+        let mut entry = CAcceptEncodingEntry {
+            name: ptr::null(),
+            name_len: 0,
+            q_millis: 0,
+            q: 0.0,
+            param_count: 0,
+        };
+        assert_eq!(1, c_accept_encoding_entry_at(parsed, 1, &mut entry));
+        let name = unsafe { slice::from_raw_parts(entry.name as *const u8, entry.name_len) };
+        assert_eq!(b"br", name);
+        assert_eq!((1000, 1.0, 1), (entry.q_millis, entry.q, entry.param_count));
+        assert_eq!(1, c_accept_encoding_entry_at(parsed, 0, &mut entry));
+        assert_eq!((500, 0.5, 0), (entry.q_millis, entry.q, entry.param_count));
+        assert_eq!(0, c_accept_encoding_entry_at(parsed, 2, &mut entry));
+        assert_eq!(0, c_accept_encoding_entry_at(parsed, 0, ptr::null_mut()));
+        c_accept_encoding_free(parsed);
+
+        assert_eq!(0, c_accept_encoding_entry_count(ptr::null()));
+        assert_eq!(0, c_accept_encoding_entry_at(ptr::null(), 0, &mut entry));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_with_options() {
+        let lenient = COptions::LENIENT;
+        let header_value = "gzip;q=0.5000, br;q=2, zstd";
+        let value = header_value.as_ptr() as *const c_char;
+        let len = header_value.len();
+        let gzip = b"gzip".as_ptr() as *const c_char;
+
+        let m = c_match_encoding_with_options(value, len, gzip, 4, &lenient);
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+        let m = c_match_encoding_with_options(value, len, gzip, 4, ptr::null());
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+
+        let supported = [
+            b"br".as_ptr() as *const c_char,
+            b"zstd".as_ptr() as *const c_char,
+        ];
+        let lens = [2, 4];
+        assert_eq!(
+            1,
+            c_negotiate_encoding_with_options(
+                value,
+                len,
+                supported.as_ptr(),
+                lens.as_ptr(),
+                2,
+                &lenient
+            )
+        );
+
+        let parsed = c_accept_encoding_parse_with_options(value, len, &lenient);
+        assert_eq!(2, c_accept_encoding_entry_count(parsed));
+        c_accept_encoding_free(parsed);
+        assert!(c_accept_encoding_parse_with_options(value, len, ptr::null()).is_null());
+
+        let reject = COptions {
+            registry_check: C_REGISTRY_CHECK_REJECT,
+            ..COptions::DEFAULT
+        };
+        let header_value = "x-custom";
+        let m = c_match_encoding_with_options(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            &reject,
+        );
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_options() {
+        let header_value = "gzip;q=0.5, br, gzip;q=0.8";
+        let value = header_value.as_ptr() as *const c_char;
+        let len = header_value.len();
+        let gzip = b"gzip".as_ptr() as *const c_char;
+
+        for (tie_break, q) in [
+            (C_TIE_BREAK_HIGHEST_Q, 0.8),
+            (C_TIE_BREAK_FIRST_WINS, 0.5),
+            (C_TIE_BREAK_LAST_WINS, 0.8),
+        ] {
+            let options = COptions {
+                tie_break,
+                ..COptions::DEFAULT
+            };
+            let m = c_match_encoding_with_options(value, len, gzip, 4, &options);
+            assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+            assert_eq!(q, m.q);
+        }
+
+        let max_entries = COptions {
+            max_entries: 2,
+            ..COptions::DEFAULT
+        };
+        let m = c_match_encoding_with_options(value, len, gzip, 4, &max_entries);
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        let m = c_match_encoding_with_options(value, 15, gzip, 4, &max_entries);
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert!(c_accept_encoding_parse_with_options(value, len, &max_entries).is_null());
+        let supported = [gzip];
+        assert_eq!(
+            C_NEGOTIATE_NO_MATCH,
+            c_negotiate_encoding_with_options(value, len, supported.as_ptr(), &4, 1, &max_entries)
+        );
+        let max_len = COptions {
+            max_len: 4,
+            ..COptions::DEFAULT
+        };
+        let m = c_match_encoding_with_options(value, len, gzip, 4, &max_len);
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        let m = c_match_encoding_with_options(value, 4, gzip, 4, &max_len);
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+
+        for options in [
+            COptions {
+                registry_check: 3,
+                ..COptions::DEFAULT
+            },
+            COptions {
+                tie_break: -1,
+                ..COptions::DEFAULT
+            },
+        ] {
+            let m = c_match_encoding_with_options(value, len, gzip, 4, &options);
+            assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+            assert_eq!(
+                C_NEGOTIATE_ERROR,
+                c_negotiate_encoding_with_options(value, len, supported.as_ptr(), &4, 1, &options)
+            );
+            assert!(c_accept_encoding_parse_with_options(value, len, &options).is_null());
+        }
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_rewrite() {
+        let header_value = "gzip, br;q=0.5, zstd";
+        let mut buf = [0u8; 16];
+        let mut len = 0;
+        let ret = c_remove_encoding(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"br".as_ptr() as *const c_char,
+            2,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            &mut len,
+        );
+        assert_eq!(0, ret);
+        assert_eq!(b"gzip, zstd", &buf[..len]);
+        let refusing = "gzip;q=0, *";
+        let ret = c_remove_encoding(
+            refusing.as_ptr() as *const c_char,
+            refusing.len(),
+            b"gzip".as_ptr() as *const c_char,
+            4,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            &mut len,
+        );
+        assert_eq!(0, ret);
+        assert_eq!(b"gzip;q=0, *", &buf[..len]);
+
+        let ret = c_add_encoding(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"deflate".as_ptr() as *const c_char,
+            7,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            &mut len,
+        );
+        assert_eq!(C_REWRITE_BUFFER_TOO_SMALL, ret);
+        assert_eq!(29, len);
+        let ret = c_add_encoding(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"deflate".as_ptr() as *const c_char,
+            7,
+            ptr::null_mut(),
+            0,
+            &mut len,
+        );
+        assert_eq!(C_REWRITE_BUFFER_TOO_SMALL, ret);
+        assert_eq!(29, len);
+
+        let header_value = "gzip;q=2";
+        let ret = c_remove_encoding(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"br".as_ptr() as *const c_char,
+            2,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            &mut len,
+        );
+        assert_eq!(C_REWRITE_ERROR, ret);
+        let ret = c_remove_encoding(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"br".as_ptr() as *const c_char,
+            2,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            ptr::null_mut(),
+        );
+        assert_eq!(C_REWRITE_ERROR, ret);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_validate_accept_encoding() {
+        let mut error = CParseError::PANIC;
+        let header_value = "gzip, br;q=0.5";
+        assert_eq!(
+            0,
+            c_validate_accept_encoding(
+                header_value.as_ptr() as *const c_char,
+                header_value.len(),
+                &mut error
+            )
+        );
+        assert_eq!(CParseError::NONE, error);
+        let header_value = "gzip, br;q=2";
+        assert_eq!(
+            CParseErrorCode::MalformedHeader as c_int,
+            c_validate_accept_encoding(
+                header_value.as_ptr() as *const c_char,
+                header_value.len(),
+                &mut error
+            )
+        );
+        assert_eq!(11, error.offset);
+        assert_eq!(
+            CParseErrorCode::InvalidArgument as c_int,
+            c_validate_accept_encoding(ptr::null(), 0, ptr::null_mut())
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_match_encoding_lenient() {
+        let header_value = "gzip;q=0.5000, br;q=2";
+        let m = c_match_encoding_lenient(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"gzip".as_ptr() as *const c_char,
+            4,
+        );
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+        let m = c_match_encoding(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"gzip".as_ptr() as *const c_char,
+            4,
+        );
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_match_encoding_v2() {
+        let header_value = "gzip;q=0.5, BR ;q=0.8, *;q=0.1";
+        let m = c_match_encoding_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"br".as_ptr() as *const c_char,
+            2,
+            ptr::null(),
+            ptr::null_mut(),
+        );
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert_eq!(0.8, m.q);
+        assert_eq!((1, 12, 2), (m.entry_index, m.name_offset, m.name_len));
+        let m = c_match_encoding_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"zstd".as_ptr() as *const c_char,
+            4,
+            ptr::null(),
+            ptr::null_mut(),
+        );
+        assert_eq!(CEncodingMatchType::Wildcard as c_int, m.match_type);
+        assert_eq!((2, 23, 1), (m.entry_index, m.name_offset, m.name_len));
+
+        let header_value = "gzip";
+        let m = c_match_encoding_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"br".as_ptr() as *const c_char,
+            2,
+            ptr::null(),
+            ptr::null_mut(),
+        );
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        assert_eq!((-1, 0, 0), (m.entry_index, m.name_offset, m.name_len));
+
+        let mut error = CParseError::PANIC;
+        let header_value = "gzip, br;q=2";
+        let m = c_match_encoding_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"br".as_ptr() as *const c_char,
+            2,
+            ptr::null(),
+            &mut error,
+        );
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        assert_eq!(CParseErrorCode::MalformedHeader, error.code);
+        assert_eq!(11, error.offset);
+        let m = c_match_encoding_v2(
+            header_value.as_ptr() as *const c_char,
+            6,
+            b"gzip".as_ptr() as *const c_char,
+            4,
+            ptr::null(),
+            &mut error,
+        );
+        assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+        assert_eq!(CParseError::NONE, error);
+        let m = c_match_encoding_v2(
+            ptr::null(),
+            0,
+            b"br".as_ptr() as *const c_char,
+            2,
+            ptr::null(),
+            &mut error,
+        );
+        assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+        assert_eq!(-1, m.entry_index);
+        assert_eq!(CParseError::INVALID_ARGUMENT, error);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_match_encoding_v2_with_options() {
+        let header_value = "gzip;q=0.5, br, gzip;q=0.8";
+        let value = header_value.as_ptr() as *const c_char;
+        let len = header_value.len();
+        let gzip = b"gzip".as_ptr() as *const c_char;
+        let mut error = CParseError::PANIC;
+
+        for (tie_break, q, entry_index, name_offset) in [
+            (C_TIE_BREAK_HIGHEST_Q, 0.8, 2, 16),
+            (C_TIE_BREAK_FIRST_WINS, 0.5, 0, 0),
+            (C_TIE_BREAK_LAST_WINS, 0.8, 2, 16),
+        ] {
+            let options = COptions {
+                tie_break,
+                ..COptions::DEFAULT
+            };
+            let m = c_match_encoding_v2(value, len, gzip, 4, &options, &mut error);
+            assert_eq!(CEncodingMatchType::Exact as c_int, m.match_type);
+            assert_eq!(q, m.q);
+            assert_eq!(
+                (entry_index, name_offset, 4),
+                (m.entry_index, m.name_offset, m.name_len)
+            );
+            assert_eq!(CParseError::NONE, error);
+        }
+
+        let options = COptions {
+            max_entries: 2,
+            ..COptions::DEFAULT
+        };
+        let m = c_match_encoding_v2(value, len, gzip, 4, &options, &mut error);
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        assert_eq!(-1, m.entry_index);
+        assert_eq!(CParseErrorCode::MalformedHeader, error.code);
+        assert_eq!(16, error.offset);
+        let options = COptions {
+            max_len: 4,
+            ..COptions::DEFAULT
+        };
+        let m = c_match_encoding_v2(value, len, gzip, 4, &options, &mut error);
+        assert_eq!(CEncodingMatchType::NoMatch as c_int, m.match_type);
+        assert_eq!(CParseErrorCode::MalformedHeader, error.code);
+        assert_eq!(4, error.offset);
+
+        let options = COptions {
+            tie_break: 3,
+            ..COptions::DEFAULT
+        };
+        let m = c_match_encoding_v2(value, len, gzip, 4, &options, &mut error);
+        assert_eq!(CEncodingMatchType::Error as c_int, m.match_type);
+        assert_eq!(CParseError::INVALID_ARGUMENT, error);
     }
 
+    #[cfg(feature = "encoding")]
     #[test]
     fn test_c_cmp_encoding_match() {
         assert_eq!(
             -1,
             c_cmp_encoding_match(
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_NO_MATCH,
+                    match_type: CEncodingMatchType::NoMatch as c_int,
                     q: 0.0,
                 },
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_WILDCARD,
+                    match_type: CEncodingMatchType::Wildcard as c_int,
                     q: 0.0,
                 }
             )
@@ -184,11 +2089,11 @@ mod tests {
             1,
             c_cmp_encoding_match(
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 0.0,
                 },
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_NO_MATCH,
+                    match_type: CEncodingMatchType::NoMatch as c_int,
                     q: 0.0,
                 }
             )
@@ -198,11 +2103,11 @@ mod tests {
             -1,
             c_cmp_encoding_match(
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 0.0,
                 },
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 0.1,
                 }
             )
@@ -212,11 +2117,11 @@ mod tests {
             0,
             c_cmp_encoding_match(
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 0.8,
                 },
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 0.8,
                 }
             )
@@ -226,11 +2131,11 @@ mod tests {
             1,
             c_cmp_encoding_match(
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 1.0,
                 },
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_EXACT,
+                    match_type: CEncodingMatchType::Exact as c_int,
                     q: 0.9,
                 }
             )
@@ -240,17 +2145,28 @@ mod tests {
             0,
             c_cmp_encoding_match(
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_NO_MATCH,
+                    match_type: CEncodingMatchType::NoMatch as c_int,
                     q: 0.0,
                 },
                 CEncodingMatch {
-                    match_type: C_ENCODING_MATCH_TYPE_NO_MATCH,
+                    match_type: CEncodingMatchType::NoMatch as c_int,
                     q: 1.0,
                 }
             )
         );
+
+        let exact = CEncodingMatch {
+            match_type: CEncodingMatchType::Exact as c_int,
+            q: 1.0,
+        };
+        for match_type in [-2, 3] {
+            let invalid = CEncodingMatch { match_type, q: 1.0 };
+            assert_eq!(C_CMP_INVALID, c_cmp_encoding_match(exact, invalid));
+            assert_eq!(C_CMP_INVALID, c_cmp_encoding_match(invalid, exact));
+        }
     }
 
+    #[cfg(feature = "mime")]
     #[test]
     fn test_c_match_mime_type() {
         {
@@ -262,7 +2178,7 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_MIME_TYPE_MATCH_TYPE_EXACT, m.match_type);
+            assert_eq!(CMimeTypeMatchType::Exact as c_int, m.match_type);
             assert_eq!(1.0, m.q);
         }
         {
@@ -274,7 +2190,7 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_MIME_TYPE_MATCH_TYPE_SUB_TYPE_WILDCARD, m.match_type);
+            assert_eq!(CMimeTypeMatchType::SubTypeWildcard as c_int, m.match_type);
             assert_eq!(1.0, m.q);
         }
         {
@@ -286,7 +2202,7 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_MIME_TYPE_MATCH_TYPE_MAIN_TYPE_WILDCARD, m.match_type);
+            assert_eq!(CMimeTypeMatchType::MainTypeWildcard as c_int, m.match_type);
             assert_eq!(1.0, m.q);
         }
         {
@@ -298,21 +2214,135 @@ mod tests {
                 encoding.as_ptr(),
                 encoding.as_bytes().len(),
             );
-            assert_eq!(C_ENCODING_MATCH_TYPE_NO_MATCH, m.match_type);
+            assert_eq!(CMimeTypeMatchType::NoMatch as c_int, m.match_type);
             assert_eq!(0.0, m.q);
         }
+        {
+            let mime_type = CString::new("image/webp").unwrap();
+            let m = c_match_mime_type(ptr::null(), 0, mime_type.as_ptr(), 10);
+            assert_eq!(CMimeTypeMatchType::Error as c_int, m.match_type);
+            let m = c_match_mime_type(mime_type.as_ptr(), 10, ptr::null(), 0);
+            assert_eq!(CMimeTypeMatchType::Error as c_int, m.match_type);
+        }
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_c_match_mime_type_cstr() {
+        let header_value = CString::new("image/*;q=0.5").unwrap();
+        let mime_type = CString::new("image/webp").unwrap();
+        let m = c_match_mime_type_cstr(header_value.as_ptr(), mime_type.as_ptr());
+        assert_eq!(CMimeTypeMatchType::SubTypeWildcard as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+
+        let m = c_match_mime_type_cstr(ptr::null(), mime_type.as_ptr());
+        assert_eq!(CMimeTypeMatchType::Error as c_int, m.match_type);
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_c_match_mime_type_with_options() {
+        let lenient = COptions::LENIENT;
+        let header_value = "text/html;q=2, image/*;q=0.5";
+        let m = c_match_mime_type_with_options(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"image/webp".as_ptr() as *const c_char,
+            10,
+            &lenient,
+        );
+        assert_eq!(CMimeTypeMatchType::SubTypeWildcard as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_c_match_mime_type_lenient() {
+        let header_value = "text/html;q=2, image/*;q=0.5";
+        let m = c_match_mime_type_lenient(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"image/webp".as_ptr() as *const c_char,
+            10,
+        );
+        assert_eq!(CMimeTypeMatchType::SubTypeWildcard as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_c_match_mime_type_v2() {
+        let mut error = CParseError::NONE;
+        let header_value = "text/html, image/*;q=0.8, */*;q=0.1";
+        let m = c_match_mime_type_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"image/webp".as_ptr() as *const c_char,
+            10,
+            ptr::null(),
+            ptr::null_mut(),
+        );
+        assert_eq!(CMimeTypeMatchType::SubTypeWildcard as c_int, m.match_type);
+        assert_eq!(0.8, m.q);
+        assert_eq!((1, 11, 7), (m.entry_index, m.name_offset, m.name_len));
+        let m = c_match_mime_type_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            ptr::null(),
+            0,
+            ptr::null(),
+            &mut error,
+        );
+        assert_eq!(CMimeTypeMatchType::Error as c_int, m.match_type);
+        assert_eq!(-1, m.entry_index);
+        assert_eq!(CParseError::INVALID_ARGUMENT, error);
+
+        let header_value = "text/html, image/webp;q=x";
+        c_match_mime_type_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"image/webp".as_ptr() as *const c_char,
+            10,
+            ptr::null(),
+            &mut error,
+        );
+        assert_eq!(CParseErrorCode::MalformedHeader, error.code);
+        assert_eq!(24, error.offset);
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_c_match_mime_type_v2_with_options() {
+        let header_value = "image/*;q=0.5, image/*;q=0.2";
+        let options = COptions {
+            tie_break: C_TIE_BREAK_LAST_WINS,
+            ..COptions::DEFAULT
+        };
+        let m = c_match_mime_type_v2(
+            header_value.as_ptr() as *const c_char,
+            header_value.len(),
+            b"image/webp".as_ptr() as *const c_char,
+            10,
+            &options,
+            ptr::null_mut(),
+        );
+        assert_eq!(CMimeTypeMatchType::SubTypeWildcard as c_int, m.match_type);
+        assert_eq!(0.2, m.q);
+        assert_eq!((1, 15, 7), (m.entry_index, m.name_offset, m.name_len));
     }
+
+    #[cfg(feature = "mime")]
     #[test]
     fn test_c_cmp_mime_type_match() {
         assert_eq!(
             -1,
             c_cmp_mime_type_match(
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_NO_MATCH,
+                    match_type: CMimeTypeMatchType::NoMatch as c_int,
                     q: 0.0,
                 },
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_MAIN_TYPE_WILDCARD,
+                    match_type: CMimeTypeMatchType::MainTypeWildcard as c_int,
                     q: 0.0,
                 }
             )
@@ -322,11 +2352,11 @@ mod tests {
             1,
             c_cmp_mime_type_match(
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 0.0,
                 },
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_NO_MATCH,
+                    match_type: CMimeTypeMatchType::NoMatch as c_int,
                     q: 0.0,
                 }
             )
@@ -336,11 +2366,11 @@ mod tests {
             -1,
             c_cmp_mime_type_match(
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 0.0,
                 },
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 0.1,
                 }
             )
@@ -350,11 +2380,11 @@ mod tests {
             0,
             c_cmp_mime_type_match(
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 0.8,
                 },
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 0.8,
                 }
             )
@@ -364,11 +2394,11 @@ mod tests {
             1,
             c_cmp_mime_type_match(
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 1.0,
                 },
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_EXACT,
+                    match_type: CMimeTypeMatchType::Exact as c_int,
                     q: 0.9,
                 }
             )
@@ -378,14 +2408,164 @@ mod tests {
             0,
             c_cmp_mime_type_match(
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_NO_MATCH,
+                    match_type: CMimeTypeMatchType::NoMatch as c_int,
                     q: 0.0,
                 },
                 CMimeTypeMatch {
-                    match_type: C_MIME_TYPE_MATCH_TYPE_NO_MATCH,
+                    match_type: CMimeTypeMatchType::NoMatch as c_int,
                     q: 1.0,
                 }
             )
         );
+
+        let exact = CMimeTypeMatch {
+            match_type: CMimeTypeMatchType::Exact as c_int,
+            q: 1.0,
+        };
+        for match_type in [-2, 4] {
+            let invalid = CMimeTypeMatch { match_type, q: 1.0 };
+            assert_eq!(C_CMP_INVALID, c_cmp_mime_type_match(exact, invalid));
+            assert_eq!(C_CMP_INVALID, c_cmp_mime_type_match(invalid, exact));
+        }
+    }
+
+    #[cfg(feature = "language")]
+    #[test]
+    fn test_c_match_language() {
+        let header_value = "fr;q=0.5, en, *;q=0.1";
+        let value = header_value.as_ptr() as *const c_char;
+        let len = header_value.len();
+
+        let m = c_match_language(value, len, b"en-US".as_ptr() as *const c_char, 5);
+        assert_eq!(CLanguageMatchType::Prefix as c_int, m.match_type);
+        assert_eq!(1.0, m.q);
+        let m = c_match_language(value, len, b"fr".as_ptr() as *const c_char, 2);
+        assert_eq!(CLanguageMatchType::Exact as c_int, m.match_type);
+        assert_eq!(0.5, m.q);
+        let m = c_match_language(value, len, b"de".as_ptr() as *const c_char, 2);
+        assert_eq!(CLanguageMatchType::Wildcard as c_int, m.match_type);
+        assert_eq!(0.1, m.q);
+        let m = c_match_language(value, 2, b"de".as_ptr() as *const c_char, 2);
+        assert_eq!(CLanguageMatchType::NoMatch as c_int, m.match_type);
+        assert_eq!(0.0, m.q);
+
+        let m = c_match_language(ptr::null(), 0, b"en".as_ptr() as *const c_char, 2);
+        assert_eq!(CLanguageMatchType::Error as c_int, m.match_type);
+        let m = c_match_language(value, len, ptr::null(), 0);
+        assert_eq!(CLanguageMatchType::Error as c_int, m.match_type);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_ngx_match_encoding() {
+        let mut q_millis = 1;
+        let header_value = b"gzip;q=0.5, *;q=0.2";
+        let m = |encoding: &[u8], q_millis: &mut u16| {
+            c_ngx_match_encoding(
+                header_value.as_ptr(),
+                header_value.len(),
+                encoding.as_ptr(),
+                encoding.len(),
+                q_millis,
+            )
+        };
+        assert_eq!(CEncodingMatchType::Exact, m(b"gzip", &mut q_millis));
+        assert_eq!(500, q_millis);
+        assert_eq!(CEncodingMatchType::Wildcard, m(b"br", &mut q_millis));
+        assert_eq!(200, q_millis);
+        assert_eq!(
+            CEncodingMatchType::NoMatch,
+            c_ngx_match_encoding(b"gzip".as_ptr(), 4, b"br".as_ptr(), 2, &mut q_millis)
+        );
+        assert_eq!(0, q_millis);
+        assert_eq!(
+            CEncodingMatchType::Error,
+            c_ngx_match_encoding(ptr::null(), 0, b"br".as_ptr(), 2, ptr::null_mut())
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_ngx_negotiate_encoding() {
+        let supported = [&b"zstd"[..], b"br", b"gzip"].map(|s| CNgxStr {
+            len: s.len(),
+            data: s.as_ptr(),
+        });
+        let negotiate = |header_value: &str| {
+            let mut q_millis = 1;
+            let i = c_ngx_negotiate_encoding(
+                header_value.as_ptr(),
+                header_value.len(),
+                supported.as_ptr(),
+                supported.len(),
+                &mut q_millis,
+            );
+            (i, q_millis)
+        };
+        assert_eq!((2, 800), negotiate("gzip;q=0.8, br;q=0.5"));
+        assert_eq!((C_NEGOTIATE_NO_MATCH, 0), negotiate("deflate"));
+        assert_eq!(
+            C_NEGOTIATE_ERROR,
+            c_ngx_negotiate_encoding(b"br".as_ptr(), 2, ptr::null(), 1, ptr::null_mut())
+        );
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_c_ngx_match_mime_type() {
+        let mut q_millis = 0;
+        let header_value = b"text/html, image/*;q=0.8";
+        assert_eq!(
+            CMimeTypeMatchType::SubTypeWildcard,
+            c_ngx_match_mime_type(
+                header_value.as_ptr(),
+                header_value.len(),
+                b"image/png".as_ptr(),
+                9,
+                &mut q_millis
+            )
+        );
+        assert_eq!(800, q_millis);
+        assert_eq!(
+            CMimeTypeMatchType::Error,
+            c_ngx_match_mime_type(header_value.as_ptr(), 1, ptr::null(), 0, &mut q_millis)
+        );
+        assert_eq!(0, q_millis);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_c_vmod_negotiate_encoding() {
+        let supported = [c"zstd", c"br", c"gzip"].map(CStr::as_ptr);
+        let mut buf = [1 as c_char; 8];
+        let mut negotiate = |header_value: *const c_char, cap: usize| {
+            c_vmod_negotiate_encoding(
+                header_value,
+                supported.as_ptr(),
+                supported.len(),
+                buf.as_mut_ptr(),
+                cap,
+            )
+        };
+        assert_eq!(2, negotiate(c"gzip, deflate".as_ptr(), 8));
+        assert_eq!(C_NEGOTIATE_BUFFER_TOO_SMALL, negotiate(c"zstd".as_ptr(), 4));
+        assert_eq!(C_NEGOTIATE_NO_MATCH, negotiate(c"deflate".as_ptr(), 8));
+        assert_eq!(C_NEGOTIATE_NO_MATCH, negotiate(ptr::null(), 8));
+        assert_eq!(c"gzip", unsafe { CStr::from_ptr(buf.as_ptr()) });
+
+        assert_eq!(
+            1,
+            c_vmod_negotiate_encoding(
+                c"br".as_ptr(),
+                supported.as_ptr(),
+                supported.len(),
+                ptr::null_mut(),
+                0
+            )
+        );
+        assert_eq!(
+            C_NEGOTIATE_ERROR,
+            c_vmod_negotiate_encoding(c"br".as_ptr(), ptr::null(), 1, ptr::null_mut(), 0)
+        );
     }
 }
@@ -0,0 +1,90 @@
+use std::num::NonZeroUsize;
+
+use lru::LruCache;
+
+use crate::{
+    encoding_matcher::{match_for_encoding_with_options, EncodingMatch},
+    options::MatchOptions,
+};
+
+/// Remembers the results for the most recently seen (header value,
+/// encoding) pairs, for servers that see few distinct header values.
+///
+/// Lookups take `&mut self`; share one behind a `Mutex` or keep one per
+/// thread.
+#[derive(Debug)]
+pub struct MatcherCache {
+    results: LruCache<Box<[u8]>, Option<EncodingMatch>>,
+    options: MatchOptions,
+    key: Vec<u8>,
+}
+
+impl MatcherCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self::with_options(capacity, MatchOptions::default())
+    }
+
+    pub fn with_options(capacity: NonZeroUsize, options: MatchOptions) -> Self {
+        Self {
+            results: LruCache::new(capacity),
+            options,
+            key: Vec::new(),
+        }
+    }
+
+    pub fn match_for_encoding(&mut self, input: &[u8], encoding: &[u8]) -> Option<EncodingMatch> {
+        // The length prefix keeps ("ab", "c") and ("a", "bc") apart.
+        self.key.clear();
+        self.key
+            .extend_from_slice(&(encoding.len() as u64).to_le_bytes());
+        self.key.extend_from_slice(encoding);
+        self.key.extend_from_slice(input);
+        if let Some(result) = self.results.get(&self.key[..]) {
+            return *result;
+        }
+        let result = match_for_encoding_with_options(input, encoding, &self.options);
+        self.results.put(self.key[..].into(), result);
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::match_for_encoding;
+
+    #[test]
+    fn test_matcher_cache() {
+        let mut cache = MatcherCache::new(NonZeroUsize::new(2).unwrap());
+        assert!(cache.is_empty());
+
+        for (input, encoding) in [
+            (&b"gzip;q=0.5, br"[..], &b"gzip"[..]),
+            (b"gzip;q=0.5, br", b"br"),
+            (b"gzip;q=0.5, br", b"gzip"),
+            (b"gzip;q=0.5, b", b"rgzip"),
+            (b"gzip;q=0.5, br", b"zstd"),
+        ] {
+            assert_eq!(
+                match_for_encoding(input, encoding),
+                cache.match_for_encoding(input, encoding)
+            );
+        }
+        assert_eq!(2, cache.len());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
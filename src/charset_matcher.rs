@@ -0,0 +1,93 @@
+//! Accept-Charset matching. Charsets are compared case-insensitively and
+//! without resolving aliases like "latin1".
+
+use crate::{byte_slice::bytes_eq_ignore_case, lexer, matched::Match, weighted_list::WeightedList};
+
+/// Returns the entry of an Accept-Charset value that applies to `charset`:
+/// an exact entry over "*", then the highest q-value, then the earlier
+/// entry. Returns None if no entry matches or `input` is malformed.
+pub fn match_for_charset(input: &[u8], charset: &[u8]) -> Option<CharsetMatch> {
+    let mut best: Option<CharsetMatch> = None;
+    lexer::weighted_list(
+        input,
+        |_| true,
+        |entry, q| {
+            let Some(match_type) = get_charset_match_type(entry, charset) else {
+                return;
+            };
+            let cur = CharsetMatch { match_type, q };
+            if best.is_none_or(|b| cur > b) {
+                best = Some(cur);
+            }
+        },
+    )
+    .ok()?;
+    best
+}
+
+/// Like `match_for_charset`, for a header value and charset held as `&str`.
+pub fn match_for_charset_str(input: &str, charset: &str) -> Option<CharsetMatch> {
+    match_for_charset(input.as_bytes(), charset.as_bytes())
+}
+
+/// Returns the charsets of an Accept-Charset value as written, with their
+/// q-values, in order. Returns None if `input` is malformed.
+pub fn parse_accept_charset(input: &[u8]) -> Option<WeightedList<&[u8]>> {
+    let mut entries = WeightedList::new();
+    lexer::weighted_list(input, |_| true, |entry, q| entries.push(entry, q)).ok()?;
+    Some(entries)
+}
+
+pub(crate) fn get_charset_match_type(entry: &[u8], charset: &[u8]) -> Option<CharsetMatchType> {
+    if entry == b"*" {
+        Some(CharsetMatchType::Wildcard)
+    } else if bytes_eq_ignore_case(entry, charset) {
+        Some(CharsetMatchType::Exact)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum CharsetMatchType {
+    Wildcard,
+    Exact,
+}
+
+pub type CharsetMatch = Match<CharsetMatchType>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::q_value::QValue;
+
+    #[test]
+    fn test_match_for_charset() {
+        let input = b"utf-8, ISO-8859-1;q=0.5, *;q=0.1";
+        let m = match_for_charset(input, b"UTF-8").unwrap();
+        assert_eq!(
+            (CharsetMatchType::Exact, 1000),
+            (m.match_type, m.q.as_millis())
+        );
+        let m = match_for_charset(input, b"iso-8859-1").unwrap();
+        assert_eq!(500, m.q.as_millis());
+        assert_eq!(
+            Some(m),
+            match_for_charset_str("iso-8859-1;q=0.5", "ISO-8859-1")
+        );
+        let m = match_for_charset(input, b"shift_jis").unwrap();
+        assert_eq!(
+            (CharsetMatchType::Wildcard, 100),
+            (m.match_type, m.q.as_millis())
+        );
+        assert_eq!(None, match_for_charset(b"utf-8", b"us-ascii"));
+        assert_eq!(None, match_for_charset(b"utf-8;level=1", b"utf-8"));
+        assert_eq!(
+            Some([(&b"utf-8"[..], QValue::ONE)].into_iter().collect()),
+            parse_accept_charset(b"utf-8")
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! The parser combinators the matchers are built from, for parsing other
+//! HTTP fields with the same rules. A parser is a function of the input
+//! and a `&mut Cursor` that, on success, leaves the cursor after what it
+//! read. On failure the cursor position is unspecified; `opt` and `alt`
+//! move it back before going on.
+//!
+//! ```
+//! use accept_encoding::combinators::{byte, ows, pair, quoted_string, token, alt, Cursor};
+//!
+//! // A `name=value` parameter, as in `charset="utf-8"`.
+//! let input = b"charset=\"utf-8\" ; x";
+//! let mut c = Cursor(0);
+//! let value = alt(token, quoted_string);
+//! pair(token, pair(byte(b'='), value))(input, &mut c).unwrap();
+//! ows(input, &mut c);
+//! assert_eq!(Cursor(16), c);
+//! ```
+
+pub use crate::lexer::{
+    alt, byte, escaped, opt, ows, pair, quoted_string, token, Cursor, ParseError, ParseResult,
+};
@@ -0,0 +1,147 @@
+use crate::{
+    byte_slice::bytes_eq_ignore_case_const,
+    encoding_matcher::{EncodingMatch, EncodingMatchType},
+    lexer::{self, Cursor},
+    q_value::QValue,
+};
+
+/// A `const fn` version of `match_for_encoding`, so that results for
+/// static header values can be computed at compile time:
+///
+/// ```
+/// use accept_encoding::match_for_encoding_const;
+///
+/// const BR: bool = match_for_encoding_const(b"gzip, br", b"br").is_some();
+/// assert!(BR);
+/// ```
+///
+/// It is a straight-line version of the runtime parser with the default
+/// options, and returns the same results.
+pub const fn match_for_encoding_const(input: &[u8], encoding: &[u8]) -> Option<EncodingMatch> {
+    let alias: &[u8] = if bytes_eq_ignore_case_const(encoding, b"gzip") {
+        b"x-gzip"
+    } else if bytes_eq_ignore_case_const(encoding, b"compress") {
+        b"x-compress"
+    } else {
+        b""
+    };
+
+    let mut best: Option<EncodingMatch> = None;
+    let mut c = Cursor(0);
+    while !c.eof(input) {
+        // token
+        let start = c.0;
+        if lexer::token_const(input, &mut c).is_err() {
+            return None;
+        }
+        let mut cur = if eq_ignore_case_at(input, start, c.0, encoding)
+            || (!alias.is_empty() && eq_ignore_case_at(input, start, c.0, alias))
+        {
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::ONE,
+            })
+        } else if c.0 - start == 1 && input[start] == b'*' {
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Wildcard,
+                q: QValue::ONE,
+            })
+        } else {
+            None
+        };
+
+        // *( OWS ";" OWS parameter ) OWS ( "," OWS / end )
+        loop {
+            lexer::ows_const(input, &mut c);
+            if c.eof(input) {
+                // Trailing whitespace; an unfinished parameter is accepted
+                // below like the runtime parser does.
+                if c.0 > 0 && matches!(input[c.0 - 1], b' ' | b'\t') {
+                    return None;
+                }
+                break;
+            }
+            match input[c.0] {
+                b',' => {
+                    c.advance(1);
+                    lexer::ows_const(input, &mut c);
+                    break;
+                }
+                b';' => {}
+                _ => return None,
+            }
+            c.advance(1);
+            lexer::ows_const(input, &mut c);
+            if c.eof(input) {
+                break;
+            }
+            let name_start = c.0;
+            if lexer::token_const(input, &mut c).is_err() {
+                return None;
+            }
+            let is_q = eq_ignore_case_at(input, name_start, c.0, b"q");
+            if c.eof(input) {
+                break;
+            }
+            if input[c.0] != b'=' {
+                return None;
+            }
+            c.advance(1);
+            if c.eof(input) {
+                break;
+            }
+            if is_q {
+                match lexer::q_value_const(input, &mut c) {
+                    Ok(q) => {
+                        if let Some(m) = &mut cur {
+                            m.q = q;
+                        }
+                    }
+                    Err(_) => return None,
+                }
+            } else if lexer::token_const(input, &mut c).is_err()
+                && lexer::quoted_string_const(input, &mut c).is_err()
+            {
+                return None;
+            }
+        }
+
+        // First wins ties, as in `explain::finish_entry`.
+        if let Some(m) = cur {
+            let better = match best {
+                None => true,
+                Some(b) => {
+                    let (mt, bt) = (m.match_type as u8, b.match_type as u8);
+                    mt > bt || (mt == bt && m.q.as_millis() > b.q.as_millis())
+                }
+            };
+            if better {
+                best = cur;
+            }
+        }
+    }
+    best
+}
+
+const fn eq_ignore_case_at(input: &[u8], start: usize, end: usize, name: &[u8]) -> bool {
+    let (_, rest) = input.split_at(start);
+    let (token, _) = rest.split_at(end - start);
+    bytes_eq_ignore_case_const(token, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_for_encoding_const() {
+        const BR: Option<EncodingMatch> = match_for_encoding_const(b"gzip, br;q=0.5", b"br");
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::from_millis(500).unwrap(),
+            }),
+            BR
+        );
+    }
+}
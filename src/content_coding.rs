@@ -0,0 +1,128 @@
+use crate::{byte_slice::bytes_eq_ignore_case, supported_encodings::NegotiatedEncoding};
+
+/// The content codings compression middleware commonly implements, for
+/// turning a negotiated name into an encoder without a lookup table of its
+/// own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentCoding {
+    Brotli,
+    Deflate,
+    Gzip,
+    Identity,
+    Zstd,
+}
+
+impl ContentCoding {
+    const ALL: [ContentCoding; 5] = [
+        ContentCoding::Brotli,
+        ContentCoding::Deflate,
+        ContentCoding::Gzip,
+        ContentCoding::Identity,
+        ContentCoding::Zstd,
+    ];
+
+    /// Looks up a coding name case-insensitively, taking "x-gzip" for gzip.
+    /// Returns None for the codings this does not cover.
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        if bytes_eq_ignore_case(name, b"x-gzip") {
+            return Some(ContentCoding::Gzip);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|c| bytes_eq_ignore_case(name, c.name().as_bytes()))
+    }
+
+    /// The name to send in Content-Encoding.
+    pub const fn name(self) -> &'static str {
+        match self {
+            ContentCoding::Brotli => "br",
+            ContentCoding::Deflate => "deflate",
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Identity => "identity",
+            ContentCoding::Zstd => "zstd",
+        }
+    }
+
+    /// Wraps `reader` in the async-compression encoder for this coding, or
+    /// returns it as is for identity.
+    #[cfg(feature = "async-compression")]
+    pub fn tokio_encoder<'a, R>(
+        self,
+        reader: R,
+    ) -> std::pin::Pin<Box<dyn tokio::io::AsyncRead + Send + 'a>>
+    where
+        R: tokio::io::AsyncBufRead + Send + 'a,
+    {
+        use async_compression::tokio::bufread::{
+            BrotliEncoder, DeflateEncoder, GzipEncoder, ZstdEncoder,
+        };
+
+        match self {
+            ContentCoding::Brotli => Box::pin(BrotliEncoder::new(reader)),
+            ContentCoding::Deflate => Box::pin(DeflateEncoder::new(reader)),
+            ContentCoding::Gzip => Box::pin(GzipEncoder::new(reader)),
+            ContentCoding::Identity => Box::pin(reader),
+            ContentCoding::Zstd => Box::pin(ZstdEncoder::new(reader)),
+        }
+    }
+}
+
+impl NegotiatedEncoding {
+    /// The negotiated coding as a `ContentCoding`, or None without one or
+    /// for a coding it does not cover.
+    pub fn content_coding(&self) -> Option<ContentCoding> {
+        ContentCoding::from_name(self.encoding.as_deref()?.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_coding_from_name() {
+        for coding in ContentCoding::ALL {
+            assert_eq!(
+                Some(coding),
+                ContentCoding::from_name(coding.name().as_bytes())
+            );
+        }
+        assert_eq!(
+            Some(ContentCoding::Gzip),
+            ContentCoding::from_name(b"X-GZIP")
+        );
+        assert_eq!(Some(ContentCoding::Brotli), ContentCoding::from_name(b"BR"));
+        assert_eq!(None, ContentCoding::from_name(b"compress"));
+        assert_eq!(None, ContentCoding::from_name(b"*"));
+
+        let negotiated = NegotiatedEncoding {
+            encoding: Some("zstd".to_string()),
+        };
+        assert_eq!(Some(ContentCoding::Zstd), negotiated.content_coding());
+        assert_eq!(None, NegotiatedEncoding { encoding: None }.content_coding());
+    }
+
+    #[cfg(feature = "async-compression")]
+    #[test]
+    fn test_tokio_encoder() {
+        use tokio::io::AsyncReadExt;
+
+        let encode = |coding: ContentCoding| {
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let mut output = Vec::new();
+                    coding
+                        .tokio_encoder(&b"hello"[..])
+                        .read_to_end(&mut output)
+                        .await
+                        .unwrap();
+                    output
+                })
+        };
+        assert_eq!(b"hello".to_vec(), encode(ContentCoding::Identity));
+        assert_eq!([0x1f, 0x8b], encode(ContentCoding::Gzip)[..2]);
+        assert_eq!([0x28, 0xb5, 0x2f, 0xfd], encode(ContentCoding::Zstd)[..4]);
+    }
+}
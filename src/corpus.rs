@@ -0,0 +1,283 @@
+//! Header values sent by real browsers and HTTP clients, with the results
+//! this crate gives for them, for reuse in downstream regression tests and
+//! benchmarks.
+
+use crate::{
+    encoding_matcher::{EncodingMatch, EncodingMatchType},
+    mime_type_matcher::{MimeTypeMatch, MimeTypeMatchType},
+    q_value::QValue,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct AcceptEncodingCase {
+    /// The client that sends this value, or where the value comes from.
+    pub source: &'static str,
+    pub header_value: &'static [u8],
+    /// Codings with the result of matching `header_value` against each.
+    pub expected: &'static [(&'static [u8], Option<EncodingMatch>)],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AcceptCase {
+    /// The client that sends this value, or where the value comes from.
+    pub source: &'static str,
+    pub header_value: &'static [u8],
+    /// Media types with the result of matching `header_value` against each.
+    pub expected: &'static [(&'static [u8], Option<MimeTypeMatch>)],
+}
+
+pub fn accept_encoding_cases() -> &'static [AcceptEncodingCase] {
+    ACCEPT_ENCODING_CASES
+}
+
+pub fn accept_cases() -> &'static [AcceptCase] {
+    ACCEPT_CASES
+}
+
+const fn exact(millis: u16) -> Option<EncodingMatch> {
+    Some(EncodingMatch {
+        match_type: EncodingMatchType::Exact,
+        q: QValue::const_millis(millis),
+    })
+}
+
+const fn wildcard(millis: u16) -> Option<EncodingMatch> {
+    Some(EncodingMatch {
+        match_type: EncodingMatchType::Wildcard,
+        q: QValue::const_millis(millis),
+    })
+}
+
+const fn mime(match_type: MimeTypeMatchType, millis: u16) -> Option<MimeTypeMatch> {
+    Some(MimeTypeMatch {
+        match_type,
+        q: QValue::const_millis(millis),
+    })
+}
+
+const ACCEPT_ENCODING_CASES: &[AcceptEncodingCase] = &[
+    AcceptEncodingCase {
+        source: "Chrome, Firefox",
+        header_value: b"gzip, deflate, br, zstd",
+        expected: &[
+            (b"gzip", exact(1000)),
+            (b"br", exact(1000)),
+            (b"zstd", exact(1000)),
+            (b"identity", None),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "Safari",
+        header_value: b"gzip, deflate, br",
+        expected: &[
+            (b"gzip", exact(1000)),
+            (b"br", exact(1000)),
+            (b"zstd", None),
+            (b"identity", None),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "curl --compressed",
+        header_value: b"deflate, gzip, br, zstd",
+        expected: &[
+            (b"gzip", exact(1000)),
+            (b"br", exact(1000)),
+            (b"zstd", exact(1000)),
+            (b"identity", None),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "Python requests",
+        header_value: b"gzip, deflate",
+        expected: &[
+            (b"gzip", exact(1000)),
+            (b"br", None),
+            (b"zstd", None),
+            (b"identity", None),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "Go net/http, OkHttp",
+        header_value: b"gzip",
+        expected: &[
+            (b"gzip", exact(1000)),
+            (b"br", None),
+            (b"zstd", None),
+            (b"identity", None),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "Wget",
+        header_value: b"identity",
+        expected: &[
+            (b"gzip", None),
+            (b"br", None),
+            (b"zstd", None),
+            (b"identity", exact(1000)),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "RFC 9110 section 12.5.3",
+        header_value: b"gzip;q=1.0, identity; q=0.5, *;q=0",
+        expected: &[
+            (b"gzip", exact(1000)),
+            (b"br", wildcard(0)),
+            (b"zstd", wildcard(0)),
+            (b"identity", exact(500)),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "MDN",
+        header_value: b"br;q=1.0, gzip;q=0.8, *;q=0.1",
+        expected: &[
+            (b"gzip", exact(800)),
+            (b"br", exact(1000)),
+            (b"zstd", wildcard(100)),
+            (b"identity", wildcard(100)),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "RFC 9110 section 12.5.3",
+        header_value: b"*",
+        expected: &[
+            (b"gzip", wildcard(1000)),
+            (b"br", wildcard(1000)),
+            (b"zstd", wildcard(1000)),
+            (b"identity", wildcard(1000)),
+        ],
+    },
+    AcceptEncodingCase {
+        source: "RFC 9110 section 12.5.3",
+        header_value: b"",
+        expected: &[
+            (b"gzip", None),
+            (b"br", None),
+            (b"zstd", None),
+            (b"identity", None),
+        ],
+    },
+];
+
+const ACCEPT_CASES: &[AcceptCase] = {
+    use MimeTypeMatchType::*;
+    &[
+        AcceptCase {
+            source: "Chrome navigation",
+            header_value: b"text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+            expected: &[
+                (b"text/html", mime(Exact, 1000)),
+                (b"image/webp", mime(Exact, 1000)),
+                (b"image/png", mime(MainTypeWildcard, 800)),
+                (b"application/json", mime(MainTypeWildcard, 800)),
+            ],
+        },
+        AcceptCase {
+            source: "Firefox navigation",
+            header_value: b"text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8",
+            expected: &[
+                (b"text/html", mime(Exact, 1000)),
+                (b"image/webp", mime(Exact, 1000)),
+                (b"image/png", mime(MainTypeWildcard, 800)),
+                (b"application/json", mime(MainTypeWildcard, 800)),
+            ],
+        },
+        AcceptCase {
+            source: "Safari navigation",
+            header_value: b"text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+            expected: &[
+                (b"text/html", mime(Exact, 1000)),
+                (b"image/webp", mime(MainTypeWildcard, 800)),
+                (b"image/png", mime(MainTypeWildcard, 800)),
+                (b"application/json", mime(MainTypeWildcard, 800)),
+            ],
+        },
+        AcceptCase {
+            source: "Chrome image",
+            header_value: b"image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8",
+            expected: &[
+                (b"text/html", mime(MainTypeWildcard, 800)),
+                (b"image/webp", mime(Exact, 1000)),
+                (b"image/png", mime(SubTypeWildcard, 1000)),
+                (b"application/json", mime(MainTypeWildcard, 800)),
+            ],
+        },
+        AcceptCase {
+            source: "Firefox image",
+            header_value: b"image/avif,image/webp,image/png,image/svg+xml,image/*;q=0.8,*/*;q=0.5",
+            expected: &[
+                (b"text/html", mime(MainTypeWildcard, 500)),
+                (b"image/webp", mime(Exact, 1000)),
+                (b"image/png", mime(Exact, 1000)),
+                (b"application/json", mime(MainTypeWildcard, 500)),
+            ],
+        },
+        AcceptCase {
+            source: "Chrome stylesheet",
+            header_value: b"text/css,*/*;q=0.1",
+            expected: &[
+                (b"text/html", mime(MainTypeWildcard, 100)),
+                (b"image/webp", mime(MainTypeWildcard, 100)),
+                (b"image/png", mime(MainTypeWildcard, 100)),
+                (b"application/json", mime(MainTypeWildcard, 100)),
+            ],
+        },
+        AcceptCase {
+            source: "curl, fetch()",
+            header_value: b"*/*",
+            expected: &[
+                (b"text/html", mime(MainTypeWildcard, 1000)),
+                (b"image/webp", mime(MainTypeWildcard, 1000)),
+                (b"image/png", mime(MainTypeWildcard, 1000)),
+                (b"application/json", mime(MainTypeWildcard, 1000)),
+            ],
+        },
+        AcceptCase {
+            source: "JSON API client",
+            header_value: b"application/json",
+            expected: &[
+                (b"text/html", None),
+                (b"image/webp", None),
+                (b"image/png", None),
+                (b"application/json", mime(Exact, 1000)),
+            ],
+        },
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encoding_matcher::match_for_encoding, mime_type_matcher::match_for_mime_type};
+
+    #[test]
+    fn test_accept_encoding_cases() {
+        for case in accept_encoding_cases() {
+            for (encoding, expected) in case.expected {
+                assert_eq!(
+                    *expected,
+                    match_for_encoding(case.header_value, encoding),
+                    "{}: {:?} {:?}",
+                    case.source,
+                    String::from_utf8_lossy(case.header_value),
+                    String::from_utf8_lossy(encoding)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_accept_cases() {
+        for case in accept_cases() {
+            for (mime_type, expected) in case.expected {
+                assert_eq!(
+                    *expected,
+                    match_for_mime_type(case.header_value, mime_type),
+                    "{}: {:?} {:?}",
+                    case.source,
+                    String::from_utf8_lossy(case.header_value),
+                    String::from_utf8_lossy(mime_type)
+                );
+            }
+        }
+    }
+}
@@ -1,139 +1,546 @@
-use std::{cmp::Ordering, str};
+use std::{error::Error, fmt};
 
+#[cfg(feature = "tracing")]
+use crate::trace;
 use crate::{
     byte_slice::bytes_eq_ignore_case,
-    lexer::{self, Cursor},
+    explain::{finish_entry, EntryVerdict, Explanation, Observer, Recorder},
+    lexer::{self, Cursor, ParseError},
+    matched::Match,
+    options::{self, check_unregistered, MatchOptions, RegistryCheck},
     q_value::QValue,
+    registry::is_registered_content_coding,
+    warning::{Warning, WarningForwarder, WarningReason},
 };
 
+#[cfg(not(feature = "parser-const"))]
 pub fn match_for_encoding(input: &[u8], encoding: &[u8]) -> Option<EncodingMatch> {
-    let mut state = State::SearchingEncoding;
-    let mut cur_result: Option<EncodingMatch> = None;
-    let mut best_result: Option<EncodingMatch> = None;
+    match_for_encoding_with_options(input, encoding, &MatchOptions::default())
+}
+
+/// Backed by the straight-line parser of `match_for_encoding_const`, which
+/// returns the same results, so that the two can be benchmarked against
+/// each other.
+#[cfg(feature = "parser-const")]
+pub fn match_for_encoding(input: &[u8], encoding: &[u8]) -> Option<EncodingMatch> {
+    crate::const_matcher::match_for_encoding_const(input, encoding)
+}
+
+/// Like `match_for_encoding`, for a header value and coding held as `&str`.
+pub fn match_for_encoding_str(input: &str, encoding: &str) -> Option<EncodingMatch> {
+    match_for_encoding(input.as_bytes(), encoding.as_bytes())
+}
+
+pub fn match_for_encoding_with_options(
+    input: &[u8],
+    encoding: &[u8],
+    options: &MatchOptions,
+) -> Option<EncodingMatch> {
+    match_encoding_traced(input, encoding, options, &mut ())
+}
+
+/// Like `match_for_encoding_with_options`, but passes what lenient
+/// matching tolerated to `on_warning`.
+pub fn match_for_encoding_with_warnings<F: FnMut(Warning)>(
+    input: &[u8],
+    encoding: &[u8],
+    options: &MatchOptions,
+    on_warning: F,
+) -> Option<EncodingMatch> {
+    match_encoding_traced(input, encoding, options, &mut WarningForwarder(on_warning))
+}
+
+fn match_encoding_traced<O: Observer<EncodingMatch>>(
+    input: &[u8],
+    encoding: &[u8],
+    options: &MatchOptions,
+    observer: &mut O,
+) -> Option<EncodingMatch> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!(
+        "match_for_encoding",
+        encoding = %String::from_utf8_lossy(encoding)
+    )
+    .entered();
+
+    let mut c = Cursor(0);
+    let result = match_encoding(input, encoding, options, &mut c, observer);
+    #[cfg(feature = "tracing")]
+    trace::outcome(&result, c);
+    result.ok().flatten()
+}
 
-    let is_gzip = bytes_eq_ignore_case(encoding, b"gzip");
-    let is_compress = bytes_eq_ignore_case(encoding, b"compress");
+pub fn explain_encoding<'a>(
+    input: &'a [u8],
+    encoding: &[u8],
+    options: &MatchOptions,
+) -> Explanation<'a, EncodingMatch> {
+    let mut recorder = Recorder::new(input);
+    let mut c = Cursor(0);
+    let result = match_encoding(input, encoding, options, &mut c, &mut recorder);
+    recorder.finish(result.map_err(|_| c.0))
+}
 
-    let mut is_q_param = false;
+/// Checks `input` against the strict grammar without matching anything.
+/// Returns the offset where parsing failed if it is malformed.
+pub fn validate_accept_encoding(input: &[u8]) -> Result<(), usize> {
     let mut c = Cursor(0);
-    while !c.eof(input) {
-        match state {
+    match_target(
+        input,
+        Target::any(),
+        &MatchOptions::default(),
+        &mut c,
+        &mut (),
+    )
+    .map(|_| ())
+    .map_err(|_| c.0)
+}
+
+/// Returns the quality `input` gives `encoding` under RFC 9110: the
+/// q-value of the entry that applies, or 1 for "identity" when no entry
+/// covers it, since it is acceptable unless excluded. Returns None if
+/// `encoding` is not acceptable or `input` is malformed.
+pub fn effective_q(input: &[u8], encoding: &[u8]) -> Option<QValue> {
+    let q = match match_encoding(
+        input,
+        encoding,
+        &MatchOptions::default(),
+        &mut Cursor(0),
+        &mut (),
+    ) {
+        Ok(Some(m)) => m.q,
+        Ok(None) if bytes_eq_ignore_case(encoding, b"identity") => QValue::ONE,
+        _ => return None,
+    };
+    (!q.is_zero()).then_some(q)
+}
+
+pub fn match_for_encoding_name(
+    input: &[u8],
+    encoding: EncodingName<'_>,
+    options: &MatchOptions,
+) -> Option<EncodingMatch> {
+    match_for_encoding_with_options(input, encoding.as_bytes(), options)
+}
+
+/// Like `match_for_encoding_with_options`, but also returns where the
+/// winning entry is and what it says as the client sent it: the token, e.g.
+/// `X-GZIP`, and the parameters. This costs more than the plain matchers,
+/// so use it for logging or echoing headers rather than on every request.
+pub fn match_for_encoding_detailed<'a>(
+    input: &'a [u8],
+    encoding: &[u8],
+    options: &MatchOptions,
+) -> Option<EncodingMatchDetailed<'a>> {
+    let mut tracker = WinnerTracker::default();
+    let m = match_encoding(input, encoding, options, &mut Cursor(0), &mut tracker)
+        .ok()
+        .flatten()?;
+    let (index, offset, end) = tracker.winner?;
+    let mut c = Cursor(offset);
+    lexer::token(input, &mut c).ok()?;
+    let token = Cursor(offset).slice(input, c);
+    let mut params = &input[c.0..end];
+    while let [rest @ .., b' ' | b'\t'] = params {
+        params = rest;
+    }
+    Some(EncodingMatchDetailed {
+        match_type: m.match_type,
+        q: m.q,
+        token,
+        offset,
+        index,
+        params,
+    })
+}
+
+/// Records the index, start and end of the best entry so far.
+#[derive(Default)]
+struct WinnerTracker {
+    entries: usize,
+    winner: Option<(usize, usize, usize)>,
+}
+
+impl<M> Observer<M> for WinnerTracker {
+    fn entry(&mut self, start: usize, end: usize, _matched: Option<M>, verdict: EntryVerdict) {
+        if verdict == EntryVerdict::Best {
+            self.winner = Some((self.entries, start, end));
+        }
+        self.entries += 1;
+    }
+}
+
+fn match_encoding<O: Observer<EncodingMatch>>(
+    input: &[u8],
+    encoding: &[u8],
+    options: &MatchOptions,
+    c: &mut Cursor,
+    observer: &mut O,
+) -> Result<Option<EncodingMatch>, ParseError> {
+    match_target(input, Target::new(encoding), options, c, observer)
+}
+
+pub(crate) fn match_target<O: Observer<EncodingMatch>>(
+    input: &[u8],
+    target: Target<'_>,
+    options: &MatchOptions,
+    c: &mut Cursor,
+    observer: &mut O,
+) -> Result<Option<EncodingMatch>, ParseError> {
+    if !O::NEEDS_EVERY_ENTRY && c.0 == 0 {
+        if let Some(result) = match_common_header(input, target) {
+            c.0 = input.len();
+            return Ok(result);
+        }
+    }
+    let mut parser = Parser::new(target, *c);
+    parser.run(input, options, c, observer)?;
+    Ok(parser.finish(observer))
+}
+
+/// Header values that make up most real-world traffic, with their codings.
+/// Each coding appears once with q=1, so a match is always Exact and q=1.
+const COMMON_HEADERS: &[(&[u8], &[&[u8]])] = &[
+    (b"gzip, deflate, br", &[b"gzip", b"deflate", b"br"]),
+    (
+        b"gzip, deflate, br, zstd",
+        &[b"gzip", b"deflate", b"br", b"zstd"],
+    ),
+    (b"gzip, deflate", &[b"gzip", b"deflate"]),
+    (b"gzip", &[b"gzip"]),
+];
+
+/// Returns the result for a header value in `COMMON_HEADERS` or "*"
+/// without parsing it, or None for any other value.
+fn match_common_header(input: &[u8], target: Target<'_>) -> Option<Option<EncodingMatch>> {
+    if input == b"*" {
+        // The parser checks the target first, so asking for "*" is Exact.
+        let match_type = if target.matches(b"*") {
+            EncodingMatchType::Exact
+        } else {
+            EncodingMatchType::Wildcard
+        };
+        return Some(Some(EncodingMatch {
+            match_type,
+            q: QValue::ONE,
+        }));
+    }
+    let (_, encodings) = COMMON_HEADERS.iter().find(|(header, _)| *header == input)?;
+    Some(
+        encodings
+            .iter()
+            .any(|encoding| target.matches(encoding))
+            .then_some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::ONE,
+            }),
+    )
+}
+
+/// An encoding to look for, with the legacy alias a header may use for it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Target<'e> {
+    pub(crate) encoding: &'e [u8],
+    pub(crate) alias: Option<&'static [u8]>,
+    /// Makes every token an Exact match, so that the parser reports each
+    /// entry with its q-value.
+    pub(crate) any: bool,
+}
+
+impl<'e> Target<'e> {
+    pub(crate) fn new(encoding: &'e [u8]) -> Self {
+        let alias: Option<&'static [u8]> = match encoding.len() {
+            4 if bytes_eq_ignore_case(encoding, b"gzip") => Some(b"x-gzip"),
+            8 if bytes_eq_ignore_case(encoding, b"compress") => Some(b"x-compress"),
+            _ => None,
+        };
+        Self {
+            encoding,
+            alias,
+            any: false,
+        }
+    }
+
+    pub(crate) fn any() -> Self {
+        Self {
+            encoding: b"",
+            alias: None,
+            any: true,
+        }
+    }
+
+    pub(crate) fn matches(&self, token: &[u8]) -> bool {
+        self.any
+            || bytes_eq_ignore_case(token, self.encoding)
+            || self
+                .alias
+                .is_some_and(|alias| bytes_eq_ignore_case(token, alias))
+    }
+}
+
+pub(crate) struct Parser<'e> {
+    target: Target<'e>,
+    state: State,
+    cur_result: Option<EncodingMatch>,
+    best_result: Option<EncodingMatch>,
+    is_q_param: bool,
+    entry_start: Cursor,
+    entry_end: Cursor,
+}
+
+impl<'e> Parser<'e> {
+    pub(crate) fn new(target: Target<'e>, c: Cursor) -> Self {
+        Self {
+            target,
+            state: State::SearchingEncoding,
+            cur_result: None,
+            best_result: None,
+            is_q_param: false,
+            entry_start: c,
+            entry_end: c,
+        }
+    }
+
+    /// Steps through `input` up to its end, skipping malformed entries in
+    /// lenient mode. Lenient matching without an observer that needs every
+    /// entry stops early once `has_unbeatable_match` holds.
+    pub(crate) fn run<O: Observer<EncodingMatch>>(
+        &mut self,
+        input: &[u8],
+        options: &MatchOptions,
+        c: &mut Cursor,
+        observer: &mut O,
+    ) -> Result<(), ParseError> {
+        while !c.eof(input) {
+            if let Err(e) = self.step(input, options, c, observer) {
+                if !options.lenient {
+                    return Err(e);
+                }
+                observer.warning(Warning {
+                    offset: c.0,
+                    reason: WarningReason::SkippedMalformedEntry,
+                });
+                #[cfg(feature = "tracing")]
+                trace::skipped_entry(*c);
+                self.skip_entry(input, c);
+            }
+            // Strict matching must read on, since a later malformed entry
+            // rejects the whole header.
+            if options.lenient && !O::NEEDS_EVERY_ENTRY && self.has_unbeatable_match() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn step<O: Observer<EncodingMatch>>(
+        &mut self,
+        input: &[u8],
+        options: &MatchOptions,
+        c: &mut Cursor,
+        observer: &mut O,
+    ) -> Result<(), ParseError> {
+        match self.state {
             State::SearchingEncoding => {
-                let c1 = c;
-                lexer::token(input, &mut c).ok()?;
-                let token = c1.slice(input, c);
-                cur_result = if bytes_eq_ignore_case(token, encoding)
-                    || (is_gzip && bytes_eq_ignore_case(token, b"x-gzip"))
-                    || (is_compress && bytes_eq_ignore_case(token, b"x-compress"))
+                let c1 = *c;
+                self.entry_start = c1;
+                lexer::token(input, c)?;
+                self.entry_end = *c;
+                let token = c1.slice(input, *c);
+                if options.registry_check != RegistryCheck::Off
+                    && token != b"*"
+                    && !is_registered_content_coding(token)
                 {
+                    check_unregistered(options, c1, observer)?;
+                }
+                self.cur_result = if self.target.matches(token) {
                     Some(EncodingMatch {
                         match_type: EncodingMatchType::Exact,
-                        q: QValue::from_millis(1000).unwrap(),
+                        q: QValue::ONE,
                     })
                 } else if token == b"*" {
                     Some(EncodingMatch {
                         match_type: EncodingMatchType::Wildcard,
-                        q: QValue::from_millis(1000).unwrap(),
+                        q: QValue::ONE,
                     })
                 } else {
                     None
                 };
-                state = State::SeenEncoding;
+                self.state = State::SeenEncoding;
+                if self.cur_result.is_none() && options.lenient && !O::NEEDS_EVERY_ENTRY {
+                    // Nothing in a non-matching entry can change the result,
+                    // so jump to the next one. Strict matching still has to
+                    // validate it because a malformed entry rejects the header.
+                    lexer::skip_list_element(input, c);
+                    self.state = State::SearchingEncoding;
+                }
             }
             State::SeenEncoding => {
-                if !c.eof(input) {
-                    lexer::ows(input, &mut c);
-                    if c.eof(input) {
-                        return None;
-                    } else if lexer::byte(b';')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        state = State::SeenSemicolon;
-                    } else if lexer::byte(b',')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        may_update_best_result(&mut cur_result, &mut best_result);
-                        state = State::SearchingEncoding;
-                    } else {
-                        return None;
-                    }
-                }
+                self.after_element_or_parameter(input, options, c, observer)?;
             }
             State::SeenSemicolon => {
-                let c1 = c;
-                lexer::token(input, &mut c).ok()?;
-                let param_name = c1.slice(input, c);
-                is_q_param = bytes_eq_ignore_case(param_name, b"q");
-                state = State::SeenParameterName;
+                let c1 = *c;
+                lexer::token(input, c)?;
+                let param_name = c1.slice(input, *c);
+                self.is_q_param = bytes_eq_ignore_case(param_name, b"q");
+                self.state = State::SeenParameterName;
             }
             State::SeenParameterName => {
-                lexer::byte(b'=')(input, &mut c).ok()?;
-                state = State::SeenEqual;
+                lexer::byte(b'=')(input, c)?;
+                self.state = State::SeenEqual;
             }
             State::SeenEqual => {
-                if is_q_param {
-                    let c1 = c;
-                    lexer::q_value(input, &mut c).ok()?;
-                    if let Some(cur_result) = cur_result.as_mut() {
-                        cur_result.q =
-                            QValue::try_from(str::from_utf8(c1.slice(input, c)).unwrap()).unwrap();
+                if self.is_q_param {
+                    let q = options::q_value(input, options, c, observer)?;
+                    if let Some(cur_result) = self.cur_result.as_mut() {
+                        cur_result.q = q;
                     }
                 } else {
-                    lexer::alt(lexer::token, lexer::quoted_string)(input, &mut c).ok()?;
+                    lexer::alt(lexer::token, lexer::quoted_string)(input, c)?;
                 }
-                state = State::SeenParameterValue;
+                self.entry_end = *c;
+                self.state = State::SeenParameterValue;
             }
             State::SeenParameterValue => {
-                if !c.eof(input) {
-                    lexer::ows(input, &mut c);
-                    if c.eof(input) {
-                        return None;
-                    } else if lexer::byte(b',')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        may_update_best_result(&mut cur_result, &mut best_result);
-                        state = State::SearchingEncoding;
-                    } else if lexer::byte(b';')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        state = State::SeenSemicolon;
-                    } else {
-                        return None;
-                    }
-                }
+                self.after_element_or_parameter(input, options, c, observer)?;
             }
         }
+        Ok(())
+    }
+
+    fn after_element_or_parameter<O: Observer<EncodingMatch>>(
+        &mut self,
+        input: &[u8],
+        options: &MatchOptions,
+        c: &mut Cursor,
+        observer: &mut O,
+    ) -> Result<(), ParseError> {
+        let c1 = *c;
+        lexer::ows(input, c);
+        if c.eof(input) {
+            if !options.lenient {
+                return Err(ParseError);
+            }
+            observer.warning(Warning {
+                offset: c1.0,
+                reason: WarningReason::TrailingWhitespace,
+            });
+        } else if lexer::byte(b';')(input, c).is_ok() {
+            lexer::ows(input, c);
+            self.state = State::SeenSemicolon;
+        } else if lexer::byte(b',')(input, c).is_ok() {
+            lexer::ows(input, c);
+            self.finish_entry(observer);
+            self.state = State::SearchingEncoding;
+        } else {
+            return Err(ParseError);
+        }
+        Ok(())
+    }
+
+    fn finish_entry<O: Observer<EncodingMatch>>(&mut self, observer: &mut O) {
+        finish_entry(
+            &mut self.cur_result,
+            &mut self.best_result,
+            self.entry_start.0,
+            self.entry_end.0,
+            observer,
+        );
+    }
+
+    fn skip_entry(&mut self, input: &[u8], c: &mut Cursor) {
+        *c = self.entry_start;
+        lexer::skip_list_element(input, c);
+        self.cur_result = None;
+        self.state = State::SearchingEncoding;
+    }
+
+    /// Later entries can at best tie an Exact match with q=1, and a tie
+    /// keeps the earlier entry.
+    pub(crate) fn has_unbeatable_match(&self) -> bool {
+        self.best_result
+            == Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::ONE,
+            })
     }
-    may_update_best_result(&mut cur_result, &mut best_result);
-    best_result.take()
-}
 
-fn may_update_best_result(
-    cur_result: &mut Option<EncodingMatch>,
-    best_result: &mut Option<EncodingMatch>,
-) {
-    if cur_result.gt(&best_result) {
-        *best_result = cur_result.take();
+    pub(crate) fn finish<O: Observer<EncodingMatch>>(
+        mut self,
+        observer: &mut O,
+    ) -> Option<EncodingMatch> {
+        if !matches!(self.state, State::SearchingEncoding) {
+            self.finish_entry(observer);
+        }
+        self.best_result
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
 pub enum EncodingMatchType {
     Wildcard,
     Exact,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct EncodingMatch {
+pub type EncodingMatch = Match<EncodingMatchType>;
+
+/// An `EncodingMatch` with the matched token borrowed from the header.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct EncodingMatchDetailed<'a> {
     pub match_type: EncodingMatchType,
     pub q: QValue,
+    pub token: &'a [u8],
+    /// Offset of `token` in the header value.
+    pub offset: usize,
+    /// Position of the entry in the list, counted as `AcceptEncoding::entry`
+    /// counts them.
+    pub index: usize,
+    /// The parameters after `token` as written, q included, like
+    /// `;level=1;q=0.8`, or empty.
+    pub params: &'a [u8],
+}
+
+impl EncodingMatchDetailed<'_> {
+    pub fn to_match(&self) -> EncodingMatch {
+        EncodingMatch {
+            match_type: self.match_type,
+            q: self.q,
+        }
+    }
 }
 
-impl Ord for EncodingMatch {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (self.match_type, &self.q).cmp(&(other.match_type, &other.q))
+/// A content-coding name checked to be a valid token, so that it can
+/// match an entry in a header value.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct EncodingName<'a>(&'a [u8]);
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidEncodingNameError;
+
+impl fmt::Display for InvalidEncodingNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("content coding is not a token")
     }
 }
 
-impl PartialOrd for EncodingMatch {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Error for InvalidEncodingNameError {}
+
+impl<'a> EncodingName<'a> {
+    pub fn new(name: &'a [u8]) -> Result<Self, InvalidEncodingNameError> {
+        if lexer::is_token(name) {
+            Ok(Self(name))
+        } else {
+            Err(InvalidEncodingNameError)
+        }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
     }
 }
 
@@ -149,7 +556,14 @@ enum State {
 
 #[cfg(test)]
 mod tests {
+    use std::{cmp::Ordering, collections::HashSet};
+
     use super::*;
+    use crate::{
+        explain::{EntryVerdict, ExplainedEntry},
+        options::RegistryCheck,
+        test_util::for_each_input,
+    };
 
     #[test]
     fn test_match_for_encoding_gzip_deflate_br_to_br() {
@@ -179,6 +593,10 @@ mod tests {
             }),
             match_for_encoding(b"*  ; q=0.5", b"gzip")
         );
+        assert_eq!(
+            match_for_encoding(b"*  ; q=0.5", b"gzip"),
+            match_for_encoding_str("*  ; q=0.5", "gzip")
+        );
 
         assert_eq!(
             Some(EncodingMatch {
@@ -358,6 +776,18 @@ mod tests {
                 gzip_res
             );
         }
+        {
+            // parameter names are case-insensitive
+            let header_value = b"gzip;Q=0.5, br;q=0.9";
+            let gzip_res = match_for_encoding(header_value, b"gzip");
+            assert_eq!(
+                Some(EncodingMatch {
+                    match_type: EncodingMatchType::Exact,
+                    q: QValue::try_from(0.5).unwrap(),
+                }),
+                gzip_res
+            );
+        }
 
         assert_eq!(None, match_for_encoding(b" ", b"gzip"));
         assert_eq!(None, match_for_encoding(b"br/", b"gzip"));
@@ -367,6 +797,358 @@ mod tests {
         assert_eq!(None, match_for_encoding(b"br  ; q=1 /", b"gzip"));
     }
 
+    #[test]
+    fn test_match_for_encoding_with_options_lenient() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::try_from(0.999).unwrap(),
+            }),
+            match_for_encoding_with_options(b"gzip;q=0.99999, br", b"gzip", &lenient)
+        );
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::try_from(1.0).unwrap(),
+            }),
+            match_for_encoding_with_options(b"gzip;q=0.99999, br", b"br", &lenient)
+        );
+        assert_eq!(None, match_for_encoding(b"gzip;q=0.99999, br", b"br"));
+        assert_eq!(
+            None,
+            match_for_encoding_with_options(b"gzip;q=1.5", b"gzip", &lenient)
+        );
+    }
+
+    #[test]
+    fn test_match_for_encoding_with_warnings() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::try_from(0.5).unwrap(),
+            }),
+            match_for_encoding_with_warnings(b"br;q=/, ,gzip;q=0.50000 ", b"gzip", &lenient, |w| {
+                warnings.push(w)
+            })
+        );
+        assert_eq!(
+            vec![
+                Warning {
+                    offset: 5,
+                    reason: WarningReason::SkippedMalformedEntry,
+                },
+                Warning {
+                    offset: 8,
+                    reason: WarningReason::SkippedMalformedEntry,
+                },
+                Warning {
+                    offset: 16,
+                    reason: WarningReason::TruncatedQValue,
+                },
+                Warning {
+                    offset: 23,
+                    reason: WarningReason::TrailingWhitespace,
+                },
+            ],
+            warnings
+        );
+
+        let mut warnings = Vec::new();
+        assert_eq!(
+            None,
+            match_for_encoding_with_warnings(
+                b"br;q=/, gzip",
+                b"gzip",
+                &MatchOptions::default(),
+                |w| warnings.push(w)
+            )
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_match_for_encoding_registry_check() {
+        let header_value = b"sdch, gzip;q=0.5";
+        let gzip_match = Some(EncodingMatch {
+            match_type: EncodingMatchType::Exact,
+            q: QValue::try_from(0.5).unwrap(),
+        });
+
+        let warn = MatchOptions {
+            registry_check: RegistryCheck::Warn,
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        assert_eq!(
+            gzip_match,
+            match_for_encoding_with_warnings(header_value, b"gzip", &warn, |w| warnings.push(w))
+        );
+        assert_eq!(
+            vec![Warning {
+                offset: 0,
+                reason: WarningReason::UnregisteredToken,
+            }],
+            warnings
+        );
+
+        let reject = MatchOptions {
+            registry_check: RegistryCheck::Reject,
+            ..Default::default()
+        };
+        assert_eq!(
+            None,
+            match_for_encoding_with_options(header_value, b"gzip", &reject)
+        );
+        assert_eq!(
+            gzip_match,
+            match_for_encoding_with_options(
+                header_value,
+                b"gzip",
+                &MatchOptions {
+                    lenient: true,
+                    ..reject
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_effective_q() {
+        let q = |millis| Some(QValue::from_millis(millis).unwrap());
+        assert_eq!(q(500), effective_q(b"gzip;q=0.5, *", b"gzip"));
+        assert_eq!(q(1000), effective_q(b"gzip;q=0.5, *", b"br"));
+        assert_eq!(None, effective_q(b"gzip;q=0, *", b"gzip"));
+        assert_eq!(None, effective_q(b"gzip", b"br"));
+        assert_eq!(q(1000), effective_q(b"gzip", b"identity"));
+        assert_eq!(q(1000), effective_q(b"", b"IDENTITY"));
+        assert_eq!(None, effective_q(b"", b"gzip"));
+        assert_eq!(None, effective_q(b"gzip, identity;q=0", b"identity"));
+        assert_eq!(None, effective_q(b"gzip, *;q=0", b"identity"));
+        assert_eq!(q(300), effective_q(b"*;q=0, identity;q=0.3", b"identity"));
+        assert_eq!(None, effective_q(b"gzip;q=2", b"identity"));
+    }
+
+    #[test]
+    fn test_validate_accept_encoding() {
+        assert_eq!(Ok(()), validate_accept_encoding(b""));
+        assert_eq!(Ok(()), validate_accept_encoding(b"gzip, deflate, br"));
+        assert_eq!(
+            Ok(()),
+            validate_accept_encoding(b"gzip;q=0.5, *;a=\"b, c\"")
+        );
+        assert_eq!(Err(11), validate_accept_encoding(b"gzip, br;q=2"));
+        for input in [&b"gzip,,br"[..], b"gzip;q=0.5000", b"gzip "] {
+            assert_eq!(
+                explain_encoding(input, b"gzip", &MatchOptions::default()).error_offset,
+                validate_accept_encoding(input).err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_explain_encoding() {
+        let explanation = explain_encoding(
+            b"br;q=0.9, *;q=0.5, x-gzip;q=0.8, gzip;q=0.8",
+            b"gzip",
+            &MatchOptions::default(),
+        );
+        let gzip_match = EncodingMatch {
+            match_type: EncodingMatchType::Exact,
+            q: QValue::try_from(0.8).unwrap(),
+        };
+        assert_eq!(
+            vec![
+                ExplainedEntry {
+                    offset: 0,
+                    text: b"br;q=0.9".as_slice(),
+                    matched: None,
+                    verdict: EntryVerdict::NotMatched,
+                },
+                ExplainedEntry {
+                    offset: 10,
+                    text: b"*;q=0.5".as_slice(),
+                    matched: Some(EncodingMatch {
+                        match_type: EncodingMatchType::Wildcard,
+                        q: QValue::try_from(0.5).unwrap(),
+                    }),
+                    verdict: EntryVerdict::Best,
+                },
+                ExplainedEntry {
+                    offset: 19,
+                    text: b"x-gzip;q=0.8".as_slice(),
+                    matched: Some(gzip_match),
+                    verdict: EntryVerdict::Best,
+                },
+                ExplainedEntry {
+                    offset: 33,
+                    text: b"gzip;q=0.8".as_slice(),
+                    matched: Some(gzip_match),
+                    verdict: EntryVerdict::Tie,
+                },
+            ],
+            explanation.entries
+        );
+        assert_eq!(Some(gzip_match), explanation.result);
+        assert_eq!(Some(2), explanation.winner);
+        assert_eq!(None, explanation.error_offset);
+
+        let explanation = explain_encoding(b"gzip, br;q=2", b"gzip", &MatchOptions::default());
+        assert_eq!(1, explanation.entries.len());
+        assert_eq!(None, explanation.result);
+        assert_eq!(None, explanation.winner);
+        assert_eq!(Some(11), explanation.error_offset);
+    }
+
+    #[test]
+    fn test_encoding_name() {
+        assert_eq!(b"br", EncodingName::new(b"br").unwrap().as_bytes());
+        assert_eq!(Err(InvalidEncodingNameError), EncodingName::new(b""));
+        assert_eq!(Err(InvalidEncodingNameError), EncodingName::new(b"br "));
+        assert_eq!(Err(InvalidEncodingNameError), EncodingName::new(b"x/gzip"));
+        let err: Box<dyn Error> = Box::new(InvalidEncodingNameError);
+        assert_eq!("content coding is not a token", err.to_string());
+
+        assert_eq!(
+            Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::try_from(0.5).unwrap(),
+            }),
+            match_for_encoding_name(
+                b"gzip;q=0.5",
+                EncodingName::new(b"gzip").unwrap(),
+                &MatchOptions::default()
+            )
+        );
+    }
+
+    #[test]
+    fn test_match_for_encoding_never_panics() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        for_each_input(b"gzip*;,=q01. \t\"\\\xff", 0..=4, |input| {
+            match_for_encoding(input, b"gzip");
+            match_for_encoding(input, b"x-gzip");
+            // The fast skip of non-matching entries must not change the result.
+            assert_eq!(
+                match_for_encoding_with_warnings(input, b"gzip", &lenient, |_| {}),
+                match_for_encoding_with_options(input, b"gzip", &lenient),
+                "input={:?}",
+                String::from_utf8_lossy(input)
+            );
+        });
+    }
+
+    #[test]
+    fn test_match_for_encoding_detailed() {
+        let options = MatchOptions::default();
+        assert_eq!(
+            Some(EncodingMatchDetailed {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::from_millis(800).unwrap(),
+                token: b"X-GZIP",
+                offset: 14,
+                index: 2,
+                params: b";q=0.8",
+            }),
+            match_for_encoding_detailed(
+                b"br, *;q=0.5,  X-GZIP;q=0.8, gzip;q=0.7",
+                b"gzip",
+                &options
+            )
+        );
+        let detailed = match_for_encoding_detailed(b"br, *;q=0.5", b"gzip", &options).unwrap();
+        assert_eq!(b"*", detailed.token);
+        assert_eq!((1, &b";q=0.5"[..]), (detailed.index, detailed.params));
+        let with_params = match_for_encoding_detailed(
+            b"gzip;q=0.1, deflate, br ; level=\"a, b\" ;q=1 , zstd",
+            b"br",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            (&b"br"[..], 2, &b" ; level=\"a, b\" ;q=1"[..]),
+            (with_params.token, with_params.index, with_params.params)
+        );
+        assert_eq!(
+            Some((&b"br"[..], QValue::ONE, 1)),
+            crate::accept_encoding::AcceptEncoding::parse(
+                b"gzip;q=0.1, deflate, br ; level=\"a, b\" ;q=1 , zstd"
+            )
+            .unwrap()
+            .entry(2)
+        );
+        let plain = match_for_encoding_detailed(b"gzip", b"gzip", &options).unwrap();
+        assert_eq!((0, &b""[..]), (plain.index, plain.params));
+        assert_eq!(
+            match_for_encoding(b"br, *;q=0.5", b"gzip"),
+            Some(detailed.to_match())
+        );
+        assert_eq!(None, match_for_encoding_detailed(b"br", b"gzip", &options));
+        assert_eq!(
+            None,
+            match_for_encoding_detailed(b"gzip;q=2", b"gzip", &options)
+        );
+    }
+
+    #[test]
+    fn test_match_for_encoding_stops_at_exact_q1() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let exact_q1 = Some(EncodingMatch {
+            match_type: EncodingMatchType::Exact,
+            q: QValue::ONE,
+        });
+        assert_eq!(
+            exact_q1,
+            match_for_encoding_with_options(b"gzip, br;q=0.5, gzip;q=0, \"", b"gzip", &lenient)
+        );
+        // Strict matching still rejects a malformed entry after the match.
+        assert_eq!(None, match_for_encoding(b"gzip, br;q=2", b"gzip"));
+    }
+
+    #[test]
+    fn test_match_common_header() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        for (header, _) in COMMON_HEADERS.iter().chain([(&b"*"[..], &[][..])].iter()) {
+            for encoding in [
+                &b"gzip"[..],
+                b"x-gzip",
+                b"br",
+                b"zstd",
+                b"identity",
+                b"GZIP",
+                b"*",
+            ] {
+                let parsed = match_for_encoding_with_warnings(header, encoding, &lenient, |_| {});
+                assert_eq!(
+                    Some(parsed),
+                    match_common_header(header, Target::new(encoding)),
+                    "header={:?}, encoding={:?}",
+                    String::from_utf8_lossy(header),
+                    String::from_utf8_lossy(encoding)
+                );
+                assert_eq!(parsed, match_for_encoding(header, encoding));
+            }
+        }
+        assert_eq!(None, match_common_header(b"gzip, br", Target::new(b"br")));
+    }
+
     #[test]
     fn test_match_result_cmp() {
         assert_eq!(
@@ -450,7 +1232,7 @@ mod tests {
     #[test]
     fn test_encoding_match_derive() {
         assert_eq!(
-            "EncodingMatch { match_type: Exact, q: QValue { millis: 1000 } }".to_string(),
+            "Match { match_type: Exact, q: QValue { millis: 1000 } }".to_string(),
             format!(
                 "{:?}",
                 EncodingMatch {
@@ -462,6 +1244,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encoding_match_hash() {
+        let mut seen = HashSet::new();
+        assert!(seen.insert(match_for_encoding(b"gzip;q=0.5", b"gzip")));
+        assert!(seen.insert(match_for_encoding(b"*;q=0.5", b"gzip")));
+        assert!(!seen.insert(match_for_encoding(b"br, gzip;q=0.5", b"gzip")));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_encoding_match_serde() {
+        let m = EncodingMatch {
+            match_type: EncodingMatchType::Exact,
+            q: QValue::try_from(0.5).unwrap(),
+        };
+        let json = r#"{"match_type":"Exact","q":0.5}"#;
+        assert_eq!(json, serde_json::to_string(&m).unwrap());
+        assert_eq!(m, serde_json::from_str(json).unwrap());
+    }
+
     #[test]
     fn test_state_derive() {
         assert_eq!(
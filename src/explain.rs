@@ -0,0 +1,178 @@
+use std::cmp::Ordering;
+
+use crate::warning::Warning;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum EntryVerdict {
+    /// The entry does not apply to the requested value.
+    NotMatched,
+    /// The entry beat every earlier entry and became the best so far.
+    Best,
+    /// The entry matched but ranked lower than the best so far.
+    Lower,
+    /// The entry matched and ranked equal to the best so far, which is kept.
+    Tie,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExplainedEntry<'a, M> {
+    pub offset: usize,
+    pub text: &'a [u8],
+    pub matched: Option<M>,
+    pub verdict: EntryVerdict,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Explanation<'a, M> {
+    pub entries: Vec<ExplainedEntry<'a, M>>,
+    pub result: Option<M>,
+    /// Index into `entries` of the entry that produced `result`.
+    pub winner: Option<usize>,
+    /// Offset where parsing failed; the header is rejected as a whole.
+    pub error_offset: Option<usize>,
+    pub warnings: Vec<Warning>,
+}
+
+pub(crate) trait Observer<M> {
+    /// False lets lenient matching skip entries that cannot match without
+    /// reporting them or validating their parameters.
+    const NEEDS_EVERY_ENTRY: bool = true;
+
+    fn entry(&mut self, start: usize, end: usize, matched: Option<M>, verdict: EntryVerdict);
+
+    #[inline]
+    fn warning(&mut self, _warning: Warning) {}
+}
+
+impl<M> Observer<M> for () {
+    const NEEDS_EVERY_ENTRY: bool = false;
+
+    #[inline]
+    fn entry(&mut self, _start: usize, _end: usize, _matched: Option<M>, _verdict: EntryVerdict) {}
+}
+
+pub(crate) fn finish_entry<M: Ord + Copy, O: Observer<M>>(
+    cur_result: &mut Option<M>,
+    best_result: &mut Option<M>,
+    start: usize,
+    end: usize,
+    observer: &mut O,
+) {
+    let verdict = match (*cur_result, *best_result) {
+        (None, _) => EntryVerdict::NotMatched,
+        (Some(_), None) => EntryVerdict::Best,
+        (Some(cur), Some(best)) => match cur.cmp(&best) {
+            Ordering::Greater => EntryVerdict::Best,
+            Ordering::Equal => EntryVerdict::Tie,
+            Ordering::Less => EntryVerdict::Lower,
+        },
+    };
+    observer.entry(start, end, *cur_result, verdict);
+    if verdict == EntryVerdict::Best {
+        *best_result = cur_result.take();
+    }
+}
+
+pub(crate) struct Recorder<'a, M> {
+    input: &'a [u8],
+    entries: Vec<ExplainedEntry<'a, M>>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a, M> Recorder<'a, M> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            entries: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn finish(self, result: Result<Option<M>, usize>) -> Explanation<'a, M> {
+        match result {
+            Ok(result) => Explanation {
+                winner: result.as_ref().and_then(|_| {
+                    self.entries
+                        .iter()
+                        .rposition(|e| e.verdict == EntryVerdict::Best)
+                }),
+                entries: self.entries,
+                result,
+                error_offset: None,
+                warnings: self.warnings,
+            },
+            Err(offset) => Explanation {
+                entries: self.entries,
+                result: None,
+                winner: None,
+                error_offset: Some(offset),
+                warnings: self.warnings,
+            },
+        }
+    }
+}
+
+impl<'a, M> Observer<M> for Recorder<'a, M> {
+    fn entry(&mut self, start: usize, end: usize, matched: Option<M>, verdict: EntryVerdict) {
+        self.entries.push(ExplainedEntry {
+            offset: start,
+            text: &self.input[start..end],
+            matched,
+            verdict,
+        });
+    }
+
+    fn warning(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_entry() {
+        let mut best = None;
+
+        let mut cur = None;
+        finish_entry(&mut cur, &mut best, 0, 1, &mut ());
+        assert_eq!(None, best);
+
+        let mut recorder = Recorder::new(b"a,b,c,d");
+        for (i, v) in [2, 1, 2, 3].into_iter().enumerate() {
+            let mut cur = Some(v);
+            finish_entry(&mut cur, &mut best, i * 2, i * 2 + 1, &mut recorder);
+        }
+        assert_eq!(Some(3), best);
+
+        let explanation = recorder.finish(Ok(best));
+        assert_eq!(
+            vec![
+                EntryVerdict::Best,
+                EntryVerdict::Lower,
+                EntryVerdict::Tie,
+                EntryVerdict::Best
+            ],
+            explanation
+                .entries
+                .iter()
+                .map(|e| e.verdict)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(b"d", explanation.entries[3].text);
+        assert_eq!(Some(3), explanation.winner);
+    }
+
+    #[test]
+    fn test_recorder_finish_error() {
+        let explanation = Recorder::<u8>::new(b"a/").finish(Err(1));
+        assert_eq!(None, explanation.result);
+        assert_eq!(None, explanation.winner);
+        assert_eq!(Some(1), explanation.error_offset);
+    }
+}
@@ -0,0 +1,110 @@
+//! The gRPC message compression headers. grpc-accept-encoding lists the
+//! codings a peer can decompress, without q-values, and grpc-encoding names
+//! the one a message was compressed with.
+
+use crate::{
+    byte_slice::bytes_eq_ignore_case,
+    lexer::{self, Cursor, ParseError},
+    supported_encodings::SupportedEncodings,
+};
+
+/// Returns whether a grpc-accept-encoding value lists `encoding`.
+/// "identity" is always accepted, as every peer must read uncompressed
+/// messages. Returns None if `input` is malformed.
+pub fn grpc_accepts_encoding(input: &[u8], encoding: &[u8]) -> Option<bool> {
+    let mut found = bytes_eq_ignore_case(encoding, b"identity");
+    for_each_encoding(input, |e| found |= bytes_eq_ignore_case(e, encoding)).ok()?;
+    Some(found)
+}
+
+impl SupportedEncodings {
+    /// Picks the coding to compress messages with from a
+    /// grpc-accept-encoding value: the first of these that it lists. None
+    /// means messages should be sent uncompressed, including when `input`
+    /// is malformed.
+    pub fn negotiate_grpc(&self, input: &[u8]) -> Option<&[u8]> {
+        let mut listed = Vec::new();
+        for_each_encoding(input, |e| listed.push(e)).ok()?;
+        self.iter()
+            .find(|&s| listed.iter().any(|&e| bytes_eq_ignore_case(e, s)))
+    }
+
+    /// Looks up the coding a grpc-encoding value names, returning the
+    /// supported name to decompress with. "identity" is always supported.
+    /// None means the call should fail with UNIMPLEMENTED.
+    pub fn find_grpc_encoding<'a>(&'a self, input: &'a [u8]) -> Option<&'a [u8]> {
+        let input = input.trim_ascii();
+        if bytes_eq_ignore_case(input, b"identity") {
+            return Some(b"identity");
+        }
+        self.iter().find(|&s| bytes_eq_ignore_case(input, s))
+    }
+}
+
+/// Calls `f` with each coding in a comma-separated list, skipping empty
+/// elements.
+fn for_each_encoding<'a>(input: &'a [u8], mut f: impl FnMut(&'a [u8])) -> Result<(), ParseError> {
+    let mut c = Cursor(0);
+    loop {
+        lexer::ows(input, &mut c);
+        if c.eof(input) {
+            return Ok(());
+        }
+        if c.peek(input) != Some(b',') {
+            let start = c;
+            lexer::token(input, &mut c)?;
+            f(start.slice(input, c));
+            lexer::ows(input, &mut c);
+            if c.eof(input) {
+                return Ok(());
+            }
+        }
+        lexer::byte(b',')(input, &mut c)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grpc_accepts_encoding() {
+        assert_eq!(
+            Some(true),
+            grpc_accepts_encoding(b"gzip,deflate", b"deflate")
+        );
+        assert_eq!(
+            Some(true),
+            grpc_accepts_encoding(b" gzip , ,Snappy ", b"snappy")
+        );
+        assert_eq!(Some(false), grpc_accepts_encoding(b"gzip", b"deflate"));
+        assert_eq!(Some(true), grpc_accepts_encoding(b"", b"identity"));
+        assert_eq!(None, grpc_accepts_encoding(b"gzip;q=0.5", b"gzip"));
+        assert_eq!(None, grpc_accepts_encoding(b"gzip deflate", b"gzip"));
+    }
+
+    #[test]
+    fn test_negotiate_grpc() {
+        let supported = SupportedEncodings::new(["zstd", "gzip"]).unwrap();
+        assert_eq!(
+            Some(&b"gzip"[..]),
+            supported.negotiate_grpc(b"identity,deflate,gzip")
+        );
+        assert_eq!(Some(&b"zstd"[..]), supported.negotiate_grpc(b"gzip, ZSTD"));
+        assert_eq!(None, supported.negotiate_grpc(b"deflate"));
+        assert_eq!(None, supported.negotiate_grpc(b""));
+        assert_eq!(None, supported.negotiate_grpc(b"gzip;q=1"));
+    }
+
+    #[test]
+    fn test_find_grpc_encoding() {
+        let supported = SupportedEncodings::new(["gzip"]).unwrap();
+        assert_eq!(Some(&b"gzip"[..]), supported.find_grpc_encoding(b"GZIP"));
+        assert_eq!(
+            Some(&b"identity"[..]),
+            supported.find_grpc_encoding(b"identity")
+        );
+        assert_eq!(None, supported.find_grpc_encoding(b"snappy"));
+        assert_eq!(None, supported.find_grpc_encoding(b"gzip,deflate"));
+    }
+}
@@ -0,0 +1,132 @@
+//! Matching methods on header values themselves, for quick checks in
+//! handlers and tests.
+
+#[cfg(feature = "charset")]
+use crate::charset_matcher::{match_for_charset, CharsetMatch};
+#[cfg(feature = "language")]
+use crate::language_matcher::{match_for_language, LanguageMatch};
+#[cfg(feature = "mime")]
+use crate::mime_type_matcher::{match_for_mime_type, MimeTypeMatch};
+#[cfg(feature = "encoding")]
+use crate::{
+    accept_encoding::AcceptEncoding,
+    encoding_matcher::{match_for_encoding, EncodingMatch},
+    weighted_list::WeightedList,
+};
+
+/// The `match_for_*` functions as methods on anything holding a header
+/// value: `b"gzip, br".match_encoding(b"br")`, or a `str`, `Vec<u8>` or
+/// `http::HeaderValue`. Candidates can be given as bytes or `&str`.
+pub trait AcceptHeaderExt {
+    /// Like `match_for_encoding`, for an Accept-Encoding value.
+    #[cfg(feature = "encoding")]
+    fn match_encoding(&self, encoding: impl AsRef<[u8]>) -> Option<EncodingMatch>;
+
+    /// Returns the codings an Accept-Encoding value names as acceptable,
+    /// highest q-value first, keeping the header's order among equal ones.
+    /// "*" and q=0 are left out. Returns None if the value is malformed.
+    #[cfg(feature = "encoding")]
+    fn preferred_encodings(&self) -> Option<Vec<&[u8]>>;
+
+    /// Like `match_for_mime_type`, for an Accept value.
+    #[cfg(feature = "mime")]
+    fn match_mime_type(&self, mime_type: impl AsRef<[u8]>) -> Option<MimeTypeMatch>;
+
+    /// Like `match_for_language`, for an Accept-Language value.
+    #[cfg(feature = "language")]
+    fn match_language(&self, language: impl AsRef<[u8]>) -> Option<LanguageMatch>;
+
+    /// Like `match_for_charset`, for an Accept-Charset value.
+    #[cfg(feature = "charset")]
+    fn match_charset(&self, charset: impl AsRef<[u8]>) -> Option<CharsetMatch>;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> AcceptHeaderExt for T {
+    #[cfg(feature = "encoding")]
+    fn match_encoding(&self, encoding: impl AsRef<[u8]>) -> Option<EncodingMatch> {
+        match_for_encoding(self.as_ref(), encoding.as_ref())
+    }
+
+    #[cfg(feature = "encoding")]
+    fn preferred_encodings(&self) -> Option<Vec<&[u8]>> {
+        let input = self.as_ref();
+        let parsed = AcceptEncoding::parse(input)?;
+        let mut encodings: WeightedList<&[u8]> =
+            parsed.spans().map(|(span, q)| (&input[span], q)).collect();
+        encodings.retain(|encoding, q| *encoding != b"*" && !q.is_zero());
+        encodings.sort_by_q();
+        Some(
+            encodings
+                .into_iter()
+                .map(|(encoding, _)| encoding)
+                .collect(),
+        )
+    }
+
+    #[cfg(feature = "mime")]
+    fn match_mime_type(&self, mime_type: impl AsRef<[u8]>) -> Option<MimeTypeMatch> {
+        match_for_mime_type(self.as_ref(), mime_type.as_ref())
+    }
+
+    #[cfg(feature = "language")]
+    fn match_language(&self, language: impl AsRef<[u8]>) -> Option<LanguageMatch> {
+        match_for_language(self.as_ref(), language.as_ref())
+    }
+
+    #[cfg(feature = "charset")]
+    fn match_charset(&self, charset: impl AsRef<[u8]>) -> Option<CharsetMatch> {
+        match_for_charset(self.as_ref(), charset.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_encoding() {
+        assert_eq!(
+            match_for_encoding(b"gzip;q=0.5, br", b"br"),
+            "gzip;q=0.5, br".match_encoding("br")
+        );
+        assert_eq!(
+            match_for_encoding(b"gzip", b"br"),
+            b"gzip".to_vec().match_encoding(b"br")
+        );
+        assert_eq!(
+            Some(vec![&b"br"[..], b"zstd", b"gzip"]),
+            "gzip;q=0.5, br, *;q=0.1, zstd, deflate;q=0".preferred_encodings()
+        );
+        assert_eq!(Some(vec![]), b"".preferred_encodings());
+        assert_eq!(None, "gzip;q=2".preferred_encodings());
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_mime_type() {
+        let header_value = String::from("text/*;q=0.5, text/html");
+        assert_eq!(
+            match_for_mime_type(header_value.as_bytes(), b"text/plain"),
+            header_value.match_mime_type(b"text/plain")
+        );
+    }
+
+    #[cfg(feature = "language")]
+    #[test]
+    fn test_language() {
+        assert_eq!(
+            match_for_language(b"en;q=0.8, ja", b"en-US"),
+            "en;q=0.8, ja".match_language(b"en-US")
+        );
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn test_charset() {
+        assert_eq!(
+            match_for_charset(b"utf-8, *;q=0.1", b"utf-8"),
+            b"utf-8, *;q=0.1"[..].match_charset(b"utf-8")
+        );
+    }
+}
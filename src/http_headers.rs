@@ -0,0 +1,174 @@
+//! Matching against the header types of the http crate, as hyper and axum
+//! hand them over.
+
+use std::borrow::Cow;
+
+use http::{request::Parts, HeaderMap, HeaderName, HeaderValue, Request};
+
+use crate::byte_slice::join_field_values;
+
+#[cfg(feature = "mime")]
+use crate::mime_type_matcher::{match_for_mime_type, MimeTypeMatch};
+#[cfg(feature = "encoding")]
+use crate::{
+    encoding_matcher::{effective_q, match_for_encoding, EncodingMatch},
+    supported_encodings::SupportedEncodings,
+};
+
+/// Where the `*_from_headers` functions read a header value from: a single
+/// `HeaderValue`, or a `HeaderMap`, whose repeated fields are joined with
+/// ", " as RFC 9110 allows for lists. A missing field reads as empty.
+pub trait HeaderSource {
+    fn header_value(&self, name: &HeaderName) -> Cow<'_, [u8]>;
+}
+
+impl HeaderSource for HeaderValue {
+    fn header_value(&self, _name: &HeaderName) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+}
+
+impl HeaderSource for HeaderMap {
+    fn header_value(&self, name: &HeaderName) -> Cow<'_, [u8]> {
+        join_field_values(self.get_all(name).iter().map(HeaderValue::as_bytes))
+    }
+}
+
+impl<B> HeaderSource for Request<B> {
+    fn header_value(&self, name: &HeaderName) -> Cow<'_, [u8]> {
+        self.headers().header_value(name)
+    }
+}
+
+impl HeaderSource for Parts {
+    fn header_value(&self, name: &HeaderName) -> Cow<'_, [u8]> {
+        self.headers.header_value(name)
+    }
+}
+
+/// Like `match_for_encoding`, reading Accept-Encoding from `headers`.
+#[cfg(feature = "encoding")]
+pub fn match_for_encoding_from_headers(
+    headers: &impl HeaderSource,
+    encoding: &[u8],
+) -> Option<EncodingMatch> {
+    match_for_encoding(
+        &headers.header_value(&http::header::ACCEPT_ENCODING),
+        encoding,
+    )
+}
+
+/// Like `match_for_mime_type`, reading Accept from `headers`.
+#[cfg(feature = "mime")]
+pub fn match_for_mime_type_from_headers(
+    headers: &impl HeaderSource,
+    mime_type: &[u8],
+) -> Option<MimeTypeMatch> {
+    match_for_mime_type(&headers.header_value(&http::header::ACCEPT), mime_type)
+}
+
+#[cfg(feature = "encoding")]
+impl SupportedEncodings {
+    /// Like `best_match`, reading Accept-Encoding from `headers`.
+    pub fn best_match_from_headers(
+        &self,
+        headers: &impl HeaderSource,
+    ) -> Option<(&[u8], EncodingMatch)> {
+        self.best_match(&headers.header_value(&http::header::ACCEPT_ENCODING))
+    }
+
+    /// Picks the coding to respond with, like `best_match_from_headers`,
+    /// but falls back to "identity", if supported, when no entry names it
+    /// and the header does not exclude it, as RFC 9110 allows. None means
+    /// the response should be 406 Not Acceptable.
+    pub fn negotiate_from_headers(&self, headers: &impl HeaderSource) -> Option<&[u8]> {
+        let header_value = headers.header_value(&http::header::ACCEPT_ENCODING);
+        if let Some((encoding, _)) = self.best_match(&header_value) {
+            return Some(encoding);
+        }
+        self.iter()
+            .find(|&e| e == b"identity")
+            .filter(|_| effective_q(&header_value, b"identity").is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::{ACCEPT, ACCEPT_ENCODING};
+
+    use super::*;
+
+    #[test]
+    fn test_header_value() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(&b""[..], &*headers.header_value(&ACCEPT_ENCODING));
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+        assert!(matches!(
+            headers.header_value(&ACCEPT_ENCODING),
+            Cow::Borrowed(b"gzip")
+        ));
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("br;q=0.5"));
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("zstd"));
+        assert_eq!(
+            &b"gzip, br;q=0.5, zstd"[..],
+            &*headers.header_value(&ACCEPT_ENCODING)
+        );
+        assert_eq!(&b""[..], &*headers.header_value(&ACCEPT));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_match_for_encoding_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("gzip;q=0.5"));
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("br"));
+        let m = match_for_encoding_from_headers(&headers, b"br").unwrap();
+        assert_eq!(1000, m.q.as_millis());
+        let value = HeaderValue::from_static("gzip;q=0.5");
+        let m = match_for_encoding_from_headers(&value, b"gzip").unwrap();
+        assert_eq!(500, m.q.as_millis());
+        assert!(match_for_encoding_from_headers(&HeaderMap::new(), b"gzip").is_none());
+
+        let supported = SupportedEncodings::new(["zstd", "gzip", "br"]).unwrap();
+        assert_eq!(
+            Some(&b"br"[..]),
+            supported.best_match_from_headers(&headers).map(|(e, _)| e)
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_negotiate_from_headers() {
+        let supported = SupportedEncodings::new(["br", "identity"]).unwrap();
+        let negotiate = |header_value: Option<&'static str>| {
+            let mut request = Request::builder();
+            if let Some(value) = header_value {
+                request = request.header(ACCEPT_ENCODING, value);
+            }
+            let (parts, ()) = request.body(()).unwrap().into_parts();
+            supported.negotiate_from_headers(&parts).map(<[u8]>::to_vec)
+        };
+        assert_eq!(Some(b"br".to_vec()), negotiate(Some("gzip, br")));
+        assert_eq!(Some(b"identity".to_vec()), negotiate(Some("gzip")));
+        assert_eq!(Some(b"identity".to_vec()), negotiate(None));
+        assert_eq!(None, negotiate(Some("gzip, identity;q=0")));
+
+        let request = Request::builder()
+            .header(ACCEPT_ENCODING, "br")
+            .body(())
+            .unwrap();
+        assert!(match_for_encoding_from_headers(&request, b"br").is_some());
+        let brotli_only = SupportedEncodings::new(["br"]).unwrap();
+        assert_eq!(None, brotli_only.negotiate_from_headers(&HeaderMap::new()));
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_match_for_mime_type_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(ACCEPT, HeaderValue::from_static("text/html"));
+        headers.append(ACCEPT, HeaderValue::from_static("image/*;q=0.8"));
+        let m = match_for_mime_type_from_headers(&headers, b"image/webp").unwrap();
+        assert_eq!(800, m.q.as_millis());
+    }
+}
@@ -0,0 +1,80 @@
+//! Accept-Language negotiation with the `LanguageIdentifier` of
+//! unic-langid, as Fluent uses.
+
+use unic_langid::LanguageIdentifier;
+
+use crate::{
+    language_matcher::{match_for_language, parse_accept_language, LanguageMatch},
+    matched::pick_best,
+};
+
+/// Like `match_for_language`, for a `LanguageIdentifier`.
+pub fn match_for_langid(input: &[u8], langid: &LanguageIdentifier) -> Option<LanguageMatch> {
+    match_for_language(input, langid.to_string().as_bytes())
+}
+
+/// Returns the ranges of an Accept-Language value as `LanguageIdentifier`s,
+/// highest q-value first, leaving out "*", q=0 and the ones unic-langid
+/// rejects. This is the requested list `fluent_langneg::negotiate_languages`
+/// takes. Returns None if `input` is malformed.
+pub fn requested_langids(input: &[u8]) -> Option<Vec<LanguageIdentifier>> {
+    let mut ranges = parse_accept_language(input)?;
+    ranges.retain_acceptable();
+    ranges.retain(|range, _| *range != b"*");
+    ranges.sort_by_q();
+    Some(
+        ranges
+            .into_iter()
+            .filter_map(|(range, _)| LanguageIdentifier::from_bytes(range).ok())
+            .collect(),
+    )
+}
+
+/// Picks the locale to respond in from `available`, in the server's order
+/// of preference, by the rules of `match_for_language`. Returns None if
+/// none is acceptable.
+pub fn negotiate_langid<'a>(
+    input: &[u8],
+    available: &'a [LanguageIdentifier],
+) -> Option<&'a LanguageIdentifier> {
+    pick_best(available.iter().map(|l| (l, match_for_langid(input, l))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language_matcher::LanguageMatchType;
+
+    fn langid(s: &str) -> LanguageIdentifier {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_match_for_langid() {
+        let m = match_for_langid(b"en;q=0.5", &langid("en-Latn-US")).unwrap();
+        assert_eq!(LanguageMatchType::Prefix, m.match_type);
+        assert_eq!(500, m.q.as_millis());
+    }
+
+    #[test]
+    fn test_requested_langids() {
+        assert_eq!(
+            Some(vec![langid("fr"), langid("en-US"), langid("de")]),
+            requested_langids(b"en-US;q=0.8, fr, de;q=0.8, *;q=0.5, ja;q=0")
+        );
+        assert_eq!(None, requested_langids(b"en_US"));
+    }
+
+    #[test]
+    fn test_negotiate_langid() {
+        let available = [langid("en-US"), langid("ja"), langid("de")];
+        let negotiate = |input: &[u8]| negotiate_langid(input, &available).cloned();
+        assert_eq!(Some(langid("ja")), negotiate(b"ja, en;q=0.9"));
+        // Basic filtering does not fall back from a range to its prefix.
+        assert_eq!(Some(langid("en-US")), negotiate(b"ja-JP, en;q=0.9"));
+        assert_eq!(Some(langid("en-US")), negotiate(b"fr, en;q=0.5"));
+        assert_eq!(Some(langid("de")), negotiate(b"en;q=0.1, de;q=0.2"));
+        assert_eq!(Some(langid("en-US")), negotiate(b"*"));
+        assert_eq!(None, negotiate(b"fr, *;q=0"));
+    }
+}
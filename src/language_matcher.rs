@@ -0,0 +1,136 @@
+//! Accept-Language matching by the basic filtering of RFC 4647: a range
+//! matches a tag equal to it or starting with it and a "-".
+
+use crate::{byte_slice::bytes_eq_ignore_case, lexer, matched::Match, weighted_list::WeightedList};
+
+/// Returns the entry of an Accept-Language value that applies to
+/// `language`, a tag like "en-US": an exact range over a prefix over "*",
+/// then the highest q-value, then the earlier entry. Returns None if no
+/// range matches or `input` is malformed.
+pub fn match_for_language(input: &[u8], language: &[u8]) -> Option<LanguageMatch> {
+    let mut best: Option<LanguageMatch> = None;
+    lexer::weighted_list(input, is_language_range, |range, q| {
+        let Some(match_type) = get_language_match_type(range, language) else {
+            return;
+        };
+        let cur = LanguageMatch { match_type, q };
+        if best.is_none_or(|b| cur > b) {
+            best = Some(cur);
+        }
+    })
+    .ok()?;
+    best
+}
+
+/// Like `match_for_language`, for a header value and tag held as `&str`.
+pub fn match_for_language_str(input: &str, language: &str) -> Option<LanguageMatch> {
+    match_for_language(input.as_bytes(), language.as_bytes())
+}
+
+/// Returns the language ranges of an Accept-Language value as written,
+/// with their q-values, in order. Returns None if `input` is malformed.
+pub fn parse_accept_language(input: &[u8]) -> Option<WeightedList<&[u8]>> {
+    let mut ranges = WeightedList::new();
+    lexer::weighted_list(input, is_language_range, |range, q| ranges.push(range, q)).ok()?;
+    Some(ranges)
+}
+
+/// Checks for "*" or `1*8ALPHA *("-" 1*8alphanum)`.
+fn is_language_range(range: &[u8]) -> bool {
+    if range == b"*" {
+        return true;
+    }
+    range.split(|&b| b == b'-').enumerate().all(|(i, subtag)| {
+        (1..=8).contains(&subtag.len())
+            && subtag.iter().all(|b| {
+                if i == 0 {
+                    b.is_ascii_alphabetic()
+                } else {
+                    b.is_ascii_alphanumeric()
+                }
+            })
+    })
+}
+
+pub(crate) fn get_language_match_type(range: &[u8], language: &[u8]) -> Option<LanguageMatchType> {
+    if range == b"*" {
+        Some(LanguageMatchType::Wildcard)
+    } else if bytes_eq_ignore_case(range, language) {
+        Some(LanguageMatchType::Exact)
+    } else if language.len() > range.len()
+        && language[range.len()] == b'-'
+        && bytes_eq_ignore_case(range, &language[..range.len()])
+    {
+        Some(LanguageMatchType::Prefix)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum LanguageMatchType {
+    Wildcard,
+    /// The range is a prefix of the tag, like "en" for "en-US".
+    Prefix,
+    Exact,
+}
+
+pub type LanguageMatch = Match<LanguageMatchType>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::q_value::QValue;
+
+    fn m(match_type: LanguageMatchType, millis: u16) -> Option<LanguageMatch> {
+        Some(LanguageMatch {
+            match_type,
+            q: QValue::from_millis(millis).unwrap(),
+        })
+    }
+
+    #[test]
+    fn test_match_for_language() {
+        use LanguageMatchType::*;
+
+        let input = b"en-US, en;q=0.8, ja;q=0.5, *;q=0.1";
+        assert_eq!(m(Exact, 1000), match_for_language(input, b"en-us"));
+        assert_eq!(m(Prefix, 800), match_for_language(input, b"en-GB"));
+        assert_eq!(m(Exact, 800), match_for_language(input, b"en"));
+        assert_eq!(m(Wildcard, 100), match_for_language(input, b"fr"));
+        assert_eq!(m(Prefix, 500), match_for_language(input, b"ja-JP"));
+        assert_eq!(None, match_for_language(b"en", b"eng"));
+        assert_eq!(m(Exact, 0), match_for_language(b"*, de;q=0", b"de"));
+        assert_eq!(m(Prefix, 800), match_for_language_str("en;q=0.8", "en-GB"));
+        assert_eq!(None, match_for_language(b"", b"en"));
+    }
+
+    #[test]
+    fn test_match_for_language_malformed() {
+        for input in [
+            &b"en_US"[..],
+            b"toolongsubtag",
+            b"1en",
+            b"en-",
+            b"en;level=1",
+            b"en;q=2",
+            b"en de",
+        ] {
+            assert_eq!(None, match_for_language(input, b"en"), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_accept_language() {
+        let ranges = parse_accept_language(b"de-CH ,, fr;Q=0.9,*;q=0").unwrap();
+        let ranges: Vec<_> = ranges.iter().map(|(r, q)| (*r, q.as_millis())).collect();
+        assert_eq!(
+            vec![(&b"de-CH"[..], 1000), (&b"fr"[..], 900), (&b"*"[..], 0)],
+            ranges
+        );
+        assert_eq!(None, parse_accept_language(b"de;q"));
+    }
+}
@@ -1,19 +1,49 @@
-#[derive(Debug, PartialEq)]
-pub(crate) struct ParseError;
+//! The lexers every matcher parses with. Each takes the input and a
+//! `&mut Cursor` into it, and on success leaves the cursor after what it
+//! read. On failure the cursor position is unspecified, so callers that
+//! try alternatives copy it first, as `alt` does. The combinators are
+//! public through the `combinators` module.
+//!
+//! The `*_const` functions are `const fn` versions for
+//! `match_for_encoding_const`, with the same grammar and cursor handling.
 
-pub(crate) type ParseResult = Result<(), ParseError>;
+use std::{error::Error, fmt};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub(crate) struct Cursor(pub usize);
+use memchr::memchr2;
+
+#[cfg(any(feature = "charset", feature = "language"))]
+use crate::byte_slice::bytes_eq_ignore_case;
+use crate::q_value::{QValue, Q_VALUE_FRAC_MAX_DIGITS};
+#[cfg(feature = "simd")]
+use crate::simd;
+
+/// The input does not match what a parser expects. It carries no position;
+/// the cursor tells where parsing stopped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseError;
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("parse error")
+    }
+}
+
+impl Error for ParseError {}
+
+pub type ParseResult = Result<(), ParseError>;
+
+/// A byte offset into the input being parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Cursor(pub usize);
 
 impl Cursor {
     #[inline]
-    pub fn eof(&self, input: &[u8]) -> bool {
+    pub const fn eof(&self, input: &[u8]) -> bool {
         self.0 >= input.len()
     }
 
     #[inline]
-    pub fn peek(&self, input: &[u8]) -> Option<u8> {
+    pub const fn peek(&self, input: &[u8]) -> Option<u8> {
         if self.0 < input.len() {
             Some(input[self.0])
         } else {
@@ -22,17 +52,19 @@ impl Cursor {
     }
 
     #[inline]
-    pub fn advance(&mut self, n: usize) {
+    pub const fn advance(&mut self, n: usize) {
         self.0 += n;
     }
 
+    /// Returns the input from this cursor up to `end`.
     #[inline]
     pub fn slice<'a>(&self, input: &'a [u8], end: Cursor) -> &'a [u8] {
         &input[self.0..end.0]
     }
 }
 
-pub(crate) fn byte(b: u8) -> impl Fn(&[u8], &mut Cursor) -> ParseResult {
+/// Reads the byte `b`.
+pub fn byte(b: u8) -> impl Fn(&[u8], &mut Cursor) -> ParseResult {
     move |input: &[u8], c: &mut Cursor| {
         if let Some(b2) = c.peek(input) {
             if b2 == b {
@@ -44,6 +76,7 @@ pub(crate) fn byte(b: u8) -> impl Fn(&[u8], &mut Cursor) -> ParseResult {
     }
 }
 
+#[allow(dead_code)]
 fn match_m_n<F>(pred: F, m: usize, n: usize) -> impl Fn(&[u8], &mut Cursor) -> ParseResult
 where
     F: Fn(u8) -> bool,
@@ -69,6 +102,7 @@ where
     }
 }
 
+#[cfg_attr(feature = "simd", allow(dead_code))]
 fn match_one_or_more<F>(pred: F) -> impl Fn(&[u8], &mut Cursor) -> ParseResult
 where
     F: Fn(u8) -> bool,
@@ -105,7 +139,8 @@ where
     }
 }
 
-fn pair(
+/// Runs `parser1`, then `parser2`.
+pub fn pair(
     parser1: impl Fn(&[u8], &mut Cursor) -> ParseResult,
     parser2: impl Fn(&[u8], &mut Cursor) -> ParseResult,
 ) -> impl Fn(&[u8], &mut Cursor) -> ParseResult {
@@ -115,7 +150,9 @@ fn pair(
     }
 }
 
-fn opt(
+/// Runs `parser`, and if it fails, moves the cursor back to where it was
+/// and succeeds.
+pub fn opt(
     parser: impl Fn(&[u8], &mut Cursor) -> ParseResult,
 ) -> impl Fn(&[u8], &mut Cursor) -> ParseResult {
     move |input: &[u8], c: &mut Cursor| {
@@ -130,7 +167,9 @@ fn opt(
     }
 }
 
-pub(crate) fn alt(
+/// Runs `parser1`, and if it fails, moves the cursor back to where it was
+/// and runs `parser2`.
+pub fn alt(
     parser1: impl Fn(&[u8], &mut Cursor) -> ParseResult,
     parser2: impl Fn(&[u8], &mut Cursor) -> ParseResult,
 ) -> impl Fn(&[u8], &mut Cursor) -> ParseResult {
@@ -146,7 +185,10 @@ pub(crate) fn alt(
     }
 }
 
-fn escaped<F, G>(
+/// Reads bytes that `is_normal_char` accepts, and pairs of `escape_char`
+/// and a byte `is_escapable_char` accepts, as long as there are any. Fails
+/// only on an escape that is not followed by an escapable byte.
+pub fn escaped<F, G>(
     is_normal_char: F,
     escape_char: u8,
     is_escapable_char: G,
@@ -178,15 +220,66 @@ where
     }
 }
 
-pub(crate) fn token(input: &[u8], c: &mut Cursor) -> ParseResult {
+/// Reads a `token` of RFC 9110: one or more `tchar`s.
+#[cfg(not(feature = "simd"))]
+pub fn token(input: &[u8], c: &mut Cursor) -> ParseResult {
     match_one_or_more(is_tchar)(input, c)
 }
 
+/// Reads a `token` of RFC 9110: one or more `tchar`s.
+#[cfg(feature = "simd")]
+pub fn token(input: &[u8], c: &mut Cursor) -> ParseResult {
+    let n = simd::tchar_prefix_len(input.get(c.0..).unwrap_or_default());
+    if n == 0 {
+        return Err(ParseError);
+    }
+    c.advance(n);
+    Ok(())
+}
+
+pub(crate) fn is_token(input: &[u8]) -> bool {
+    let mut c = Cursor(0);
+    token(input, &mut c).is_ok() && c.eof(input)
+}
+
+/// Packs a 256-entry table into 32 bytes at compile time, so only the
+/// bitmask ends up in the binary.
+#[cfg(not(feature = "small-tables"))]
+const fn to_bitmask(table: &[bool; 256]) -> [u64; 4] {
+    let mut bits = [0; 4];
+    let mut i = 0;
+    while i < 256 {
+        if table[i] {
+            bits[i / 64] |= 1 << (i % 64);
+        }
+        i += 1;
+    }
+    bits
+}
+
+#[cfg(not(feature = "small-tables"))]
 #[inline]
-fn is_tchar(c: u8) -> bool {
-    TCHAR_TABLE[c as usize]
+const fn test_bit(bits: &[u64; 4], c: u8) -> bool {
+    bits[(c / 64) as usize] & (1 << (c % 64)) != 0
 }
 
+#[cfg(not(feature = "small-tables"))]
+#[inline]
+pub(crate) const fn is_tchar(c: u8) -> bool {
+    test_bit(&TCHAR_BITS, c)
+}
+
+#[cfg(feature = "small-tables")]
+pub(crate) const fn is_tchar(c: u8) -> bool {
+    matches!(c,
+        b'!' | b'#'..=b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~'
+        | b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z')
+}
+
+#[cfg(not(feature = "small-tables"))]
+const TCHAR_BITS: [u64; 4] = to_bitmask(&TCHAR_TABLE);
+
+#[cfg(not(feature = "small-tables"))]
 #[rustfmt::skip]
 const TCHAR_TABLE: [bool; 256] = [
     // tchar = "!" / "#" / "$" / "%" / "&" / "'" / "*" / "+" / "-" / "." /
@@ -209,17 +302,48 @@ const TCHAR_TABLE: [bool; 256] = [
     false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
 ];
 
-pub(crate) fn quoted_string(input: &[u8], c: &mut Cursor) -> ParseResult {
+/// Reads a `quoted-string` of RFC 9110, including the quotes.
+#[cfg(not(feature = "simd"))]
+pub fn quoted_string(input: &[u8], c: &mut Cursor) -> ParseResult {
     byte(b'"')(input, c)?;
     escaped(is_qdtext, b'\\', is_quoted_pair_char)(input, c)?;
     byte(b'"')(input, c)
 }
 
+/// Reads a `quoted-string` of RFC 9110, including the quotes.
+#[cfg(feature = "simd")]
+pub fn quoted_string(input: &[u8], c: &mut Cursor) -> ParseResult {
+    byte(b'"')(input, c)?;
+    loop {
+        c.advance(simd::qdtext_prefix_len(
+            input.get(c.0..).unwrap_or_default(),
+        ));
+        if byte(b'\\')(input, c).is_err() {
+            break;
+        }
+        match c.peek(input) {
+            Some(b) if is_quoted_pair_char(b) => c.advance(1),
+            _ => return Err(ParseError),
+        }
+    }
+    byte(b'"')(input, c)
+}
+
+#[cfg(not(feature = "small-tables"))]
 #[inline]
-fn is_qdtext(c: u8) -> bool {
-    QDTEXT_TABLE[c as usize]
+pub(crate) const fn is_qdtext(c: u8) -> bool {
+    test_bit(&QDTEXT_BITS, c)
+}
+
+#[cfg(feature = "small-tables")]
+pub(crate) const fn is_qdtext(c: u8) -> bool {
+    matches!(c, b'\t' | b' ' | b'!' | 0x23..=0x5b | 0x5d..=0x7e | 0x80..=0xff)
 }
 
+#[cfg(not(feature = "small-tables"))]
+const QDTEXT_BITS: [u64; 4] = to_bitmask(&QDTEXT_TABLE);
+
+#[cfg(not(feature = "small-tables"))]
 #[rustfmt::skip]
 const QDTEXT_TABLE: [bool; 256] = [
     // qdtext = HTAB / SP / "!" / %x23-5B ; '#'-'['
@@ -244,11 +368,21 @@ const QDTEXT_TABLE: [bool; 256] = [
     true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,
 ];
 
+#[cfg(not(feature = "small-tables"))]
 #[inline]
-fn is_quoted_pair_char(c: u8) -> bool {
-    QUOTED_PAIR_CHAR_TABLE[c as usize]
+pub(crate) const fn is_quoted_pair_char(c: u8) -> bool {
+    test_bit(&QUOTED_PAIR_CHAR_BITS, c)
 }
 
+#[cfg(feature = "small-tables")]
+pub(crate) const fn is_quoted_pair_char(c: u8) -> bool {
+    matches!(c, b'\t' | b' '..=0x7e | 0x80..=0xff)
+}
+
+#[cfg(not(feature = "small-tables"))]
+const QUOTED_PAIR_CHAR_BITS: [u64; 4] = to_bitmask(&QUOTED_PAIR_CHAR_TABLE);
+
+#[cfg(not(feature = "small-tables"))]
 #[rustfmt::skip]
 const QUOTED_PAIR_CHAR_TABLE: [bool; 256] = [
     // quoted-pair = "\" ( HTAB / SP / VCHAR / obs-text )
@@ -273,7 +407,8 @@ const QUOTED_PAIR_CHAR_TABLE: [bool; 256] = [
     true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,  true,
 ];
 
-pub(crate) fn ows(input: &[u8], c: &mut Cursor) {
+/// Reads optional whitespace, spaces and tabs. It never fails.
+pub fn ows(input: &[u8], c: &mut Cursor) {
     match_zero_or_more(|b| matches!(b, b' ' | b'\t'))(input, c)
 }
 
@@ -282,19 +417,209 @@ fn is_digit(b: u8) -> bool {
     b.is_ascii_digit()
 }
 
-pub(crate) fn q_value(input: &[u8], c: &mut Cursor) -> ParseResult {
-    alt(
-        pair(byte(b'0'), opt(pair(byte(b'.'), match_m_n(is_digit, 0, 3)))),
-        pair(
-            byte(b'1'),
-            opt(pair(byte(b'.'), match_m_n(|b| b == b'0', 0, 3))),
-        ),
-    )(input, c)
+/// Lexes a q-value and computes its weight in the same pass. Fractional
+/// digits past the third, and non-zero ones after "1.", are left unread.
+///
+/// The fractional part is read without data-dependent branches: three
+/// bytes are loaded, each is checked by a range compare, and the valid
+/// leading digits are combined with a single multiply.
+pub(crate) fn q_value(input: &[u8], c: &mut Cursor) -> Result<QValue, ParseError> {
+    let (max_frac_digit, int_millis) = match c.peek(input) {
+        Some(b'0') => (9, 0),
+        Some(b'1') => (0, QValue::ONE.as_millis()),
+        _ => return Err(ParseError),
+    };
+    c.advance(1);
+    if c.peek(input) != Some(b'.') {
+        return QValue::from_millis(int_millis).map_err(|_| ParseError);
+    }
+    c.advance(1);
+
+    const N: usize = Q_VALUE_FRAC_MAX_DIGITS as usize;
+    let rest = input.get(c.0..).unwrap_or_default();
+    let len = rest.len().min(N);
+    // 0xff is never a digit, so it pads a value that ends early.
+    let mut digits = [0xff; N];
+    digits[..len].copy_from_slice(&rest[..len]);
+    let digits = digits.map(|b| b.wrapping_sub(b'0'));
+
+    // valid[i] is 1 when digits 0..=i are all valid.
+    let v0 = (digits[0] <= max_frac_digit) as u64;
+    let v1 = v0 & (digits[1] <= max_frac_digit) as u64;
+    let v2 = v1 & (digits[2] <= max_frac_digit) as u64;
+
+    // One digit per 16-bit lane; the multiply sums 100*d0 + 10*d1 + d2
+    // into the lane at bit 32 without carries between lanes.
+    let packed =
+        (digits[0] as u64 * v0) | (digits[1] as u64 * v1) << 16 | (digits[2] as u64 * v2) << 32;
+    let frac_millis = (packed.wrapping_mul(1 | 10 << 16 | 100 << 32) >> 32) & 0xffff;
+
+    c.advance((v0 + v1 + v2) as usize);
+    QValue::from_millis(int_millis + frac_millis as u16).map_err(|_| ParseError)
+}
+
+/// Like `q_value`, but skips any digits after the third fractional one.
+/// Other leftover digits, as in "1.5" or "05", are an error.
+pub(crate) fn q_value_truncating(input: &[u8], c: &mut Cursor) -> Result<QValue, ParseError> {
+    let start = c.0;
+    let q = q_value(input, c)?;
+    if c.0 - start < 2 + Q_VALUE_FRAC_MAX_DIGITS as usize && c.peek(input).is_some_and(is_digit) {
+        return Err(ParseError);
+    }
+    match_zero_or_more(is_digit)(input, c);
+    Ok(q)
+}
+
+/// Moves the cursor past the next comma outside a quoted string and the
+/// whitespace after it, or to the end of the input.
+pub(crate) fn skip_list_element(input: &[u8], c: &mut Cursor) {
+    while let Some(i) = memchr2(b',', b'"', &input[c.0..]) {
+        c.advance(i + 1);
+        if input[c.0 - 1] == b',' {
+            ows(input, c);
+            return;
+        }
+        // Skip the rest of the quoted string, honoring quoted-pairs.
+        loop {
+            match memchr2(b'"', b'\\', &input[c.0..]) {
+                Some(i) => {
+                    c.advance(i + 1);
+                    if input[c.0 - 1] == b'"' {
+                        break;
+                    }
+                    if c.eof(input) {
+                        return;
+                    }
+                    c.advance(1);
+                }
+                None => {
+                    c.0 = input.len();
+                    return;
+                }
+            }
+        }
+    }
+    c.0 = input.len();
+}
+
+#[cfg(feature = "encoding")]
+pub(crate) const fn token_const(input: &[u8], c: &mut Cursor) -> ParseResult {
+    let start = c.0;
+    while !c.eof(input) && is_tchar(input[c.0]) {
+        c.advance(1);
+    }
+    if c.0 == start {
+        Err(ParseError)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "encoding")]
+pub(crate) const fn ows_const(input: &[u8], c: &mut Cursor) {
+    while !c.eof(input) && matches!(input[c.0], b' ' | b'\t') {
+        c.advance(1);
+    }
+}
+
+#[cfg(feature = "encoding")]
+pub(crate) const fn q_value_const(input: &[u8], c: &mut Cursor) -> Result<QValue, ParseError> {
+    let (max_frac_digit, mut millis) = match c.peek(input) {
+        Some(b'0') => (b'9', 0),
+        Some(b'1') => (b'0', QValue::ONE.as_millis()),
+        _ => return Err(ParseError),
+    };
+    c.advance(1);
+    if matches!(c.peek(input), Some(b'.')) {
+        c.advance(1);
+        let mut scale = 100;
+        while scale > 0 && !c.eof(input) && input[c.0] >= b'0' && input[c.0] <= max_frac_digit {
+            millis += (input[c.0] - b'0') as u16 * scale;
+            scale /= 10;
+            c.advance(1);
+        }
+    }
+    match QValue::from_millis(millis) {
+        Ok(q) => Ok(q),
+        Err(_) => Err(ParseError),
+    }
+}
+
+#[cfg(feature = "encoding")]
+pub(crate) const fn quoted_string_const(input: &[u8], c: &mut Cursor) -> ParseResult {
+    if !matches!(c.peek(input), Some(b'"')) {
+        return Err(ParseError);
+    }
+    c.advance(1);
+    while let Some(b) = c.peek(input) {
+        if is_qdtext(b) {
+            c.advance(1);
+        } else if b == b'\\' {
+            if c.0 + 1 < input.len() && is_quoted_pair_char(input[c.0 + 1]) {
+                c.advance(2);
+            } else {
+                return Err(ParseError);
+            }
+        } else {
+            break;
+        }
+    }
+    if matches!(c.peek(input), Some(b'"')) {
+        c.advance(1);
+        Ok(())
+    } else {
+        Err(ParseError)
+    }
+}
+
+/// Parses a list of `element [ weight ]`, as in Accept-Language and
+/// Accept-Charset, calling `f` with each element and its q-value. Elements
+/// are tokens that `is_valid` accepts; empty list elements are skipped.
+#[cfg(any(feature = "charset", feature = "language"))]
+pub(crate) fn weighted_list<'a>(
+    input: &'a [u8],
+    is_valid: impl Fn(&[u8]) -> bool,
+    mut f: impl FnMut(&'a [u8], QValue),
+) -> ParseResult {
+    let mut c = Cursor(0);
+    loop {
+        ows(input, &mut c);
+        if c.eof(input) {
+            return Ok(());
+        }
+        if c.peek(input) != Some(b',') {
+            let start = c;
+            token(input, &mut c)?;
+            let element = start.slice(input, c);
+            if !is_valid(element) {
+                return Err(ParseError);
+            }
+            ows(input, &mut c);
+            let mut q = QValue::ONE;
+            if byte(b';')(input, &mut c).is_ok() {
+                ows(input, &mut c);
+                let name_start = c;
+                token(input, &mut c)?;
+                if !bytes_eq_ignore_case(name_start.slice(input, c), b"q") {
+                    return Err(ParseError);
+                }
+                byte(b'=')(input, &mut c)?;
+                q = q_value(input, &mut c)?;
+                ows(input, &mut c);
+            }
+            f(element, q);
+            if c.eof(input) {
+                return Ok(());
+            }
+        }
+        byte(b',')(input, &mut c)?;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::for_each_input;
 
     #[test]
     fn test_is_tchar() {
@@ -379,6 +704,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_token() {
+        assert!(is_token(b"gzip"));
+        assert!(is_token(b"*"));
+        assert!(!is_token(b""));
+        assert!(!is_token(b"gzip "));
+        assert!(!is_token(b"image/webp"));
+    }
+
     #[test]
     fn test_quoted_string() {
         {
@@ -476,83 +810,191 @@ mod tests {
         {
             let input = b"0";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(Ok(QValue::from_millis(0).unwrap()), q_value(input, &mut c));
             assert_eq!(Cursor(1), c);
         }
         {
             let input = b"0.";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(Ok(QValue::from_millis(0).unwrap()), q_value(input, &mut c));
             assert_eq!(Cursor(2), c);
         }
         {
             let input = b"0.,";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(Ok(QValue::from_millis(0).unwrap()), q_value(input, &mut c));
             assert_eq!(Cursor(2), c);
         }
         {
             let input = b"0.8";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(800).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(3), c);
         }
         {
             let input = b"0.8,";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(800).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(3), c);
         }
         {
             let input = b"0.1239";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(123).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(5), c);
         }
         {
             let input = b"1";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(1), c);
         }
         {
             let input = b"1.";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(2), c);
         }
         {
             let input = b"1.0";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(3), c);
         }
         {
             let input = b"1.00";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(4), c);
         }
         {
             let input = b"1.000";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(5), c);
         }
         {
             let input = b"1.0000";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(5), c);
         }
         {
             let input = b"1.1";
             let mut c = Cursor(0);
-            assert_eq!(Ok(()), q_value(input, &mut c));
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value(input, &mut c)
+            );
             assert_eq!(Cursor(2), c);
         }
     }
 
+    #[test]
+    fn test_q_value_matches_try_from() {
+        for_each_input(b"0159.a", 1..=6, |input| {
+            let mut c = Cursor(0);
+            if let Ok(q) = q_value(input, &mut c) {
+                assert_eq!(Ok(q), QValue::try_from(&input[..c.0]), "{:?}", input);
+            } else {
+                assert!(!matches!(input[0], b'0' | b'1'));
+            }
+        });
+    }
+
+    #[test]
+    fn test_q_value_truncating() {
+        {
+            let input = b"0.12345,";
+            let mut c = Cursor(0);
+            assert_eq!(
+                Ok(QValue::from_millis(123).unwrap()),
+                q_value_truncating(input, &mut c)
+            );
+            assert_eq!(Cursor(7), c);
+        }
+        {
+            let input = b"1";
+            let mut c = Cursor(0);
+            assert_eq!(
+                Ok(QValue::from_millis(1000).unwrap()),
+                q_value_truncating(input, &mut c)
+            );
+            assert_eq!(Cursor(1), c);
+        }
+        {
+            let input = b"1.0009";
+            let mut c = Cursor(0);
+            assert_eq!(Ok(QValue::ONE), q_value_truncating(input, &mut c));
+            assert_eq!(Cursor(6), c);
+        }
+        for input in [&b"a"[..], b"1.5", b"1.05", b"15", b"05"] {
+            let mut c = Cursor(0);
+            assert_eq!(Err(ParseError), q_value_truncating(input, &mut c));
+        }
+    }
+
+    #[test]
+    fn test_skip_list_element() {
+        {
+            let input = b"gzip;q=/, br";
+            let mut c = Cursor(0);
+            skip_list_element(input, &mut c);
+            assert_eq!(Cursor(10), c);
+        }
+        {
+            let input = br#"gzip;a="x,\"y", br"#;
+            let mut c = Cursor(0);
+            skip_list_element(input, &mut c);
+            assert_eq!(Cursor(16), c);
+        }
+        {
+            let input = br#"gzip;a="x\"#;
+            let mut c = Cursor(0);
+            skip_list_element(input, &mut c);
+            assert_eq!(Cursor(input.len()), c);
+        }
+        {
+            let input = br#"gzip;a="x\""#;
+            let mut c = Cursor(0);
+            skip_list_element(input, &mut c);
+            assert_eq!(Cursor(input.len()), c);
+        }
+        {
+            let input = b"gzip;a=b";
+            let mut c = Cursor(5);
+            skip_list_element(input, &mut c);
+            assert_eq!(Cursor(input.len()), c);
+        }
+    }
+
     #[test]
     fn test_alt() {
         {
@@ -594,6 +1036,44 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_const_lexers_match_runtime() {
+        let inputs: &[&[u8]] = &[
+            b"gzip, br",
+            b"",
+            b" \t;",
+            b"\"a\\\"b\" x",
+            b"\"unterminated",
+            b"\"bad\\\x7f\"",
+            b"0.5;",
+            b"1.000",
+            b"1.5",
+            b"0.1234",
+            b"2",
+        ];
+        for &input in inputs {
+            // The cursor is only specified on success.
+            let run = |f: fn(&[u8], &mut Cursor) -> ParseResult| {
+                let mut c = Cursor(0);
+                let result = f(input, &mut c);
+                result.map(|()| c)
+            };
+            assert_eq!(run(token), run(token_const), "{:?}", input);
+            assert_eq!(run(quoted_string), run(quoted_string_const), "{:?}", input);
+            let (mut c1, mut c2) = (Cursor(0), Cursor(0));
+            ows(input, &mut c1);
+            ows_const(input, &mut c2);
+            assert_eq!(c1, c2);
+            let (mut c1, mut c2) = (Cursor(0), Cursor(0));
+            let (q1, q2) = (q_value(input, &mut c1), q_value_const(input, &mut c2));
+            assert_eq!(q1, q2, "{:?}", input);
+            if q1.is_ok() {
+                assert_eq!(c1, c2, "{:?}", input);
+            }
+        }
+    }
+
     #[test]
     fn test_cursor_derive() {
         assert_eq!("Cursor(0)".to_string(), format!("{:?}", Cursor(0).clone()));
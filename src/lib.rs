@@ -1,9 +1,208 @@
-pub use encoding_matcher::match_for_encoding;
-pub use mime_type_matcher::match_for_mime_type;
+//! Matching of the Accept-Encoding, Accept and other Accept-* header
+//! values of HTTP requests.
+//!
+//! The root holds the whole Rust API: the `match_for_*` functions and their
+//! result types, the parsed header types, `SupportedEncodings` and `Matcher`
+//! for negotiation, and the builders. The `c` module holds the C API, which
+//! is used through `include/accept_encoding.h` rather than from Rust.
+//!
+//! ```
+//! # #[cfg(feature = "encoding")] {
+//! use accept_encoding::{match_for_encoding, EncodingMatch, EncodingMatchType, QValue};
+//!
+//! let m: Option<EncodingMatch> = match_for_encoding(b"gzip;q=0.5, br", b"gzip");
+//! assert_eq!(
+//!     Some(EncodingMatch::new(EncodingMatchType::Exact, QValue::const_millis(500))),
+//!     m
+//! );
+//! # }
+//! ```
 
+// Without the encoding or mime matcher most of the shared parsing code has
+// no callers.
+#![cfg_attr(not(any(feature = "encoding", feature = "mime")), allow(unused))]
+
+#[cfg(feature = "mime")]
+pub use accept::Accept;
+#[cfg(feature = "encoding")]
+pub use accept_encoding::AcceptEncoding;
+#[cfg(feature = "mime")]
+pub use accept_post::choose_request_media_type;
+#[cfg(feature = "rayon")]
+pub use batch::match_for_encoding_batch_par;
+#[cfg(feature = "encoding")]
+pub use batch::{EncodingBatchMatcher, EncodingBatchResults};
+#[cfg(feature = "mime")]
+pub use builder::AcceptBuilder;
+#[cfg(feature = "encoding")]
+pub use builder::AcceptEncodingBuilder;
+#[cfg(feature = "cache")]
+pub use cache::MatcherCache;
+#[cfg(feature = "charset")]
+pub use charset_matcher::{
+    match_for_charset, match_for_charset_str, parse_accept_charset, CharsetMatch, CharsetMatchType,
+};
+#[cfg(feature = "encoding")]
+pub use const_matcher::match_for_encoding_const;
+#[cfg(feature = "encoding")]
+pub use content_coding::ContentCoding;
+#[cfg(feature = "encoding")]
+pub use encoding_matcher::{
+    effective_q, explain_encoding, match_for_encoding, match_for_encoding_detailed,
+    match_for_encoding_name, match_for_encoding_str, match_for_encoding_with_options,
+    match_for_encoding_with_warnings, validate_accept_encoding, EncodingMatch,
+    EncodingMatchDetailed, EncodingMatchType, EncodingName, InvalidEncodingNameError,
+};
+pub use explain::{EntryVerdict, ExplainedEntry, Explanation};
+#[cfg(feature = "encoding")]
+pub use grpc::grpc_accepts_encoding;
+#[cfg(any(
+    feature = "charset",
+    feature = "encoding",
+    feature = "language",
+    feature = "mime"
+))]
+pub use header_ext::AcceptHeaderExt;
+#[cfg(all(feature = "http", feature = "encoding"))]
+pub use http_headers::match_for_encoding_from_headers;
+#[cfg(all(feature = "http", feature = "mime"))]
+pub use http_headers::match_for_mime_type_from_headers;
+#[cfg(feature = "http")]
+pub use http_headers::HeaderSource;
+#[cfg(feature = "unic-langid")]
+pub use langid::{match_for_langid, negotiate_langid, requested_langids};
+#[cfg(feature = "language")]
+pub use language_matcher::{
+    match_for_language, match_for_language_str, parse_accept_language, LanguageMatch,
+    LanguageMatchType,
+};
+pub use matched::{pick_best, Match};
+#[cfg(any(feature = "encoding", feature = "language", feature = "mime"))]
+pub use matcher::{Limits, Matcher, MatcherBuilder, TieBreak};
+#[cfg(feature = "mime")]
+pub use mime_type_matcher::{
+    explain_mime_type, match_for_mime_type, match_for_mime_type_ref, match_for_mime_type_str,
+    match_for_mime_type_with_options, match_for_mime_type_with_warnings, try_match_for_mime_type,
+    InvalidMimeTypeError, MimeTypeMatch, MimeTypeMatchType, MimeTypeRef,
+};
+#[cfg(any(
+    feature = "charset",
+    feature = "encoding",
+    feature = "language",
+    feature = "mime"
+))]
+pub use negotiation_context::NegotiationContext;
+#[cfg(feature = "charset")]
+pub use negotiator::CharsetNegotiator;
+pub use negotiator::ContentNegotiator;
+#[cfg(feature = "encoding")]
+pub use negotiator::EncodingNegotiator;
+#[cfg(feature = "language")]
+pub use negotiator::LanguageNegotiator;
+#[cfg(feature = "mime")]
+pub use negotiator::MimeTypeNegotiator;
+pub use options::{MatchOptions, RegistryCheck};
+#[allow(deprecated)]
+pub use q_value::InvaliQValueError;
+pub use q_value::{InvalidQValueError, QValue};
+#[cfg(feature = "encoding")]
+pub use rewrite::{add_encoding, remove_encoding};
+#[cfg(feature = "encoding")]
+pub use streaming::{Feed, StreamingEncodingMatcher};
+#[cfg(feature = "encoding")]
+pub use supported_encodings::{NegotiatedEncoding, SupportedEncodings};
+pub use warning::{Warning, WarningReason};
+pub use weighted_list::WeightedList;
+
+#[cfg(feature = "mime")]
+mod accept;
+#[cfg(feature = "encoding")]
+mod accept_encoding;
+#[cfg(feature = "mime")]
+mod accept_post;
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "encoding")]
+mod batch;
+#[cfg(any(feature = "encoding", feature = "mime"))]
+mod builder;
 mod byte_slice;
+#[cfg(feature = "capi")]
+#[doc(hidden)]
 pub mod c;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "charset")]
+mod charset_matcher;
+pub mod combinators;
+#[cfg(feature = "encoding")]
+mod const_matcher;
+#[cfg(feature = "encoding")]
+mod content_coding;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+#[cfg(feature = "encoding")]
 mod encoding_matcher;
+mod explain;
+#[cfg(feature = "encoding")]
+mod grpc;
+#[cfg(any(
+    feature = "charset",
+    feature = "encoding",
+    feature = "language",
+    feature = "mime"
+))]
+mod header_ext;
+#[cfg(feature = "http")]
+mod http_headers;
+#[cfg(feature = "unic-langid")]
+mod langid;
+#[cfg(feature = "language")]
+mod language_matcher;
 mod lexer;
+#[cfg(feature = "mlua")]
+mod lua;
+mod matched;
+#[cfg(any(feature = "encoding", feature = "language", feature = "mime"))]
+mod matcher;
+#[cfg(feature = "mime")]
 mod mime_type_matcher;
+#[cfg(any(
+    feature = "charset",
+    feature = "encoding",
+    feature = "language",
+    feature = "mime"
+))]
+mod negotiation_context;
+mod negotiator;
+#[cfg(feature = "napi")]
+mod node;
+mod options;
+#[cfg(feature = "poem")]
+pub mod poem;
+#[cfg(feature = "proxy-wasm")]
+pub mod proxy_wasm;
 mod q_value;
+pub mod registry;
+#[cfg(feature = "encoding")]
+mod rewrite;
+#[cfg(feature = "ruby")]
+mod ruby;
+#[cfg(feature = "simd")]
+mod simd;
+#[cfg(feature = "encoding")]
+mod streaming;
+#[cfg(feature = "encoding")]
+mod supported_encodings;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "tracing")]
+mod trace;
+mod warning;
+#[cfg(feature = "warp")]
+pub mod warp;
+mod weighted_list;
+#[cfg(feature = "wit")]
+mod wit;
@@ -0,0 +1,108 @@
+//! A Lua module for OpenResty and other LuaJIT hosts, loaded with
+//! `require "accept_encoding_native"` instead of declaring the C functions
+//! with `ffi.cdef` as accept_encoding.lua does.
+
+use std::ffi::c_int;
+
+use mlua::{lua_State, Lua, Result, String as LuaString, Table, Value};
+
+use crate::{
+    encoding_matcher::{match_for_encoding, EncodingMatchType, Target},
+    mime_type_matcher::{match_for_mime_type, MimeTypeMatchType},
+    options::MatchOptions,
+    q_value::QValue,
+    supported_encodings::negotiate,
+};
+
+/// The entry point `require` looks up. It does what `mlua::lua_module`
+/// generates.
+///
+/// # Safety
+///
+/// `state` must be a Lua state calling this as a C function.
+#[no_mangle]
+pub unsafe extern "C-unwind" fn luaopen_accept_encoding_native(state: *mut lua_State) -> c_int {
+    Lua::init_from_ptr(state).entrypoint1(state, module)
+}
+
+fn module(lua: &Lua) -> Result<Table<'_>> {
+    let exports = lua.create_table()?;
+    exports.set(
+        "match_for_encoding",
+        lua.create_function(|lua, (header_value, encoding): (LuaString, LuaString)| {
+            let Some(m) = match_for_encoding(header_value.as_bytes(), encoding.as_bytes()) else {
+                return Ok(Value::Nil);
+            };
+            let match_type = match m.match_type {
+                EncodingMatchType::Wildcard => "wildcard",
+                EncodingMatchType::Exact => "exact",
+            };
+            match_table(lua, match_type, m.q)
+        })?,
+    )?;
+    exports.set(
+        "match_for_mime_type",
+        lua.create_function(|lua, (header_value, mime_type): (LuaString, LuaString)| {
+            let Some(m) = match_for_mime_type(header_value.as_bytes(), mime_type.as_bytes()) else {
+                return Ok(Value::Nil);
+            };
+            let match_type = match m.match_type {
+                MimeTypeMatchType::MainTypeWildcard => "main_type_wildcard",
+                MimeTypeMatchType::SubTypeWildcard => "subtype_wildcard",
+                MimeTypeMatchType::Exact => "exact",
+            };
+            match_table(lua, match_type, m.q)
+        })?,
+    )?;
+    exports.set(
+        "negotiate_encoding",
+        lua.create_function(
+            |_, (header_value, supported): (LuaString, Vec<LuaString>)| {
+                let targets = supported.iter().map(|s| Target::new(s.as_bytes()));
+                Ok(
+                    negotiate(header_value.as_bytes(), targets, &MatchOptions::default())
+                        .map(|(i, _)| supported[i].clone()),
+                )
+            },
+        )?,
+    )?;
+    Ok(exports)
+}
+
+/// Returns `{ match_type = ..., q = ... }`.
+fn match_table<'lua>(lua: &'lua Lua, match_type: &str, q: QValue) -> Result<Value<'lua>> {
+    let table = lua.create_table()?;
+    table.set("match_type", match_type)?;
+    table.set("q", f64::from(q))?;
+    Ok(Value::Table(table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module() {
+        let lua = Lua::new();
+        lua.globals().set("m", module(&lua).unwrap()).unwrap();
+        let check = |code: &str| {
+            assert!(lua.load(code).eval::<bool>().unwrap(), "{code}");
+        };
+        check(
+            r#"local r = m.match_for_encoding("gzip;q=0.5, *", "gzip")
+            return r.match_type == "exact" and r.q == 0.5"#,
+        );
+        check(r#"return m.match_for_encoding("gzip", "br") == nil"#);
+        check(r#"return m.match_for_encoding("gzip;q=2", "gzip") == nil"#);
+        check(
+            r#"local r = m.match_for_mime_type("text/html, image/*;q=0.8", "image/webp")
+            return r.match_type == "subtype_wildcard" and r.q == 0.8"#,
+        );
+        check(r#"return m.negotiate_encoding("gzip;q=0.5, br", {"zstd", "gzip", "br"}) == "br""#);
+        check(r#"return m.negotiate_encoding("gzip", {"br"}) == nil"#);
+        assert!(lua
+            .load(r#"return m.match_for_encoding(nil, "gzip")"#)
+            .exec()
+            .is_err());
+    }
+}
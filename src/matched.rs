@@ -0,0 +1,116 @@
+use std::cmp::Ordering;
+
+use crate::q_value::QValue;
+
+/// The entry of a header value that applies to a candidate: how it matched,
+/// one of the `*MatchType` enums, and its q-value. Matches rank by match
+/// type, then by q-value. Outside this crate, make one with `new`, as more
+/// fields may be added.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub struct Match<K> {
+    pub match_type: K,
+    pub q: QValue,
+}
+
+impl<K> Match<K> {
+    pub const fn new(match_type: K, q: QValue) -> Self {
+        Self { match_type, q }
+    }
+
+    pub const fn match_type(&self) -> K
+    where
+        K: Copy,
+    {
+        self.match_type
+    }
+
+    pub const fn q(&self) -> QValue {
+        self.q
+    }
+
+    /// Converts the match type, such as to the enum of a binding.
+    pub fn map_type<L>(self, f: impl FnOnce(K) -> L) -> Match<L> {
+        Match {
+            match_type: f(self.match_type),
+            q: self.q,
+        }
+    }
+}
+
+impl<K: Ord> Ord for Match<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.match_type, &self.q).cmp(&(&other.match_type, &other.q))
+    }
+}
+
+impl<K: Ord> PartialOrd for Match<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Picks the candidate to respond with from candidates paired with their
+/// matches, in the server's order of preference, the way
+/// `SupportedEncodings::best_match` does: the highest q-value wins, then
+/// the more specific match, then the earlier candidate. Unmatched
+/// candidates and those with q=0 are left out.
+///
+/// ```
+/// # #[cfg(feature = "encoding")] {
+/// use accept_encoding::{match_for_encoding, pick_best};
+///
+/// let input = b"gzip;q=0.5, *;q=0.8";
+/// let best = pick_best(["gzip", "br"].map(|e| (e, match_for_encoding(input, e.as_bytes()))));
+/// assert_eq!(Some("br"), best);
+/// # }
+/// ```
+pub fn pick_best<C, K: Ord>(
+    candidates: impl IntoIterator<Item = (C, Option<Match<K>>)>,
+) -> Option<C> {
+    let mut best: Option<(C, Match<K>)> = None;
+    for (candidate, m) in candidates {
+        let Some(m) = m.filter(|m| !m.q.is_zero()) else {
+            continue;
+        };
+        if best
+            .as_ref()
+            .is_none_or(|(_, b)| (m.q, &m.match_type) > (b.q, &b.match_type))
+        {
+            best = Some((candidate, m));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_ord() {
+        let m = |match_type: u8, millis| Match::new(match_type, QValue::const_millis(millis));
+        assert!(m(1, 100) > m(0, 1000));
+        assert!(m(1, 500) > m(1, 100));
+        assert_eq!(m(2, 500), m(1, 500).map_type(|t| t * 2));
+        assert_eq!(
+            (1, QValue::const_millis(100)),
+            (m(1, 100).match_type(), m(1, 100).q())
+        );
+    }
+
+    #[test]
+    fn test_pick_best() {
+        let m = |match_type: u8, millis| Some(Match::new(match_type, QValue::const_millis(millis)));
+        assert_eq!(
+            Some("b"),
+            pick_best([("a", m(1, 500)), ("b", m(0, 800)), ("c", None)])
+        );
+        assert_eq!(Some("b"), pick_best([("a", m(0, 800)), ("b", m(1, 800))]));
+        assert_eq!(Some("a"), pick_best([("a", m(1, 800)), ("b", m(1, 800))]));
+        assert_eq!(None, pick_best([("a", m(1, 0)), ("b", None)]));
+        assert_eq!(None, pick_best(Vec::<(&str, Option<Match<u8>>)>::new()));
+    }
+}
@@ -0,0 +1,391 @@
+//! A matcher configured once, for services that need more than the options
+//! of the `*_with_options` functions: coding aliases, a different rule for
+//! repeated entries, and bounds on what a header may cost to parse.
+
+use std::cmp::Ordering;
+
+#[cfg(feature = "language")]
+use crate::language_matcher::{get_language_match_type, parse_accept_language, LanguageMatch};
+#[cfg(feature = "mime")]
+use crate::{
+    accept::Accept,
+    mime_type_matcher::{get_mime_type_match_type, split_mime_type, MimeTypeMatch},
+};
+#[cfg(feature = "encoding")]
+use crate::{
+    accept_encoding::AcceptEncoding,
+    encoding_matcher::{EncodingMatch, EncodingMatchType, Target},
+};
+use crate::{
+    byte_slice::bytes_eq_ignore_case,
+    matched::Match,
+    options::{MatchOptions, RegistryCheck},
+    q_value::QValue,
+};
+
+/// Which entry applies when several match a candidate equally
+/// specifically, like "gzip;q=0.5, gzip".
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieBreak {
+    /// The one with the highest q-value, then the earliest, as the
+    /// `match_for_*` functions pick.
+    #[default]
+    HighestQ,
+    FirstWins,
+    /// The latest, as if each entry overrode the earlier ones.
+    LastWins,
+}
+
+/// Bounds on a header value, past which it is treated as malformed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Limits {
+    /// The longest value in bytes.
+    pub max_len: usize,
+    /// The most list entries.
+    pub max_entries: usize,
+}
+
+impl TieBreak {
+    /// Whether `cur` replaces `best` as the match: a more specific match
+    /// does, and an equally specific one by this rule.
+    pub(crate) fn prefers<K: Ord>(self, cur: &Match<K>, best: &Match<K>) -> bool {
+        match cur.match_type.cmp(&best.match_type) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => match self {
+                TieBreak::HighestQ => cur.q > best.q,
+                TieBreak::FirstWins => false,
+                TieBreak::LastWins => true,
+            },
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_len: usize::MAX,
+            max_entries: usize::MAX,
+        }
+    }
+}
+
+/// Matches header values with the behavior set by `Matcher::builder()`.
+/// The default matcher gives the same results as the `match_for_*`
+/// functions.
+///
+/// ```
+/// # #[cfg(feature = "encoding")] {
+/// use accept_encoding::{Matcher, TieBreak};
+///
+/// let matcher = Matcher::builder()
+///     .lenient(true)
+///     .alias(b"x-br", b"br")
+///     .tie_break(TieBreak::LastWins)
+///     .build();
+/// let m = matcher.match_encoding("x-br;q=0.5, br;q=0.8", "br").unwrap();
+/// assert_eq!(800, m.q.as_millis());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Matcher {
+    options: MatchOptions,
+    aliases: Vec<Alias>,
+    tie_break: TieBreak,
+    limits: Limits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alias {
+    alias: Box<[u8]>,
+    canonical: Box<[u8]>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatcherBuilder {
+    matcher: Matcher,
+}
+
+impl Matcher {
+    pub fn builder() -> MatcherBuilder {
+        MatcherBuilder::default()
+    }
+
+    /// Like `match_for_encoding_with_options`, with the configured
+    /// behavior.
+    #[cfg(feature = "encoding")]
+    pub fn match_encoding(
+        &self,
+        input: impl AsRef<[u8]>,
+        encoding: impl AsRef<[u8]>,
+    ) -> Option<EncodingMatch> {
+        let parsed =
+            AcceptEncoding::parse_with_options(self.check(input.as_ref())?, &self.options)?;
+        self.check_entries(parsed.len())?;
+        let target = Target::new(self.canonical(encoding.as_ref()));
+        self.pick(parsed.iter(), |name| {
+            if target.matches(name) {
+                Some(EncodingMatchType::Exact)
+            } else if name == b"*" {
+                Some(EncodingMatchType::Wildcard)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Like `match_for_mime_type_with_options`, with the configured
+    /// behavior.
+    #[cfg(feature = "mime")]
+    pub fn match_mime_type(
+        &self,
+        input: impl AsRef<[u8]>,
+        mime_type: impl AsRef<[u8]>,
+    ) -> Option<MimeTypeMatch> {
+        let parsed = Accept::parse_with_options(self.check(input.as_ref())?, &self.options)?;
+        self.check_entries(parsed.len())?;
+        let (want_main_type, want_subtype) = split_mime_type(self.canonical(mime_type.as_ref()))?;
+        self.pick(parsed.iter(), |range| {
+            let (main_type, subtype) = split_mime_type(range)?;
+            get_mime_type_match_type(main_type, subtype, want_main_type, want_subtype)
+        })
+    }
+
+    /// Like `match_for_language`, with the configured behavior. The
+    /// Accept-Language parser has no lenient mode or registry, so those
+    /// options do not apply.
+    #[cfg(feature = "language")]
+    pub fn match_language(
+        &self,
+        input: impl AsRef<[u8]>,
+        language: impl AsRef<[u8]>,
+    ) -> Option<LanguageMatch> {
+        let parsed = parse_accept_language(self.check(input.as_ref())?)?;
+        self.check_entries(parsed.len())?;
+        let language = self.canonical(language.as_ref());
+        self.pick(parsed.iter().map(|(range, q)| (*range, q)), |range| {
+            get_language_match_type(range, language)
+        })
+    }
+
+    fn check<'a>(&self, input: &'a [u8]) -> Option<&'a [u8]> {
+        (input.len() <= self.limits.max_len).then_some(input)
+    }
+
+    fn check_entries(&self, n: usize) -> Option<()> {
+        (n <= self.limits.max_entries).then_some(())
+    }
+
+    /// Returns the name `token` is an alias of, or `token` itself.
+    fn canonical<'a>(&'a self, token: &'a [u8]) -> &'a [u8] {
+        self.aliases
+            .iter()
+            .find(|a| bytes_eq_ignore_case(&a.alias, token))
+            .map_or(token, |a| &a.canonical)
+    }
+
+    /// Returns the most specific match among `entries`, breaking ties
+    /// between equally specific ones by `self.tie_break`.
+    fn pick<'t, K: Ord + Copy>(
+        &self,
+        entries: impl Iterator<Item = (&'t [u8], QValue)>,
+        mut match_type: impl FnMut(&[u8]) -> Option<K>,
+    ) -> Option<Match<K>> {
+        let mut best: Option<Match<K>> = None;
+        for (token, q) in entries {
+            let Some(match_type) = match_type(self.canonical(token)) else {
+                continue;
+            };
+            let cur = Match::new(match_type, q);
+            if best.is_none_or(|b| self.tie_break.prefers(&cur, &b)) {
+                best = Some(cur);
+            }
+        }
+        best
+    }
+}
+
+impl MatcherBuilder {
+    /// See `MatchOptions::lenient`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.matcher.options.lenient = lenient;
+        self
+    }
+
+    /// See `MatchOptions::registry_check`.
+    pub fn registry_check(mut self, registry_check: RegistryCheck) -> Self {
+        self.matcher.options.registry_check = registry_check;
+        self
+    }
+
+    pub fn options(mut self, options: MatchOptions) -> Self {
+        self.matcher.options = options;
+        self
+    }
+
+    /// Treats `alias` as `canonical`, ignoring case, in header entries and
+    /// in the candidates matched against them. "x-gzip" and "x-compress"
+    /// are always aliases.
+    pub fn alias(mut self, alias: &[u8], canonical: &[u8]) -> Self {
+        self.matcher.aliases.push(Alias {
+            alias: alias.into(),
+            canonical: canonical.into(),
+        });
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.matcher.tie_break = tie_break;
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.matcher.limits = limits;
+        self
+    }
+
+    pub fn build(self) -> Matcher {
+        self.matcher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_match_encoding() {
+        use crate::encoding_matcher::{match_for_encoding, match_for_encoding_with_options};
+
+        let m = |match_type, millis| Some(Match::new(match_type, QValue::const_millis(millis)));
+        let default = Matcher::default();
+        for (input, encoding) in [
+            (&b"gzip;q=0.5, br, *;q=0.1"[..], &b"gzip"[..]),
+            (b"x-gzip;q=0.5, gzip;q=0.3", b"gzip"),
+            (b"br, *;q=0.1", b"zstd"),
+            (b"br", b"gzip"),
+            (b"br;q=0.5;q=1", b"br"),
+        ] {
+            assert_eq!(
+                match_for_encoding(input, encoding),
+                default.match_encoding(input, encoding)
+            );
+        }
+
+        let lenient = Matcher::builder().lenient(true).build();
+        let options = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            match_for_encoding_with_options(b"br;q=0.5555, \x01", b"br", &options),
+            lenient.match_encoding(b"br;q=0.5555, \x01", b"br")
+        );
+
+        let aliased = Matcher::builder().alias(b"x-br", b"br").build();
+        assert_eq!(
+            m(EncodingMatchType::Exact, 500),
+            aliased.match_encoding(b"X-BR;q=0.5, *;q=0.1", b"br")
+        );
+        assert_eq!(
+            m(EncodingMatchType::Exact, 500),
+            aliased.match_encoding(b"br;q=0.5", b"x-br")
+        );
+        assert_eq!(None, default.match_encoding(b"x-br", b"br"));
+
+        let input = b"gzip;q=0.5, *, gzip, gzip;q=0.8";
+        for (tie_break, millis) in [
+            (TieBreak::HighestQ, 1000),
+            (TieBreak::FirstWins, 500),
+            (TieBreak::LastWins, 800),
+        ] {
+            let matcher = Matcher::builder().tie_break(tie_break).build();
+            assert_eq!(
+                m(EncodingMatchType::Exact, millis),
+                matcher.match_encoding(input, b"gzip")
+            );
+        }
+
+        let limited = Matcher::builder()
+            .limits(Limits {
+                max_len: 10,
+                max_entries: 2,
+            })
+            .build();
+        assert!(limited.match_encoding(b"gzip, br", b"br").is_some());
+        assert_eq!(None, limited.match_encoding(b"gzip, deflate", b"gzip"));
+        assert_eq!(None, limited.match_encoding(b"gzip,br,*", b"br"));
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_match_mime_type() {
+        use crate::mime_type_matcher::{match_for_mime_type, MimeTypeMatchType};
+
+        let default = Matcher::default();
+        for (input, mime_type) in [
+            (
+                &b"text/*;q=0.5, text/html, */*;q=0.1"[..],
+                &b"text/html"[..],
+            ),
+            (b"text/*;q=0.5, */*;q=0.1", b"text/plain"),
+            (b"image/*", b"text/plain"),
+            (b"text/html", b"text"),
+        ] {
+            assert_eq!(
+                match_for_mime_type(input, mime_type),
+                default.match_mime_type(input, mime_type)
+            );
+        }
+
+        let matcher = Matcher::builder()
+            .alias(b"text/xml", b"application/xml")
+            .tie_break(TieBreak::LastWins)
+            .build();
+        assert_eq!(
+            Some(Match::new(
+                MimeTypeMatchType::Exact,
+                QValue::const_millis(300)
+            )),
+            matcher.match_mime_type(b"application/xml, text/xml;q=0.3", b"application/xml")
+        );
+    }
+
+    #[cfg(feature = "language")]
+    #[test]
+    fn test_match_language() {
+        use crate::language_matcher::{match_for_language, LanguageMatchType};
+
+        let default = Matcher::default();
+        for (input, language) in [
+            (&b"en-US, en;q=0.8, *;q=0.1"[..], &b"en-GB"[..]),
+            (b"ja, en;q=0.8", b"en-US"),
+            (b"de", b"fr"),
+        ] {
+            assert_eq!(
+                match_for_language(input, language),
+                default.match_language(input, language)
+            );
+        }
+
+        let matcher = Matcher::builder()
+            .alias(b"iw", b"he")
+            .limits(Limits {
+                max_entries: 2,
+                ..Default::default()
+            })
+            .build();
+        assert_eq!(
+            Some(Match::new(
+                LanguageMatchType::Exact,
+                QValue::const_millis(500)
+            )),
+            matcher.match_language(b"iw;q=0.5", b"he")
+        );
+        assert_eq!(None, matcher.match_language(b"he, en, de", b"he"));
+    }
+}
@@ -1,151 +1,375 @@
-use std::{cmp::Ordering, str};
+use std::{error::Error, fmt};
 
+#[cfg(feature = "tracing")]
+use crate::trace;
 use crate::{
     byte_slice::bytes_eq_ignore_case,
-    lexer::{self, Cursor},
+    explain::{finish_entry, Explanation, Observer, Recorder},
+    lexer::{self, Cursor, ParseError},
+    matched::Match,
+    options::{self, check_unregistered, MatchOptions, RegistryCheck},
     q_value::QValue,
+    registry::is_registered_top_level_media_type,
+    warning::{Warning, WarningForwarder, WarningReason},
 };
 
 pub fn match_for_mime_type(input: &[u8], mime_type: &[u8]) -> Option<MimeTypeMatch> {
-    let (want_main_type, want_subtype) = match split_mime_type(mime_type) {
-        Some((main_type, subtype)) => (main_type, subtype),
-        None => return None,
-    };
+    match_for_mime_type_with_options(input, mime_type, &MatchOptions::default())
+}
+
+/// Like `match_for_mime_type`, for a header value and media type held as
+/// `&str`.
+pub fn match_for_mime_type_str(input: &str, mime_type: &str) -> Option<MimeTypeMatch> {
+    match_for_mime_type(input.as_bytes(), mime_type.as_bytes())
+}
+
+pub fn match_for_mime_type_with_options(
+    input: &[u8],
+    mime_type: &[u8],
+    options: &MatchOptions,
+) -> Option<MimeTypeMatch> {
+    match_mime_type_traced(input, mime_type, options, &mut ())
+}
 
+/// Like `match_for_mime_type_with_options`, but passes what lenient
+/// matching tolerated to `on_warning`.
+pub fn match_for_mime_type_with_warnings<F: FnMut(Warning)>(
+    input: &[u8],
+    mime_type: &[u8],
+    options: &MatchOptions,
+    on_warning: F,
+) -> Option<MimeTypeMatch> {
+    match_mime_type_traced(input, mime_type, options, &mut WarningForwarder(on_warning))
+}
+
+fn match_mime_type_traced<O: Observer<MimeTypeMatch>>(
+    input: &[u8],
+    mime_type: &[u8],
+    options: &MatchOptions,
+    observer: &mut O,
+) -> Option<MimeTypeMatch> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!(
+        "match_for_mime_type",
+        mime_type = %String::from_utf8_lossy(mime_type)
+    )
+    .entered();
+
+    let want = split_mime_type(mime_type)?;
     let mut c = Cursor(0);
-    let mut state = State::SearchingMainType;
-    let mut cur_result: Option<MimeTypeMatch> = None;
-    let mut best_result: Option<MimeTypeMatch> = None;
+    let result = match_mime_type(input, Some(want), options, &mut c, observer);
+    #[cfg(feature = "tracing")]
+    trace::outcome(&result, c);
+    result.ok().flatten()
+}
 
-    let mut cur_main_type = None;
-    let mut is_q_param = false;
+pub fn explain_mime_type<'a>(
+    input: &'a [u8],
+    mime_type: &[u8],
+    options: &MatchOptions,
+) -> Explanation<'a, MimeTypeMatch> {
+    let mut recorder = Recorder::new(input);
+    let Some(want) = split_mime_type(mime_type) else {
+        return recorder.finish(Ok(None));
+    };
+    let mut c = Cursor(0);
+    let result = match_mime_type(input, Some(want), options, &mut c, &mut recorder);
+    recorder.finish(result.map_err(|_| c.0))
+}
+
+/// Like `match_for_mime_type`, but tells an invalid `mime_type` argument
+/// apart from a header value that does not accept it.
+pub fn try_match_for_mime_type(
+    input: &[u8],
+    mime_type: &[u8],
+) -> Result<Option<MimeTypeMatch>, InvalidMimeTypeError> {
+    let mime_type = MimeTypeRef::new(mime_type)?;
+    Ok(match_for_mime_type_ref(
+        input,
+        mime_type,
+        &MatchOptions::default(),
+    ))
+}
+
+pub fn match_for_mime_type_ref(
+    input: &[u8],
+    mime_type: MimeTypeRef<'_>,
+    options: &MatchOptions,
+) -> Option<MimeTypeMatch> {
+    match_for_mime_type_with_options(input, mime_type.as_bytes(), options)
+}
+
+/// Matches the media type `want` splits into. None matches every range
+/// exactly, for collecting the entries of a value.
+pub(crate) fn match_mime_type<O: Observer<MimeTypeMatch>>(
+    input: &[u8],
+    want: Option<(&[u8], &[u8])>,
+    options: &MatchOptions,
+    c: &mut Cursor,
+    observer: &mut O,
+) -> Result<Option<MimeTypeMatch>, ParseError> {
+    let mut parser = Parser::new(want, *c);
     while !c.eof(input) {
-        match state {
+        if let Err(e) = parser.step(input, options, c, observer) {
+            if !options.lenient {
+                return Err(e);
+            }
+            observer.warning(Warning {
+                offset: c.0,
+                reason: WarningReason::SkippedMalformedEntry,
+            });
+            #[cfg(feature = "tracing")]
+            trace::skipped_entry(*c);
+            parser.skip_entry(input, c);
+        }
+        if options.lenient && !O::NEEDS_EVERY_ENTRY && parser.has_unbeatable_match() {
+            break;
+        }
+    }
+    Ok(parser.finish(observer))
+}
+
+struct Parser<'a, 'w> {
+    want: Option<(&'w [u8], &'w [u8])>,
+    state: State,
+    cur_main_type: &'a [u8],
+    cur_result: Option<MimeTypeMatch>,
+    best_result: Option<MimeTypeMatch>,
+    is_q_param: bool,
+    entry_start: Cursor,
+    entry_end: Cursor,
+}
+
+impl<'a, 'w> Parser<'a, 'w> {
+    fn new(want: Option<(&'w [u8], &'w [u8])>, c: Cursor) -> Self {
+        Self {
+            want,
+            state: State::SearchingMainType,
+            cur_main_type: b"",
+            cur_result: None,
+            best_result: None,
+            is_q_param: false,
+            entry_start: c,
+            entry_end: c,
+        }
+    }
+
+    fn step<O: Observer<MimeTypeMatch>>(
+        &mut self,
+        input: &'a [u8],
+        options: &MatchOptions,
+        c: &mut Cursor,
+        observer: &mut O,
+    ) -> Result<(), ParseError> {
+        match self.state {
             State::SearchingMainType => {
-                let c1 = c;
-                lexer::token(input, &mut c).ok()?;
-                let token = c1.slice(input, c);
-                cur_main_type = Some(token);
-                state = State::SeenMainType;
+                let c1 = *c;
+                self.entry_start = c1;
+                self.cur_result = None;
+                lexer::token(input, c)?;
+                self.entry_end = *c;
+                self.cur_main_type = c1.slice(input, *c);
+                if options.registry_check != RegistryCheck::Off
+                    && self.cur_main_type != b"*"
+                    && !is_registered_top_level_media_type(self.cur_main_type)
+                {
+                    check_unregistered(options, c1, observer)?;
+                }
+                self.state = State::SeenMainType;
             }
             State::SeenMainType => {
-                lexer::byte(b'/')(input, &mut c).ok()?;
-                state = State::SeenSlash;
+                lexer::byte(b'/')(input, c)?;
+                self.state = State::SeenSlash;
             }
             State::SeenSlash => {
-                let c1 = c;
-                lexer::token(input, &mut c).ok()?;
-                let subtype = c1.slice(input, c);
-                let main_type = cur_main_type.unwrap();
-                if let Some(match_type) =
-                    get_mime_type_match_type(main_type, subtype, want_main_type, want_subtype)
-                {
-                    cur_result = Some(MimeTypeMatch {
-                        match_type,
-                        q: QValue::from_millis(1000).unwrap(),
-                    })
+                let c1 = *c;
+                lexer::token(input, c)?;
+                self.entry_end = *c;
+                let subtype = c1.slice(input, *c);
+                self.cur_result = match self.want {
+                    Some((want_main_type, want_subtype)) => get_mime_type_match_type(
+                        self.cur_main_type,
+                        subtype,
+                        want_main_type,
+                        want_subtype,
+                    ),
+                    None => Some(MimeTypeMatchType::Exact),
                 }
-                state = State::SeenSubType;
+                .map(|match_type| MimeTypeMatch {
+                    match_type,
+                    q: QValue::ONE,
+                });
+                self.state = State::SeenSubType;
             }
             State::SeenSubType => {
-                if !c.eof(input) {
-                    lexer::ows(input, &mut c);
-                    if c.eof(input) {
-                        return None;
-                    } else if lexer::byte(b';')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        state = State::SeenSemicolon;
-                    } else if lexer::byte(b',')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        may_update_best_result(&mut cur_result, &mut best_result);
-                        state = State::SearchingMainType;
-                    } else {
-                        return None;
-                    }
-                }
+                self.after_element_or_parameter(input, options, c, observer)?;
             }
             State::SeenSemicolon => {
-                let c1 = c;
-                lexer::token(input, &mut c).ok()?;
-                let param_name = c1.slice(input, c);
-                is_q_param = bytes_eq_ignore_case(param_name, b"q");
-                state = State::SeenParameterName;
+                let c1 = *c;
+                lexer::token(input, c)?;
+                let param_name = c1.slice(input, *c);
+                self.is_q_param = bytes_eq_ignore_case(param_name, b"q");
+                self.state = State::SeenParameterName;
             }
             State::SeenParameterName => {
-                lexer::byte(b'=')(input, &mut c).ok()?;
-                state = State::SeenEqual;
+                lexer::byte(b'=')(input, c)?;
+                self.state = State::SeenEqual;
             }
             State::SeenEqual => {
-                if is_q_param {
-                    let c1 = c;
-                    lexer::q_value(input, &mut c).ok()?;
-                    if let Some(cur_result) = cur_result.as_mut() {
-                        cur_result.q =
-                            QValue::try_from(str::from_utf8(c1.slice(input, c)).unwrap()).unwrap();
+                if self.is_q_param {
+                    let q = options::q_value(input, options, c, observer)?;
+                    if let Some(cur_result) = self.cur_result.as_mut() {
+                        cur_result.q = q;
                     }
                 } else {
-                    lexer::alt(lexer::token, lexer::quoted_string)(input, &mut c).ok()?;
+                    lexer::alt(lexer::token, lexer::quoted_string)(input, c)?;
                 }
-                state = State::SeenParameterValue;
+                self.entry_end = *c;
+                self.state = State::SeenParameterValue;
             }
             State::SeenParameterValue => {
-                if !c.eof(input) {
-                    lexer::ows(input, &mut c);
-                    if c.eof(input) {
-                        return None;
-                    } else if lexer::byte(b',')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        may_update_best_result(&mut cur_result, &mut best_result);
-                        state = State::SearchingMainType;
-                    } else if lexer::byte(b';')(input, &mut c).is_ok() {
-                        lexer::ows(input, &mut c);
-                        state = State::SeenSemicolon;
-                    } else {
-                        return None;
-                    }
-                }
+                self.after_element_or_parameter(input, options, c, observer)?;
             }
         }
+        Ok(())
+    }
+
+    fn after_element_or_parameter<O: Observer<MimeTypeMatch>>(
+        &mut self,
+        input: &[u8],
+        options: &MatchOptions,
+        c: &mut Cursor,
+        observer: &mut O,
+    ) -> Result<(), ParseError> {
+        let c1 = *c;
+        lexer::ows(input, c);
+        if c.eof(input) {
+            if !options.lenient {
+                return Err(ParseError);
+            }
+            observer.warning(Warning {
+                offset: c1.0,
+                reason: WarningReason::TrailingWhitespace,
+            });
+        } else if lexer::byte(b';')(input, c).is_ok() {
+            lexer::ows(input, c);
+            self.state = State::SeenSemicolon;
+        } else if lexer::byte(b',')(input, c).is_ok() {
+            lexer::ows(input, c);
+            self.finish_entry(observer);
+            self.state = State::SearchingMainType;
+        } else {
+            return Err(ParseError);
+        }
+        Ok(())
+    }
+
+    fn finish_entry<O: Observer<MimeTypeMatch>>(&mut self, observer: &mut O) {
+        finish_entry(
+            &mut self.cur_result,
+            &mut self.best_result,
+            self.entry_start.0,
+            self.entry_end.0,
+            observer,
+        );
+    }
+
+    fn skip_entry(&mut self, input: &[u8], c: &mut Cursor) {
+        *c = self.entry_start;
+        lexer::skip_list_element(input, c);
+        self.cur_result = None;
+        self.state = State::SearchingMainType;
+    }
+
+    /// Later entries can at best tie an Exact match with q=1, and a tie
+    /// keeps the earlier entry.
+    fn has_unbeatable_match(&self) -> bool {
+        self.best_result
+            == Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::Exact,
+                q: QValue::ONE,
+            })
     }
-    may_update_best_result(&mut cur_result, &mut best_result);
-    best_result.take()
-}
 
-fn may_update_best_result(
-    cur_result: &mut Option<MimeTypeMatch>,
-    best_result: &mut Option<MimeTypeMatch>,
-) {
-    if cur_result.gt(&best_result) {
-        *best_result = cur_result.take();
+    fn finish<O: Observer<MimeTypeMatch>>(mut self, observer: &mut O) -> Option<MimeTypeMatch> {
+        if !matches!(self.state, State::SearchingMainType) {
+            self.finish_entry(observer);
+        }
+        self.best_result
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
 pub enum MimeTypeMatchType {
     MainTypeWildcard,
     SubTypeWildcard,
     Exact,
 }
 
+pub type MimeTypeMatch = Match<MimeTypeMatchType>;
+
+/// A `type/subtype` pair checked to consist of two valid tokens, so that
+/// it can match an entry in a header value.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct MimeTypeRef<'a> {
+    mime_type: &'a [u8],
+    main_type: &'a [u8],
+    subtype: &'a [u8],
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct MimeTypeMatch {
-    pub match_type: MimeTypeMatchType,
-    pub q: QValue,
+pub enum InvalidMimeTypeError {
+    MissingSlash,
+    InvalidMainType,
+    InvalidSubtype,
 }
 
-impl Ord for MimeTypeMatch {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (self.match_type, &self.q).cmp(&(other.match_type, &other.q))
+impl fmt::Display for InvalidMimeTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InvalidMimeTypeError::MissingSlash => "media type has no slash",
+            InvalidMimeTypeError::InvalidMainType => "media type has an invalid top-level type",
+            InvalidMimeTypeError::InvalidSubtype => "media type has an invalid subtype",
+        })
     }
 }
 
-impl PartialOrd for MimeTypeMatch {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Error for InvalidMimeTypeError {}
+
+impl<'a> MimeTypeRef<'a> {
+    pub fn new(mime_type: &'a [u8]) -> Result<Self, InvalidMimeTypeError> {
+        let (main_type, subtype) =
+            split_mime_type(mime_type).ok_or(InvalidMimeTypeError::MissingSlash)?;
+        if !lexer::is_token(main_type) {
+            Err(InvalidMimeTypeError::InvalidMainType)
+        } else if !lexer::is_token(subtype) {
+            Err(InvalidMimeTypeError::InvalidSubtype)
+        } else {
+            Ok(Self {
+                mime_type,
+                main_type,
+                subtype,
+            })
+        }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.mime_type
+    }
+
+    pub fn main_type(&self) -> &'a [u8] {
+        self.main_type
+    }
+
+    pub fn subtype(&self) -> &'a [u8] {
+        self.subtype
     }
 }
 
-fn split_mime_type(mime_type: &[u8]) -> Option<(&[u8], &[u8])> {
+pub(crate) fn split_mime_type(mime_type: &[u8]) -> Option<(&[u8], &[u8])> {
     let mut s = mime_type.splitn(2, |c| *c == b'/');
     match s.next() {
         Some(main_type) => s.next().map(|subtype| (main_type, subtype)),
@@ -153,7 +377,7 @@ fn split_mime_type(mime_type: &[u8]) -> Option<(&[u8], &[u8])> {
     }
 }
 
-fn get_mime_type_match_type(
+pub(crate) fn get_mime_type_match_type(
     main_type: &[u8],
     subtype: &[u8],
     want_main_type: &[u8],
@@ -192,7 +416,10 @@ enum State {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use super::*;
+    use crate::{explain::EntryVerdict, options::RegistryCheck, test_util::for_each_input};
 
     #[test]
     fn test_split_mime_type() {
@@ -217,6 +444,10 @@ mod tests {
             }),
             match_for_mime_type(b"*/*", b"image/webp"),
         );
+        assert_eq!(
+            match_for_mime_type(b"*/*", b"image/webp"),
+            match_for_mime_type_str("*/*", "image/webp")
+        );
 
         assert_eq!(
             Some(MimeTypeMatch {
@@ -338,6 +569,15 @@ mod tests {
         );
         assert!(firefox_webp_match.gt(&firefox_png_match));
 
+        // parameter names are case-insensitive
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::SubTypeWildcard,
+                q: QValue::try_from(0.5).unwrap(),
+            }),
+            match_for_mime_type(b"image/*;Q=0.5", b"image/webp"),
+        );
+
         // bad mime_type (no slash)
         assert_eq!(None, match_for_mime_type(b"*/*", b"webp"));
         // bad main_type
@@ -360,6 +600,233 @@ mod tests {
         assert_eq!(None, match_for_mime_type(b"image/*;p=a/", b"image/webp"));
     }
 
+    #[test]
+    fn test_match_for_mime_type_with_options_lenient() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::MainTypeWildcard,
+                q: QValue::try_from(0.8).unwrap(),
+            }),
+            match_for_mime_type_with_options(b"*/*;q=0.80000", b"image/webp", &lenient)
+        );
+        assert_eq!(None, match_for_mime_type(b"*/*;q=0.80000", b"image/webp"));
+    }
+
+    #[test]
+    fn test_match_for_mime_type_non_matching_entry_keeps_best() {
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::MainTypeWildcard,
+                q: QValue::try_from(0.5).unwrap(),
+            }),
+            match_for_mime_type(b"*/*;q=0.5, */*;q=0.4, text/html;q=1", b"image/png"),
+        );
+    }
+
+    #[test]
+    fn test_match_for_mime_type_with_warnings() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::SubTypeWildcard,
+                q: QValue::try_from(0.8).unwrap(),
+            }),
+            match_for_mime_type_with_warnings(
+                b"image;q=0.9, image/*;q=0.8",
+                b"image/webp",
+                &lenient,
+                |w| warnings.push(w)
+            )
+        );
+        assert_eq!(
+            vec![Warning {
+                offset: 5,
+                reason: WarningReason::SkippedMalformedEntry,
+            }],
+            warnings
+        );
+
+        let explanation = explain_mime_type(b"image/webp;q=1 ", b"image/webp", &lenient);
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::Exact,
+                q: QValue::try_from(1.0).unwrap(),
+            }),
+            explanation.result
+        );
+        assert_eq!(
+            vec![Warning {
+                offset: 14,
+                reason: WarningReason::TrailingWhitespace,
+            }],
+            explanation.warnings
+        );
+    }
+
+    #[test]
+    fn test_match_for_mime_type_registry_check() {
+        let header_value = b"img/webp, */*;q=0.1";
+        let reject = MatchOptions {
+            registry_check: RegistryCheck::Reject,
+            ..Default::default()
+        };
+        assert_eq!(
+            None,
+            match_for_mime_type_with_options(header_value, b"image/webp", &reject)
+        );
+
+        let explanation = explain_mime_type(
+            header_value,
+            b"image/webp",
+            &MatchOptions {
+                registry_check: RegistryCheck::Warn,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::MainTypeWildcard,
+                q: QValue::try_from(0.1).unwrap(),
+            }),
+            explanation.result
+        );
+        assert_eq!(
+            vec![Warning {
+                offset: 0,
+                reason: WarningReason::UnregisteredToken,
+            }],
+            explanation.warnings
+        );
+    }
+
+    #[test]
+    fn test_explain_mime_type() {
+        let explanation = explain_mime_type(
+            b"text/html, image/*;q=0.8, */*;q=0.5",
+            b"image/webp",
+            &MatchOptions::default(),
+        );
+        assert_eq!(
+            vec![
+                (b"text/html".as_slice(), EntryVerdict::NotMatched),
+                (b"image/*;q=0.8".as_slice(), EntryVerdict::Best),
+                (b"*/*;q=0.5".as_slice(), EntryVerdict::Lower),
+            ],
+            explanation
+                .entries
+                .iter()
+                .map(|e| (e.text, e.verdict))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::SubTypeWildcard,
+                q: QValue::try_from(0.8).unwrap(),
+            }),
+            explanation.result
+        );
+        assert_eq!(Some(1), explanation.winner);
+
+        let explanation = explain_mime_type(b"*/*", b"webp", &MatchOptions::default());
+        assert!(explanation.entries.is_empty());
+        assert_eq!(None, explanation.result);
+        assert_eq!(None, explanation.error_offset);
+
+        let explanation = explain_mime_type(b"image/*;/", b"image/webp", &MatchOptions::default());
+        assert_eq!(None, explanation.result);
+        assert_eq!(Some(8), explanation.error_offset);
+
+        // An entry cut short after its main type.
+        let explanation =
+            explain_mime_type(b"text/html, z", b"image/webp", &MatchOptions::default());
+        assert_eq!(b"z", explanation.entries[1].text);
+    }
+
+    #[test]
+    fn test_mime_type_ref() {
+        let mime_type = MimeTypeRef::new(b"image/webp").unwrap();
+        assert_eq!(b"image/webp", mime_type.as_bytes());
+        assert_eq!(b"image", mime_type.main_type());
+        assert_eq!(b"webp", mime_type.subtype());
+
+        assert_eq!(
+            Err(InvalidMimeTypeError::MissingSlash),
+            MimeTypeRef::new(b"imagewebp")
+        );
+        assert_eq!(
+            Err(InvalidMimeTypeError::InvalidSubtype),
+            MimeTypeRef::new(b"image/")
+        );
+        assert_eq!(
+            Err(InvalidMimeTypeError::InvalidMainType),
+            MimeTypeRef::new(b"/webp")
+        );
+        assert_eq!(
+            Err(InvalidMimeTypeError::InvalidSubtype),
+            MimeTypeRef::new(b"image/we/bp")
+        );
+        assert_eq!(
+            "media type has no slash",
+            InvalidMimeTypeError::MissingSlash.to_string()
+        );
+        let err: Box<dyn Error> = Box::new(InvalidMimeTypeError::InvalidSubtype);
+        assert_eq!("media type has an invalid subtype", err.to_string());
+
+        assert_eq!(
+            Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::Exact,
+                q: QValue::try_from(1.0).unwrap(),
+            }),
+            match_for_mime_type_ref(b"image/webp", mime_type, &MatchOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_try_match_for_mime_type() {
+        assert_eq!(
+            Ok(Some(MimeTypeMatch {
+                match_type: MimeTypeMatchType::SubTypeWildcard,
+                q: QValue::try_from(1.0).unwrap(),
+            })),
+            try_match_for_mime_type(b"image/*", b"image/webp")
+        );
+        assert_eq!(
+            Ok(None),
+            try_match_for_mime_type(b"text/html", b"image/webp")
+        );
+        assert_eq!(
+            Err(InvalidMimeTypeError::MissingSlash),
+            try_match_for_mime_type(b"*/*", b"webp")
+        );
+    }
+
+    #[test]
+    fn test_match_for_mime_type_never_panics() {
+        let lenient = MatchOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        for_each_input(b"a*/;,=q01. \t\"\\\xff", 0..=4, |input| {
+            match_for_mime_type(input, b"a/a");
+            match_for_mime_type(input, b"a/*");
+            // Stopping early at an Exact q=1 match must not change the result.
+            assert_eq!(
+                match_for_mime_type_with_warnings(input, b"a/a", &lenient, |_| {}),
+                match_for_mime_type_with_options(input, b"a/a", &lenient),
+                "input={:?}",
+                String::from_utf8_lossy(input)
+            );
+        });
+    }
+
     #[test]
     #[allow(clippy::clone_on_copy)]
     fn test_mime_type_match_type_derive() {
@@ -374,7 +841,7 @@ mod tests {
     #[allow(clippy::clone_on_copy)]
     fn test_mime_type_match_derive() {
         assert_eq!(
-            "MimeTypeMatch { match_type: SubTypeWildcard, q: QValue { millis: 1000 } }".to_string(),
+            "Match { match_type: SubTypeWildcard, q: QValue { millis: 1000 } }".to_string(),
             format!(
                 "{:?}",
                 MimeTypeMatch {
@@ -394,6 +861,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mime_type_match_hash() {
+        let mut seen = HashSet::new();
+        assert!(seen.insert(match_for_mime_type(b"image/*", b"image/webp")));
+        assert!(seen.insert(match_for_mime_type(b"*/*", b"image/webp")));
+        assert!(!seen.insert(match_for_mime_type(b"text/html, image/*", b"image/webp")));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_mime_type_match_serde() {
+        let m = MimeTypeMatch {
+            match_type: MimeTypeMatchType::SubTypeWildcard,
+            q: QValue::try_from(0.8).unwrap(),
+        };
+        let json = r#"{"match_type":"SubTypeWildcard","q":0.8}"#;
+        assert_eq!(json, serde_json::to_string(&m).unwrap());
+        assert_eq!(m, serde_json::from_str(json).unwrap());
+    }
+
     #[test]
     fn test_state_derive() {
         assert_eq!(
@@ -0,0 +1,252 @@
+use crate::negotiator::ContentNegotiator;
+#[cfg(feature = "charset")]
+use crate::{charset_matcher::CharsetMatch, negotiator::CharsetNegotiator};
+#[cfg(feature = "encoding")]
+use crate::{
+    encoding_matcher::EncodingMatch, negotiator::EncodingNegotiator,
+    supported_encodings::SupportedEncodings,
+};
+#[cfg(feature = "language")]
+use crate::{language_matcher::LanguageMatch, negotiator::LanguageNegotiator};
+#[cfg(feature = "mime")]
+use crate::{mime_type_matcher::MimeTypeMatch, negotiator::MimeTypeNegotiator};
+
+/// The Accept, Accept-Encoding, Accept-Language and Accept-Charset values
+/// of one request, each parsed once, for a handler that matches or
+/// negotiates several times.
+///
+/// Each method gives the same result as the function for that header on
+/// the raw value: a missing header reads as empty and matches nothing,
+/// and a malformed one matches nothing either.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NegotiationContext {
+    #[cfg(feature = "mime")]
+    accept: Option<<MimeTypeNegotiator as ContentNegotiator>::Parsed>,
+    #[cfg(feature = "encoding")]
+    accept_encoding: Option<<EncodingNegotiator as ContentNegotiator>::Parsed>,
+    #[cfg(feature = "language")]
+    accept_language: Option<<LanguageNegotiator as ContentNegotiator>::Parsed>,
+    #[cfg(feature = "charset")]
+    accept_charset: Option<<CharsetNegotiator as ContentNegotiator>::Parsed>,
+}
+
+impl NegotiationContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "mime")]
+    pub fn with_accept(mut self, value: &[u8]) -> Self {
+        self.accept = MimeTypeNegotiator.parse(value);
+        self
+    }
+
+    #[cfg(feature = "encoding")]
+    pub fn with_accept_encoding(mut self, value: &[u8]) -> Self {
+        self.accept_encoding = EncodingNegotiator.parse(value);
+        self
+    }
+
+    #[cfg(feature = "language")]
+    pub fn with_accept_language(mut self, value: &[u8]) -> Self {
+        self.accept_language = LanguageNegotiator.parse(value);
+        self
+    }
+
+    #[cfg(feature = "charset")]
+    pub fn with_accept_charset(mut self, value: &[u8]) -> Self {
+        self.accept_charset = CharsetNegotiator.parse(value);
+        self
+    }
+
+    /// Reads every header the enabled matchers cover from `headers`.
+    #[cfg(feature = "http")]
+    pub fn from_headers(headers: &impl crate::HeaderSource) -> Self {
+        #[allow(unused_mut)]
+        let mut context = Self::new();
+        #[cfg(feature = "mime")]
+        {
+            context = context.with_accept(&headers.header_value(&http::header::ACCEPT));
+        }
+        #[cfg(feature = "encoding")]
+        {
+            context =
+                context.with_accept_encoding(&headers.header_value(&http::header::ACCEPT_ENCODING));
+        }
+        #[cfg(feature = "language")]
+        {
+            context =
+                context.with_accept_language(&headers.header_value(&http::header::ACCEPT_LANGUAGE));
+        }
+        #[cfg(feature = "charset")]
+        {
+            context =
+                context.with_accept_charset(&headers.header_value(&http::header::ACCEPT_CHARSET));
+        }
+        context
+    }
+
+    /// Like `match_for_mime_type`.
+    #[cfg(feature = "mime")]
+    pub fn match_mime_type(&self, mime_type: &[u8]) -> Option<MimeTypeMatch> {
+        MimeTypeNegotiator.match_parsed(self.accept.as_ref()?, mime_type)
+    }
+
+    /// Picks the media type to respond with from `mime_types`, in the
+    /// server's order of preference: the highest q-value wins, then the one
+    /// matched most specifically, then the earlier one.
+    #[cfg(feature = "mime")]
+    pub fn negotiate_mime_type<'a>(&self, mime_types: &[&'a [u8]]) -> Option<&'a [u8]> {
+        MimeTypeNegotiator.negotiate_parsed(self.accept.as_ref()?, mime_types)
+    }
+
+    /// Like `match_for_encoding`.
+    #[cfg(feature = "encoding")]
+    pub fn match_encoding(&self, encoding: &[u8]) -> Option<EncodingMatch> {
+        EncodingNegotiator.match_parsed(self.accept_encoding.as_ref()?, encoding)
+    }
+
+    /// Like `SupportedEncodings::best_match`.
+    #[cfg(feature = "encoding")]
+    pub fn negotiate_encoding<'s>(&self, supported: &'s SupportedEncodings) -> Option<&'s [u8]> {
+        let encodings: Vec<_> = supported.iter().collect();
+        EncodingNegotiator.negotiate_parsed(self.accept_encoding.as_ref()?, &encodings)
+    }
+
+    /// Like `match_for_language`.
+    #[cfg(feature = "language")]
+    pub fn match_language(&self, language: &[u8]) -> Option<LanguageMatch> {
+        LanguageNegotiator.match_parsed(self.accept_language.as_ref()?, language)
+    }
+
+    /// Picks the language to respond in from `languages`, by the rules of
+    /// `negotiate_mime_type`.
+    #[cfg(feature = "language")]
+    pub fn negotiate_language<'a>(&self, languages: &[&'a [u8]]) -> Option<&'a [u8]> {
+        LanguageNegotiator.negotiate_parsed(self.accept_language.as_ref()?, languages)
+    }
+
+    /// Like `match_for_charset`.
+    #[cfg(feature = "charset")]
+    pub fn match_charset(&self, charset: &[u8]) -> Option<CharsetMatch> {
+        CharsetNegotiator.match_parsed(self.accept_charset.as_ref()?, charset)
+    }
+
+    /// Picks the charset to respond in from `charsets`, by the rules of
+    /// `negotiate_mime_type`.
+    #[cfg(feature = "charset")]
+    pub fn negotiate_charset<'a>(&self, charsets: &[&'a [u8]]) -> Option<&'a [u8]> {
+        CharsetNegotiator.negotiate_parsed(self.accept_charset.as_ref()?, charsets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_negotiation_context_mime_type() {
+        use crate::mime_type_matcher::match_for_mime_type;
+
+        let accept = b"text/html;q=0.9, application/json, image/*;q=0.5";
+        let context = NegotiationContext::new().with_accept(accept);
+        for mime_type in [&b"text/html"[..], b"image/png", b"video/mp4"] {
+            assert_eq!(
+                match_for_mime_type(accept, mime_type),
+                context.match_mime_type(mime_type)
+            );
+        }
+        assert_eq!(
+            Some(&b"application/json"[..]),
+            context.negotiate_mime_type(&[b"text/html", b"application/json"])
+        );
+        assert_eq!(None, context.negotiate_mime_type(&[b"video/mp4"]));
+        assert_eq!(
+            None,
+            NegotiationContext::new().match_mime_type(b"text/html")
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_negotiation_context_encoding() {
+        use crate::encoding_matcher::match_for_encoding;
+
+        let accept_encoding = b"gzip;q=0.5, x-gzip;q=0.3, br, *;q=0.1";
+        let context = NegotiationContext::new().with_accept_encoding(accept_encoding);
+        for encoding in [&b"gzip"[..], b"br", b"zstd", b"identity"] {
+            assert_eq!(
+                match_for_encoding(accept_encoding, encoding),
+                context.match_encoding(encoding)
+            );
+        }
+        for supported in [
+            &["zstd", "gzip", "br"][..],
+            &["zstd", "gzip"],
+            &["compress"],
+        ] {
+            let supported = SupportedEncodings::new(supported).unwrap();
+            assert_eq!(
+                supported.best_match(accept_encoding).map(|(e, _)| e),
+                context.negotiate_encoding(&supported)
+            );
+        }
+
+        let malformed = NegotiationContext::new().with_accept_encoding(b"gzip;q=2");
+        assert_eq!(None, malformed.match_encoding(b"gzip"));
+    }
+
+    #[cfg(feature = "language")]
+    #[test]
+    fn test_negotiation_context_language() {
+        use crate::language_matcher::match_for_language;
+
+        let accept_language = b"en-US, en;q=0.8, *;q=0.1";
+        let context = NegotiationContext::new().with_accept_language(accept_language);
+        for language in [&b"en-US"[..], b"en-GB", b"fr"] {
+            assert_eq!(
+                match_for_language(accept_language, language),
+                context.match_language(language)
+            );
+        }
+        assert_eq!(
+            Some(&b"en"[..]),
+            context.negotiate_language(&[b"fr", b"en-GB", b"en"])
+        );
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn test_negotiation_context_charset() {
+        use crate::charset_matcher::match_for_charset;
+
+        let accept_charset = b"utf-8, iso-8859-1;q=0.5";
+        let context = NegotiationContext::new().with_accept_charset(accept_charset);
+        assert_eq!(
+            match_for_charset(accept_charset, b"ISO-8859-1"),
+            context.match_charset(b"ISO-8859-1")
+        );
+        assert_eq!(
+            Some(&b"iso-8859-1"[..]),
+            context.negotiate_charset(&[b"shift_jis", b"iso-8859-1"])
+        );
+    }
+
+    #[cfg(all(feature = "http", feature = "encoding", feature = "mime"))]
+    #[test]
+    fn test_negotiation_context_from_headers() {
+        use http::{
+            header::{ACCEPT, ACCEPT_ENCODING},
+            HeaderMap, HeaderValue,
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("gzip;q=0.5"));
+        headers.append(ACCEPT_ENCODING, HeaderValue::from_static("br"));
+        headers.append(ACCEPT, HeaderValue::from_static("text/plain"));
+        let context = NegotiationContext::from_headers(&headers);
+        assert_eq!(1000, context.match_encoding(b"br").unwrap().q.as_millis());
+        assert!(context.match_mime_type(b"text/plain").is_some());
+    }
+}
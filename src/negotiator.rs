@@ -0,0 +1,353 @@
+//! One interface over the matchers for the Accept-* headers, so code that
+//! negotiates can be written once for all of them, and for custom headers.
+
+#[cfg(feature = "charset")]
+use crate::charset_matcher::{
+    get_charset_match_type, match_for_charset, parse_accept_charset, CharsetMatch,
+};
+#[cfg(feature = "language")]
+use crate::language_matcher::{
+    get_language_match_type, match_for_language, parse_accept_language, LanguageMatch,
+};
+use crate::q_value::QValue;
+#[cfg(feature = "mime")]
+use crate::{
+    accept::Accept,
+    mime_type_matcher::{match_for_mime_type, MimeTypeMatch},
+};
+#[cfg(feature = "encoding")]
+use crate::{
+    accept_encoding::AcceptEncoding,
+    encoding_matcher::{match_for_encoding, EncodingMatch},
+};
+#[cfg(any(feature = "charset", feature = "language"))]
+use crate::{explain::finish_entry, weighted_list::WeightedList};
+
+/// A header grammar that candidates, like codings or media types, are
+/// matched against: how to parse a value into entries, what the entry
+/// that applies to a candidate is, and how matches rank.
+pub trait ContentNegotiator {
+    /// A value parsed once, for matching several candidates.
+    type Parsed;
+    /// The entry that applies to a candidate. A greater match overrides a
+    /// lesser one when several entries apply.
+    type Match: Ord + Copy;
+
+    /// The lowercased header name, like "accept-encoding".
+    fn header_name(&self) -> &'static str;
+
+    /// Returns None if `input` is malformed.
+    fn parse(&self, input: &[u8]) -> Option<Self::Parsed>;
+
+    fn match_parsed(&self, parsed: &Self::Parsed, candidate: &[u8]) -> Option<Self::Match>;
+
+    fn q(&self, m: &Self::Match) -> QValue;
+
+    /// Matches without keeping the parsed value. Implementations can
+    /// override this with a parser that does not allocate.
+    fn match_value(&self, input: &[u8], candidate: &[u8]) -> Option<Self::Match> {
+        self.match_parsed(&self.parse(input)?, candidate)
+    }
+
+    /// Picks the candidate to respond with, in the server's order of
+    /// preference: the highest q-value wins, then the greater match, then
+    /// the earlier candidate. Returns None if none is acceptable.
+    fn negotiate_parsed<'a>(
+        &self,
+        parsed: &Self::Parsed,
+        candidates: &[&'a [u8]],
+    ) -> Option<&'a [u8]> {
+        pick(candidates, |c| {
+            self.match_parsed(parsed, c).map(|m| (self.q(&m), m))
+        })
+    }
+
+    /// Like `negotiate_parsed`, parsing `input` first.
+    fn negotiate<'a>(&self, input: &[u8], candidates: &[&'a [u8]]) -> Option<&'a [u8]> {
+        self.negotiate_parsed(&self.parse(input)?, candidates)
+    }
+}
+
+/// Returns the first of `candidates` with the greatest rank, leaving out
+/// unmatched ones and those with q=0.
+fn pick<'a, T: Ord>(
+    candidates: &[&'a [u8]],
+    matches: impl Fn(&[u8]) -> Option<(QValue, T)>,
+) -> Option<&'a [u8]> {
+    let mut best: Option<(&[u8], (QValue, T))> = None;
+    for &candidate in candidates {
+        let Some(rank) = matches(candidate).filter(|(q, _)| !q.is_zero()) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(_, b)| rank > *b) {
+            best = Some((candidate, rank));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+/// Ranks the entries `matches` applies to the way the matchers do.
+#[cfg(any(feature = "charset", feature = "language"))]
+fn best_entry<M: Ord + Copy>(
+    entries: &WeightedList<Box<[u8]>>,
+    matches: impl Fn(&[u8], QValue) -> Option<M>,
+) -> Option<M> {
+    let mut best = None;
+    for (entry, q) in entries.iter() {
+        let mut cur = matches(entry, q);
+        finish_entry(&mut cur, &mut best, 0, 0, &mut ());
+    }
+    best
+}
+
+/// Accept-Encoding, with the default options.
+#[cfg(feature = "encoding")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodingNegotiator;
+
+#[cfg(feature = "encoding")]
+impl ContentNegotiator for EncodingNegotiator {
+    type Parsed = AcceptEncoding;
+    type Match = EncodingMatch;
+
+    fn header_name(&self) -> &'static str {
+        "accept-encoding"
+    }
+
+    fn parse(&self, input: &[u8]) -> Option<AcceptEncoding> {
+        AcceptEncoding::parse(input)
+    }
+
+    fn match_parsed(&self, parsed: &AcceptEncoding, candidate: &[u8]) -> Option<EncodingMatch> {
+        parsed.match_encoding(candidate)
+    }
+
+    fn q(&self, m: &EncodingMatch) -> QValue {
+        m.q
+    }
+
+    fn match_value(&self, input: &[u8], candidate: &[u8]) -> Option<EncodingMatch> {
+        match_for_encoding(input, candidate)
+    }
+}
+
+/// Accept, with the default options.
+#[cfg(feature = "mime")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MimeTypeNegotiator;
+
+#[cfg(feature = "mime")]
+impl ContentNegotiator for MimeTypeNegotiator {
+    type Parsed = Accept;
+    type Match = MimeTypeMatch;
+
+    fn header_name(&self) -> &'static str {
+        "accept"
+    }
+
+    fn parse(&self, input: &[u8]) -> Option<Accept> {
+        Accept::parse(input)
+    }
+
+    fn match_parsed(&self, parsed: &Accept, candidate: &[u8]) -> Option<MimeTypeMatch> {
+        parsed.match_mime_type(candidate)
+    }
+
+    fn q(&self, m: &MimeTypeMatch) -> QValue {
+        m.q
+    }
+
+    fn match_value(&self, input: &[u8], candidate: &[u8]) -> Option<MimeTypeMatch> {
+        match_for_mime_type(input, candidate)
+    }
+}
+
+/// Accept-Language, by basic filtering.
+#[cfg(feature = "language")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LanguageNegotiator;
+
+#[cfg(feature = "language")]
+impl ContentNegotiator for LanguageNegotiator {
+    type Parsed = WeightedList<Box<[u8]>>;
+    type Match = LanguageMatch;
+
+    fn header_name(&self) -> &'static str {
+        "accept-language"
+    }
+
+    fn parse(&self, input: &[u8]) -> Option<Self::Parsed> {
+        parse_accept_language(input).map(|entries| entries.map(Box::from))
+    }
+
+    fn match_parsed(&self, parsed: &Self::Parsed, candidate: &[u8]) -> Option<LanguageMatch> {
+        best_entry(parsed, |range, q| {
+            get_language_match_type(range, candidate)
+                .map(|match_type| LanguageMatch { match_type, q })
+        })
+    }
+
+    fn q(&self, m: &LanguageMatch) -> QValue {
+        m.q
+    }
+
+    fn match_value(&self, input: &[u8], candidate: &[u8]) -> Option<LanguageMatch> {
+        match_for_language(input, candidate)
+    }
+}
+
+/// Accept-Charset.
+#[cfg(feature = "charset")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharsetNegotiator;
+
+#[cfg(feature = "charset")]
+impl ContentNegotiator for CharsetNegotiator {
+    type Parsed = WeightedList<Box<[u8]>>;
+    type Match = CharsetMatch;
+
+    fn header_name(&self) -> &'static str {
+        "accept-charset"
+    }
+
+    fn parse(&self, input: &[u8]) -> Option<Self::Parsed> {
+        parse_accept_charset(input).map(|entries| entries.map(Box::from))
+    }
+
+    fn match_parsed(&self, parsed: &Self::Parsed, candidate: &[u8]) -> Option<CharsetMatch> {
+        best_entry(parsed, |entry, q| {
+            get_charset_match_type(entry, candidate)
+                .map(|match_type| CharsetMatch { match_type, q })
+        })
+    }
+
+    fn q(&self, m: &CharsetMatch) -> QValue {
+        m.q
+    }
+
+    fn match_value(&self, input: &[u8], candidate: &[u8]) -> Option<CharsetMatch> {
+        match_for_charset(input, candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A custom header listing feature flags with q-values, matched
+    /// exactly: `Accept-Feature: dark-mode, beta;q=0.5`.
+    struct FeatureNegotiator;
+
+    impl ContentNegotiator for FeatureNegotiator {
+        type Parsed = Vec<(Vec<u8>, QValue)>;
+        type Match = QValue;
+
+        fn header_name(&self) -> &'static str {
+            "accept-feature"
+        }
+
+        fn parse(&self, input: &[u8]) -> Option<Self::Parsed> {
+            input
+                .split(|&b| b == b',')
+                .map(|entry| {
+                    let mut parts = entry.trim_ascii().splitn(2, |&b| b == b';');
+                    let name = parts.next()?.to_vec();
+                    let q = match parts.next() {
+                        Some(q) => QValue::try_from(q.strip_prefix(b"q=")?).ok()?,
+                        None => QValue::ONE,
+                    };
+                    Some((name, q))
+                })
+                .collect()
+        }
+
+        fn match_parsed(&self, parsed: &Self::Parsed, candidate: &[u8]) -> Option<QValue> {
+            parsed
+                .iter()
+                .find(|(name, _)| name == candidate)
+                .map(|(_, q)| *q)
+        }
+
+        fn q(&self, m: &QValue) -> QValue {
+            *m
+        }
+    }
+
+    #[test]
+    fn test_custom_negotiator() {
+        let n = FeatureNegotiator;
+        assert_eq!(
+            Some(&b"dark-mode"[..]),
+            n.negotiate(b"beta;q=0.5, dark-mode", &[b"beta", b"dark-mode"])
+        );
+        assert_eq!(None, n.negotiate(b"beta;q=0", &[b"beta"]));
+        assert_eq!(None, n.negotiate(b"beta;q=2", &[b"beta"]));
+        assert_eq!(Some(QValue::ONE), n.match_value(b"beta", b"beta"));
+    }
+
+    /// Checks that parsing first gives the same results as matching the
+    /// raw value.
+    fn check<N: ContentNegotiator>(n: N, input: &[u8], candidates: &[&[u8]])
+    where
+        N::Match: std::fmt::Debug,
+    {
+        let parsed = n.parse(input).unwrap();
+        for &candidate in candidates {
+            assert_eq!(
+                n.match_value(input, candidate),
+                n.match_parsed(&parsed, candidate),
+                "{:?}",
+                candidate
+            );
+        }
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_encoding_negotiator() {
+        let n = EncodingNegotiator;
+        check(
+            n,
+            b"gzip;q=0.5, br, *;q=0.1",
+            &[b"gzip", b"x-gzip", b"br", b"zstd"],
+        );
+        assert_eq!(
+            Some(&b"br"[..]),
+            n.negotiate(b"gzip;q=0.5, br", &[b"gzip", b"br"])
+        );
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_mime_type_negotiator() {
+        let n = MimeTypeNegotiator;
+        check(
+            n,
+            b"text/*;q=0.5, text/html",
+            &[b"text/html", b"text/plain", b"image/png"],
+        );
+        assert_eq!(
+            Some(&b"text/html"[..]),
+            n.negotiate(b"text/*;q=0.5, text/html", &[b"text/plain", b"text/html"])
+        );
+    }
+
+    #[cfg(feature = "language")]
+    #[test]
+    fn test_language_negotiator() {
+        let n = LanguageNegotiator;
+        check(n, b"en-US, en;q=0.5, *;q=0", &[b"en-US", b"en-GB", b"fr"]);
+        assert_eq!(None, n.negotiate(b"en-US, *;q=0", &[b"fr"]));
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn test_charset_negotiator() {
+        let n = CharsetNegotiator;
+        check(n, b"utf-8, *;q=0.1", &[b"UTF-8", b"shift_jis"]);
+        assert_eq!(
+            Some(&b"utf-8"[..]),
+            n.negotiate(b"utf-8, *;q=0.1", &[b"shift_jis", b"utf-8"])
+        );
+    }
+}
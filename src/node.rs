@@ -0,0 +1,82 @@
+//! Node.js bindings built with napi-rs. The functions and fields are
+//! camelCased on the JavaScript side, like `matchForEncoding` returning
+//! `{ matchType, q }`.
+
+use napi_derive::napi;
+
+use crate::{
+    encoding_matcher::{match_for_encoding as match_encoding, EncodingMatchType, Target},
+    mime_type_matcher::{match_for_mime_type as match_mime_type, MimeTypeMatchType},
+    options::MatchOptions,
+    supported_encodings::negotiate,
+};
+
+#[napi(object)]
+pub struct Match {
+    /// "exact" or "wildcard" for codings; "exact", "subtype_wildcard" or
+    /// "main_type_wildcard" for media types.
+    pub match_type: String,
+    pub q: f64,
+}
+
+/// Returns null if `header_value` does not accept `encoding` or is
+/// malformed.
+#[napi]
+pub fn match_for_encoding(header_value: String, encoding: String) -> Option<Match> {
+    let m = match_encoding(header_value.as_bytes(), encoding.as_bytes())?;
+    let match_type = match m.match_type {
+        EncodingMatchType::Wildcard => "wildcard",
+        EncodingMatchType::Exact => "exact",
+    };
+    Some(Match {
+        match_type: match_type.to_string(),
+        q: m.q.into(),
+    })
+}
+
+/// Returns null if `header_value` does not accept `mime_type` or is
+/// malformed.
+#[napi]
+pub fn match_for_mime_type(header_value: String, mime_type: String) -> Option<Match> {
+    let m = match_mime_type(header_value.as_bytes(), mime_type.as_bytes())?;
+    let match_type = match m.match_type {
+        MimeTypeMatchType::MainTypeWildcard => "main_type_wildcard",
+        MimeTypeMatchType::SubTypeWildcard => "subtype_wildcard",
+        MimeTypeMatchType::Exact => "exact",
+    };
+    Some(Match {
+        match_type: match_type.to_string(),
+        q: m.q.into(),
+    })
+}
+
+/// Picks the coding to respond with from `supported`, in the server's order
+/// of preference, or returns null if none is acceptable.
+#[napi]
+pub fn negotiate_encoding(header_value: String, supported: Vec<String>) -> Option<String> {
+    let targets = supported.iter().map(|s| Target::new(s.as_bytes()));
+    let (i, _) = negotiate(header_value.as_bytes(), targets, &MatchOptions::default())?;
+    supported.into_iter().nth(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_bindings() {
+        let m = match_for_encoding("gzip;q=0.5, *".into(), "gzip".into()).unwrap();
+        assert_eq!(("exact", 0.5), (m.match_type.as_str(), m.q));
+        assert!(match_for_encoding("gzip;q=2".into(), "gzip".into()).is_none());
+        let m = match_for_mime_type("text/html, image/*;q=0.8".into(), "image/png".into()).unwrap();
+        assert_eq!(("subtype_wildcard", 0.8), (m.match_type.as_str(), m.q));
+        assert_eq!(
+            Some("br".to_string()),
+            negotiate_encoding(
+                "gzip;q=0.5, br".into(),
+                vec!["zstd".into(), "gzip".into(), "br".into()]
+            )
+        );
+        assert_eq!(None, negotiate_encoding("gzip".into(), vec!["br".into()]));
+    }
+}
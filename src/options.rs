@@ -0,0 +1,105 @@
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::{
+    explain::Observer,
+    lexer::{self, Cursor, ParseError},
+    q_value::{QValue, Q_VALUE_FRAC_MAX_DIGITS},
+    warning::{Warning, WarningReason},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum RegistryCheck {
+    #[default]
+    Off,
+    /// Report tokens missing from the IANA registry as warnings.
+    Warn,
+    /// Treat tokens missing from the IANA registry as malformed.
+    Reject,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MatchOptions {
+    /// Tolerate what browsers get away with instead of rejecting the
+    /// whole header value: truncate q-values with more than three
+    /// fractional digits, ignore trailing whitespace and skip malformed
+    /// list elements.
+    pub lenient: bool,
+    /// Check content codings, or the top-level type of media ranges,
+    /// against the IANA registry. `*` always passes.
+    pub registry_check: RegistryCheck,
+}
+
+pub(crate) fn check_unregistered<M, O: Observer<M>>(
+    options: &MatchOptions,
+    token_start: Cursor,
+    observer: &mut O,
+) -> Result<(), ParseError> {
+    match options.registry_check {
+        RegistryCheck::Off => Ok(()),
+        RegistryCheck::Warn => {
+            observer.warning(Warning {
+                offset: token_start.0,
+                reason: WarningReason::UnregisteredToken,
+            });
+            Ok(())
+        }
+        RegistryCheck::Reject => Err(ParseError),
+    }
+}
+
+/// Lexes the value of a q parameter, truncating extra fractional digits
+/// with a warning when matching is lenient.
+pub(crate) fn q_value<M, O: Observer<M>>(
+    input: &[u8],
+    options: &MatchOptions,
+    c: &mut Cursor,
+    observer: &mut O,
+) -> Result<QValue, ParseError> {
+    if !options.lenient {
+        return lexer::q_value(input, c);
+    }
+    let c1 = *c;
+    let q = lexer::q_value_truncating(input, c)?;
+    if c.0 - c1.0 > 2 + Q_VALUE_FRAC_MAX_DIGITS as usize {
+        observer.warning(Warning {
+            offset: c1.0,
+            reason: WarningReason::TruncatedQValue,
+        });
+        #[cfg(feature = "tracing")]
+        trace::truncated_q_value(input, c1, *c);
+    }
+    Ok(q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_options_default() {
+        assert_eq!(
+            MatchOptions {
+                lenient: false,
+                registry_check: RegistryCheck::Off,
+            },
+            MatchOptions::default()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_match_options_serde() {
+        assert_eq!(
+            MatchOptions {
+                lenient: true,
+                ..Default::default()
+            },
+            serde_json::from_str(r#"{"lenient":true}"#).unwrap()
+        );
+    }
+}
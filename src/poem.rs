@@ -0,0 +1,172 @@
+//! poem integration: a middleware that negotiates the response coding, and
+//! an extractor for negotiating the media type in a handler.
+
+use std::sync::Arc;
+
+use poem::{
+    async_trait,
+    http::{
+        header::{HeaderName, ACCEPT_ENCODING},
+        HeaderMap,
+    },
+    Endpoint, Middleware, Request, Result,
+};
+
+use crate::{
+    byte_slice::join_field_values,
+    supported_encodings::{NegotiatedEncoding, SupportedEncodings},
+};
+#[cfg(feature = "mime")]
+use crate::{
+    matched::pick_best,
+    mime_type_matcher::{match_for_mime_type, MimeTypeMatch},
+};
+
+/// A middleware that stores a `NegotiatedEncoding` in each request's
+/// extensions, for handlers to extract with `Data<&NegotiatedEncoding>`.
+#[derive(Debug, Clone)]
+pub struct NegotiateEncoding {
+    supported: Arc<SupportedEncodings>,
+}
+
+impl NegotiateEncoding {
+    pub fn new(supported: SupportedEncodings) -> Self {
+        Self {
+            supported: Arc::new(supported),
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for NegotiateEncoding {
+    type Output = NegotiateEncodingEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        NegotiateEncodingEndpoint {
+            inner,
+            supported: self.supported.clone(),
+        }
+    }
+}
+
+/// The endpoint `NegotiateEncoding` wraps others in.
+pub struct NegotiateEncodingEndpoint<E> {
+    inner: E,
+    supported: Arc<SupportedEncodings>,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for NegotiateEncodingEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut request: Request) -> Result<Self::Output> {
+        let encoding = self
+            .supported
+            .best_match(&header_value(request.headers(), ACCEPT_ENCODING))
+            .map(|(e, _)| String::from_utf8_lossy(e).into_owned());
+        request
+            .extensions_mut()
+            .insert(NegotiatedEncoding { encoding });
+        self.inner.call(request).await
+    }
+}
+
+/// The request's Accept fields, joined with ", ", extracted for picking the
+/// media type of the response.
+#[cfg(feature = "mime")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptHeader(Vec<u8>);
+
+#[cfg(feature = "mime")]
+impl AcceptHeader {
+    pub fn match_mime_type(&self, mime_type: &str) -> Option<MimeTypeMatch> {
+        match_for_mime_type(&self.0, mime_type.as_bytes())
+    }
+
+    /// Picks the media type to respond with from `mime_types`, in the
+    /// server's order of preference: the highest q-value wins, then the one
+    /// matched most specifically, then the earlier one. Returns None if
+    /// none is acceptable.
+    pub fn negotiate<'a>(&self, mime_types: &[&'a str]) -> Option<&'a str> {
+        pick_best(mime_types.iter().map(|&t| (t, self.match_mime_type(t))))
+    }
+}
+
+#[cfg(feature = "mime")]
+#[async_trait]
+impl<'a> poem::FromRequest<'a> for AcceptHeader {
+    async fn from_request(request: &'a Request, _body: &mut poem::RequestBody) -> Result<Self> {
+        let value = header_value(request.headers(), poem::http::header::ACCEPT);
+        Ok(Self(value.into_owned()))
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: HeaderName) -> std::borrow::Cow<'_, [u8]> {
+    join_field_values(headers.get_all(name).iter().map(|v| v.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use poem::{handler, web::Data, EndpointExt, IntoEndpoint};
+
+    use super::*;
+
+    /// Calls `endpoint` with the header fields and returns the body.
+    fn call(endpoint: impl IntoEndpoint, headers: &[(HeaderName, &'static str)]) -> String {
+        let mut request = Request::builder();
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let response = endpoint
+                    .into_endpoint()
+                    .get_response(request.finish())
+                    .await;
+                response.into_body().into_string().await.unwrap()
+            })
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        #[handler]
+        fn index(negotiated: Data<&NegotiatedEncoding>) -> String {
+            negotiated.encoding.clone().unwrap_or_default()
+        }
+
+        let supported = SupportedEncodings::new(["br", "gzip"]).unwrap();
+        let app = index.with(NegotiateEncoding::new(supported));
+        assert_eq!(
+            "gzip",
+            call(
+                &app,
+                &[(ACCEPT_ENCODING, "gzip"), (ACCEPT_ENCODING, "br;q=0.5")]
+            )
+        );
+        assert_eq!("", call(&app, &[]));
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_accept_header() {
+        use poem::http::header::ACCEPT;
+
+        #[handler]
+        fn index(accept: AcceptHeader) -> String {
+            accept
+                .negotiate(&["application/json", "text/html", "image/webp"])
+                .unwrap_or("none")
+                .to_string()
+        }
+
+        for (accept, expected) in [
+            ("text/*, application/json;q=0.5", "text/html"),
+            ("*/*, image/webp", "image/webp"),
+            ("*/*", "application/json"),
+            ("text/plain", "none"),
+        ] {
+            assert_eq!(expected, call(index, &[(ACCEPT, accept)]));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Helpers for proxy-wasm filters, such as Envoy's. They take header maps
+//! in the shape `get_http_request_headers` and `get_http_response_headers`
+//! return them, and return the changes to apply with
+//! `set_http_response_header`.
+
+use crate::supported_encodings::SupportedEncodings;
+
+/// What a filter should do to a response to compress it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMutations {
+    /// The coding to compress the body with, or None to send it as is.
+    pub encoding: Option<String>,
+    /// Headers to set, replacing any value they have.
+    pub set_headers: Vec<(String, String)>,
+    /// Headers to remove, since the compressed body has a different length.
+    pub remove_headers: Vec<String>,
+}
+
+/// Picks a coding from `supported` for a response by the request's
+/// Accept-Encoding values, and returns the header changes for it. Vary is
+/// extended with Accept-Encoding even when nothing is compressed, since
+/// the choice depends on it. A response that already has a
+/// Content-Encoding is left alone.
+pub fn negotiate_response_headers(
+    request_headers: &[(String, String)],
+    response_headers: &[(String, String)],
+    supported: &SupportedEncodings,
+) -> HeaderMutations {
+    if header_values(response_headers, "content-encoding")
+        .next()
+        .is_some()
+    {
+        return HeaderMutations::default();
+    }
+
+    let mut mutations = HeaderMutations::default();
+    if let Some(vary) = vary_with_accept_encoding(response_headers) {
+        mutations.set_headers.push(("vary".to_string(), vary));
+    }
+    let accept_encoding = header_values(request_headers, "accept-encoding")
+        .collect::<Vec<_>>()
+        .join(", ");
+    if accept_encoding.is_empty() {
+        return mutations;
+    }
+    let Some((encoding, _)) = supported.best_match(accept_encoding.as_bytes()) else {
+        return mutations;
+    };
+    if encoding == b"identity" {
+        return mutations;
+    }
+    let encoding = String::from_utf8_lossy(encoding).into_owned();
+    mutations
+        .set_headers
+        .push(("content-encoding".to_string(), encoding.clone()));
+    mutations.remove_headers.push("content-length".to_string());
+    mutations.encoding = Some(encoding);
+    mutations
+}
+
+/// The values of every `name` field; header names are case-insensitive.
+fn header_values<'a>(
+    headers: &'a [(String, String)],
+    name: &'a str,
+) -> impl Iterator<Item = &'a str> {
+    headers
+        .iter()
+        .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Returns the Vary value with Accept-Encoding added, or None if it already
+/// covers it.
+fn vary_with_accept_encoding(response_headers: &[(String, String)]) -> Option<String> {
+    let values = header_values(response_headers, "vary").collect::<Vec<_>>();
+    let covered = values
+        .iter()
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|v| v == "*" || v.eq_ignore_ascii_case("accept-encoding"));
+    if covered {
+        return None;
+    }
+    let mut vary = values.join(", ");
+    if !vary.is_empty() {
+        vary.push_str(", ");
+    }
+    vary.push_str("Accept-Encoding");
+    Some(vary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_negotiate_response_headers() {
+        let supported = SupportedEncodings::new(["br", "gzip", "identity"]).unwrap();
+        let mutations = negotiate_response_headers(
+            &headers(&[
+                (":path", "/"),
+                ("Accept-Encoding", "gzip"),
+                ("accept-encoding", "br;q=0.5"),
+            ]),
+            &headers(&[("content-length", "100"), ("Vary", "Origin")]),
+            &supported,
+        );
+        assert_eq!(
+            HeaderMutations {
+                encoding: Some("gzip".to_string()),
+                set_headers: headers(&[
+                    ("vary", "Origin, Accept-Encoding"),
+                    ("content-encoding", "gzip")
+                ]),
+                remove_headers: vec!["content-length".to_string()],
+            },
+            mutations
+        );
+
+        let mutations = negotiate_response_headers(
+            &headers(&[("accept-encoding", "identity, *;q=0")]),
+            &headers(&[("vary", "accept-encoding")]),
+            &supported,
+        );
+        assert_eq!(HeaderMutations::default(), mutations);
+
+        let mutations = negotiate_response_headers(&[], &[], &supported);
+        assert_eq!(
+            headers(&[("vary", "Accept-Encoding")]),
+            mutations.set_headers
+        );
+        assert_eq!(None, mutations.encoding);
+
+        let mutations = negotiate_response_headers(
+            &headers(&[("accept-encoding", "gzip")]),
+            &headers(&[("content-encoding", "br")]),
+            &supported,
+        );
+        assert_eq!(HeaderMutations::default(), mutations);
+    }
+}
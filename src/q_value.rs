@@ -1,90 +1,294 @@
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Copy, Clone)]
+use std::{cmp::Ordering, error::Error, fmt};
+
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Copy, Clone, Hash)]
 pub struct QValue {
     millis: u16,
 }
 
-#[derive(Debug, PartialEq)]
-pub struct InvaliQValueError;
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum InvalidQValueError {
+    /// The value is below 0 or above 1.
+    OutOfRange,
+    /// The value contains something other than `0`, `1`, `.` and digits
+    /// where the q-value grammar expects them.
+    BadDigit,
+    /// The value has more than three fractional digits.
+    TooManyDigits,
+}
+
+#[deprecated(note = "renamed to InvalidQValueError")]
+pub type InvaliQValueError = InvalidQValueError;
+
+impl fmt::Display for InvalidQValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InvalidQValueError::OutOfRange => "q-value out of range 0 to 1",
+            InvalidQValueError::BadDigit => "invalid character in q-value",
+            InvalidQValueError::TooManyDigits => "q-value has more than three fractional digits",
+        })
+    }
+}
+
+impl Error for InvalidQValueError {}
 
 pub(crate) const Q_VALUE_FRAC_MAX_DIGITS: u32 = 3;
 
 impl QValue {
-    pub(crate) fn from_millis(millis: u16) -> Result<Self, InvaliQValueError> {
+    pub const ZERO: QValue = QValue { millis: 0 };
+    pub const ONE: QValue = QValue { millis: 1000 };
+
+    pub const fn from_millis(millis: u16) -> Result<Self, InvalidQValueError> {
         if millis <= 10u16.pow(Q_VALUE_FRAC_MAX_DIGITS) {
             Ok(Self { millis })
         } else {
-            Err(InvaliQValueError)
+            Err(InvalidQValueError::OutOfRange)
+        }
+    }
+
+    /// Builds a `QValue` in a const context, e.g.
+    /// `const THRESHOLD: QValue = QValue::const_millis(500);`.
+    /// Panics if `millis` is above 1000, which fails the build there.
+    pub const fn const_millis(millis: u16) -> Self {
+        match Self::from_millis(millis) {
+            Ok(q) => q,
+            Err(_) => panic!("q-value millis out of range 0 to 1000"),
+        }
+    }
+
+    pub const fn from_percent(percent: u8) -> Result<Self, InvalidQValueError> {
+        Self::from_millis(percent as u16 * 10)
+    }
+
+    pub const fn as_millis(&self) -> u16 {
+        self.millis
+    }
+
+    /// Combines two weights, e.g. a client q with a server qs, rounding
+    /// the product half up to the nearest millis.
+    pub const fn saturating_mul(self, other: QValue) -> QValue {
+        let scale = 10u32.pow(Q_VALUE_FRAC_MAX_DIGITS);
+        let millis = (self.millis as u32 * other.millis as u32 + scale / 2) / scale;
+        QValue {
+            millis: if millis < scale { millis } else { scale } as u16,
+        }
+    }
+
+    /// Returns true for q=0, which marks a value as not acceptable.
+    pub const fn is_zero(&self) -> bool {
+        self.millis == Self::ZERO.millis
+    }
+
+    pub const fn is_max(&self) -> bool {
+        self.millis == Self::ONE.millis
+    }
+
+    /// Appends the shortest q-value text for this value, like `0.25`.
+    pub(crate) fn write_to(self, out: &mut Vec<u8>) {
+        if self.is_max() {
+            out.push(b'1');
+            return;
+        }
+        out.push(b'0');
+        let mut frac = self.millis;
+        if frac == 0 {
+            return;
+        }
+        out.push(b'.');
+        let mut divisor = 100;
+        while frac != 0 {
+            out.push(b'0' + (frac / divisor) as u8);
+            frac %= divisor;
+            divisor /= 10;
+        }
+    }
+
+    /// Like `QValue::try_from(&str)`, but drops fractional digits beyond
+    /// the third instead of rejecting them, e.g. "0.99999" becomes 0.999.
+    pub fn try_from_truncating(s: &str) -> Result<Self, InvalidQValueError> {
+        Self::try_from_bytes_truncating(s.as_bytes())
+    }
+
+    pub fn try_from_bytes_truncating(v: &[u8]) -> Result<Self, InvalidQValueError> {
+        const MAX_LEN: usize = 2 + Q_VALUE_FRAC_MAX_DIGITS as usize;
+        if v.len() > MAX_LEN && v[MAX_LEN..].iter().all(u8::is_ascii_digit) {
+            Self::try_from(&v[..MAX_LEN])
+        } else {
+            Self::try_from(v)
         }
     }
 }
 
 impl TryFrom<&str> for QValue {
-    type Error = InvaliQValueError;
+    type Error = InvalidQValueError;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        const MAX_LEN: usize = 2 + Q_VALUE_FRAC_MAX_DIGITS as usize;
-        let v = s.as_bytes();
-        if !v.is_empty() {
-            match v[0] {
-                b'0' => {
-                    if (v.len() > 1 && v[1] != b'.') || v.len() > MAX_LEN {
-                        return Err(InvaliQValueError);
-                    }
-                    let mut millis: u16 = 0;
-                    if v.len() > 2 {
-                        for b in &v[2..] {
-                            match *b {
-                                b'0'..=b'9' => {
-                                    millis *= 10;
-                                    millis += (*b - b'0') as u16;
-                                }
-                                _ => return Err(InvaliQValueError),
-                            }
-                        }
-                        for _ in 0..MAX_LEN - v.len() {
-                            millis *= 10;
-                        }
+        Self::try_from(s.as_bytes())
+    }
+}
+
+impl TryFrom<&[u8]> for QValue {
+    type Error = InvalidQValueError;
+
+    fn try_from(v: &[u8]) -> Result<Self, Self::Error> {
+        if v.len() > 1 && v[1] != b'.' {
+            return Err(if matches!(v[0], b'1'..=b'9') && v[1].is_ascii_digit() {
+                InvalidQValueError::OutOfRange
+            } else {
+                InvalidQValueError::BadDigit
+            });
+        }
+        let frac = v.get(2..).unwrap_or_default();
+        if !frac.iter().all(u8::is_ascii_digit) {
+            return Err(InvalidQValueError::BadDigit);
+        }
+        let millis = match v.first() {
+            Some(b'0') => {
+                let mut millis: u16 = 0;
+                for i in 0..Q_VALUE_FRAC_MAX_DIGITS as usize {
+                    millis *= 10;
+                    if let Some(b) = frac.get(i) {
+                        millis += (*b - b'0') as u16;
                     }
-                    return Ok(Self { millis });
                 }
-                b'1' => {
-                    if (v.len() > 1 && v[1] != b'.') || v.len() > MAX_LEN {
-                        return Err(InvaliQValueError);
-                    }
-                    if v.len() > 2 {
-                        for b in &v[2..] {
-                            if *b != b'0' {
-                                return Err(InvaliQValueError);
-                            }
-                        }
-                    }
-                    return Ok(Self { millis: 1000 });
+                millis
+            }
+            Some(b'1') => {
+                if frac.iter().any(|b| *b != b'0') {
+                    return Err(InvalidQValueError::OutOfRange);
                 }
-                _ => {}
+                1000
             }
+            Some(b'2'..=b'9') => return Err(InvalidQValueError::OutOfRange),
+            _ => return Err(InvalidQValueError::BadDigit),
+        };
+        if frac.len() > Q_VALUE_FRAC_MAX_DIGITS as usize {
+            return Err(InvalidQValueError::TooManyDigits);
         }
-        Err(InvaliQValueError)
+        Ok(Self { millis })
     }
 }
 
-impl TryFrom<f64> for QValue {
-    type Error = InvaliQValueError;
-    #[inline]
-    fn try_from(v: f64) -> Result<Self, Self::Error> {
+impl QValue {
+    /// Converts `v` dropping digits past the third fractional one, so
+    /// 0.8999 becomes 0.899. This is what `QValue::try_from(f64)` does.
+    pub fn from_f64_truncating(v: f64) -> Result<Self, InvalidQValueError> {
+        // Scaling lands just below the millis for values like 0.58, which
+        // becomes 579.9999999999999, so round that error away first.
+        Self::from_scaled_f64(v, |scaled| ((scaled * 1e9).round() / 1e9).trunc())
+    }
+
+    /// Converts `v` rounding half up to the nearest millis, so 0.8999
+    /// becomes 0.9 and 0.8995 becomes 0.9.
+    pub fn from_f64_rounding(v: f64) -> Result<Self, InvalidQValueError> {
+        Self::from_scaled_f64(v, |scaled| (scaled + 0.5).floor())
+    }
+
+    /// Converts `v` only if it has at most three fractional digits, so
+    /// 0.8999 is an error while 0.7 becomes 0.7.
+    pub fn from_f64_exact(v: f64) -> Result<Self, InvalidQValueError> {
+        let q = Self::from_f64_rounding(v)?;
+        if f64::from(q) == v {
+            Ok(q)
+        } else {
+            Err(InvalidQValueError::TooManyDigits)
+        }
+    }
+
+    fn from_scaled_f64(v: f64, f: impl Fn(f64) -> f64) -> Result<Self, InvalidQValueError> {
         if v.is_nan() || !(0.0..=1.0).contains(&v) {
-            Err(InvaliQValueError)
+            Err(InvalidQValueError::OutOfRange)
         } else {
-            QValue::from_millis((v * 10u16.pow(Q_VALUE_FRAC_MAX_DIGITS) as f64) as u16)
+            QValue::from_millis(f(v * 10u16.pow(Q_VALUE_FRAC_MAX_DIGITS) as f64) as u16)
         }
     }
 }
 
+impl TryFrom<f64> for QValue {
+    type Error = InvalidQValueError;
+    #[inline]
+    fn try_from(v: f64) -> Result<Self, Self::Error> {
+        Self::from_f64_truncating(v)
+    }
+}
+
 impl From<QValue> for f64 {
     fn from(source: QValue) -> f64 {
         source.millis as f64 / 10_u32.pow(Q_VALUE_FRAC_MAX_DIGITS) as f64
     }
 }
 
+/// Converts from the 0 to 1000 integer that typed-header crates such as
+/// hyperx and headers use for quality values.
+impl TryFrom<u16> for QValue {
+    type Error = InvalidQValueError;
+
+    fn try_from(millis: u16) -> Result<Self, Self::Error> {
+        Self::from_millis(millis)
+    }
+}
+
+impl From<QValue> for u16 {
+    fn from(source: QValue) -> u16 {
+        source.millis
+    }
+}
+
+/// Compares the value a `QValue` stands for, which is exact to the
+/// millis, with `f64`, e.g. `q >= 0.5`. A `QValue` of 0.899 equals
+/// `0.899` but neither `0.8991` nor `0.8989`.
+impl PartialEq<f64> for QValue {
+    fn eq(&self, other: &f64) -> bool {
+        f64::from(*self) == *other
+    }
+}
+
+impl PartialOrd<f64> for QValue {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        f64::from(*self).partial_cmp(other)
+    }
+}
+
+impl PartialEq<QValue> for f64 {
+    fn eq(&self, other: &QValue) -> bool {
+        other == self
+    }
+}
+
+impl PartialOrd<QValue> for f64 {
+    fn partial_cmp(&self, other: &QValue) -> Option<Ordering> {
+        other.partial_cmp(self).map(Ordering::reverse)
+    }
+}
+
+/// Serialized as the number a header would carry, e.g. `0.8`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for QValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(f64::from(*self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for QValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        QValue::try_from(f64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Always produces a valid value in the 0 to 1000 millis range.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for QValue {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(QValue {
+            millis: u.int_in_range(0..=QValue::ONE.millis)?,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u16::size_hint(depth)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -96,24 +300,135 @@ mod test {
         assert_eq!(Ok(QValue { millis: 100 }), QValue::try_from("0.1"));
         assert_eq!(Ok(QValue { millis: 120 }), QValue::try_from("0.12"));
         assert_eq!(Ok(QValue { millis: 123 }), QValue::try_from("0.123"));
-        assert_eq!(Err(InvaliQValueError), QValue::try_from("0.1235"));
+        assert_eq!(
+            Err(InvalidQValueError::TooManyDigits),
+            QValue::try_from("0.1235")
+        );
 
         assert_eq!(Ok(QValue { millis: 1000 }), QValue::try_from("1"));
         assert_eq!(Ok(QValue { millis: 1000 }), QValue::try_from("1."));
         assert_eq!(Ok(QValue { millis: 1000 }), QValue::try_from("1.0"));
         assert_eq!(Ok(QValue { millis: 1000 }), QValue::try_from("1.00"));
         assert_eq!(Ok(QValue { millis: 1000 }), QValue::try_from("1.000"));
-        assert_eq!(Err(InvaliQValueError), QValue::try_from("1.0000"));
-        assert_eq!(Err(InvaliQValueError), QValue::try_from("1.1"));
+        assert_eq!(
+            Err(InvalidQValueError::TooManyDigits),
+            QValue::try_from("1.0000")
+        );
+        assert_eq!(Err(InvalidQValueError::OutOfRange), QValue::try_from("1.1"));
+        assert_eq!(Err(InvalidQValueError::OutOfRange), QValue::try_from("2"));
+        assert_eq!(Err(InvalidQValueError::OutOfRange), QValue::try_from("10"));
 
-        assert_eq!(Err(InvaliQValueError), QValue::try_from("-0"));
-        assert_eq!(Err(InvaliQValueError), QValue::try_from("0.a"));
+        assert_eq!(Err(InvalidQValueError::BadDigit), QValue::try_from("-0"));
+        assert_eq!(Err(InvalidQValueError::BadDigit), QValue::try_from(""));
+        assert_eq!(Err(InvalidQValueError::BadDigit), QValue::try_from("00"));
+        assert_eq!(Err(InvalidQValueError::BadDigit), QValue::try_from("0.a"));
+        assert_eq!(
+            Err(InvalidQValueError::BadDigit),
+            QValue::try_from("0.1234a")
+        );
+    }
+
+    #[test]
+    fn test_qvalue_try_from_truncating() {
+        assert_eq!(
+            Ok(QValue { millis: 999 }),
+            QValue::try_from_truncating("0.99999")
+        );
+        assert_eq!(
+            Ok(QValue { millis: 123 }),
+            QValue::try_from_truncating("0.1239")
+        );
+        assert_eq!(
+            Ok(QValue { millis: 500 }),
+            QValue::try_from_truncating("0.5")
+        );
+        assert_eq!(
+            Ok(QValue { millis: 1000 }),
+            QValue::try_from_truncating("1.00000")
+        );
+
+        assert_eq!(
+            Ok(QValue { millis: 1000 }),
+            QValue::try_from_truncating("1.0001")
+        );
+
+        assert_eq!(
+            Err(InvalidQValueError::OutOfRange),
+            QValue::try_from_truncating("1.1234")
+        );
+        assert_eq!(
+            Err(InvalidQValueError::BadDigit),
+            QValue::try_from_truncating("0.1234a")
+        );
+        assert_eq!(
+            Err(InvalidQValueError::BadDigit),
+            QValue::try_from_truncating("012345")
+        );
+    }
+
+    #[test]
+    fn test_qvalue_consts() {
+        assert_eq!(QValue::from_millis(0).unwrap(), QValue::ZERO);
+        assert_eq!(QValue::from_millis(1000).unwrap(), QValue::ONE);
+
+        assert!(QValue::ZERO.is_zero());
+        assert!(!QValue::ZERO.is_max());
+        assert!(QValue::ONE.is_max());
+        assert!(!QValue::ONE.is_zero());
+        assert!(!QValue::try_from(0.5).unwrap().is_zero());
+        assert!(!QValue::try_from(0.5).unwrap().is_max());
     }
 
     #[test]
     fn test_qvalue_from_millis() {
         assert_eq!(QValue { millis: 100 }, QValue::from_millis(100).unwrap());
-        assert_eq!(Err(InvaliQValueError), QValue::from_millis(1001));
+        assert_eq!(
+            Err(InvalidQValueError::OutOfRange),
+            QValue::from_millis(1001)
+        );
+    }
+
+    #[test]
+    fn test_qvalue_const_fn() {
+        const THRESHOLD: QValue = QValue::const_millis(500);
+        const SQUARED: QValue = THRESHOLD.saturating_mul(THRESHOLD);
+        const { assert!(!SQUARED.is_zero()) };
+        assert_eq!(500, THRESHOLD.as_millis());
+        assert_eq!(250, SQUARED.as_millis());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_qvalue_const_millis_out_of_range() {
+        QValue::const_millis(1001);
+    }
+
+    #[test]
+    fn test_qvalue_from_percent() {
+        assert_eq!(QValue { millis: 0 }, QValue::from_percent(0).unwrap());
+        assert_eq!(QValue { millis: 850 }, QValue::from_percent(85).unwrap());
+        assert_eq!(QValue { millis: 1000 }, QValue::from_percent(100).unwrap());
+        assert_eq!(
+            Err(InvalidQValueError::OutOfRange),
+            QValue::from_percent(101)
+        );
+    }
+
+    #[test]
+    fn test_qvalue_saturating_mul() {
+        let q = |millis| QValue::from_millis(millis).unwrap();
+        assert_eq!(q(400), q(800).saturating_mul(q(500)));
+        assert_eq!(q(1), q(1).saturating_mul(q(500)));
+        assert_eq!(q(0), q(1).saturating_mul(q(499)));
+        assert_eq!(q(998), q(999).saturating_mul(q(999)));
+        assert_eq!(QValue::ONE, QValue::ONE.saturating_mul(QValue::ONE));
+        assert_eq!(QValue::ZERO, QValue::ZERO.saturating_mul(QValue::ONE));
+    }
+
+    #[test]
+    fn test_qvalue_as_millis() {
+        assert_eq!(123, QValue::try_from("0.123").unwrap().as_millis());
+        assert_eq!(1000, QValue::ONE.as_millis());
     }
 
     #[test]
@@ -133,11 +448,28 @@ mod test {
     #[test]
     fn test_qvalue_invalid_error_format_debug() {
         assert_eq!(
-            "InvaliQValueError".to_string(),
-            format!("{:?}", InvaliQValueError)
+            "OutOfRange".to_string(),
+            format!("{:?}", InvalidQValueError::OutOfRange)
         )
     }
 
+    #[test]
+    fn test_qvalue_invalid_error_display() {
+        assert_eq!(
+            "q-value has more than three fractional digits",
+            InvalidQValueError::TooManyDigits.to_string()
+        );
+        let err: Box<dyn Error> = Box::new(InvalidQValueError::BadDigit);
+        assert_eq!("invalid character in q-value", err.to_string());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_qvalue_invalid_error_deprecated_alias() {
+        let err: InvaliQValueError = InvalidQValueError::OutOfRange;
+        assert_eq!(Err(err), QValue::from_millis(1001));
+    }
+
     #[test]
     fn test_qvalue_try_from_f64() {
         assert_eq!(
@@ -145,11 +477,144 @@ mod test {
             QValue::try_from(0.1).unwrap()
         );
 
-        assert_eq!(Err(InvaliQValueError), QValue::try_from(1.01));
+        assert_eq!(Err(InvalidQValueError::OutOfRange), QValue::try_from(1.01));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_qvalue_serde() {
+        assert_eq!(
+            "0.85",
+            serde_json::to_string(&QValue::from_millis(850).unwrap()).unwrap()
+        );
+        assert_eq!(
+            QValue::from_millis(580).unwrap(),
+            serde_json::from_str::<QValue>("0.58").unwrap()
+        );
+        assert_eq!(
+            QValue::from_millis(899).unwrap(),
+            serde_json::from_str::<QValue>("0.8999").unwrap()
+        );
+        assert_eq!(QValue::ONE, serde_json::from_str::<QValue>("1").unwrap());
+        assert!(serde_json::from_str::<QValue>("1.5").is_err());
+        assert!(serde_json::from_str::<QValue>("-0.1").is_err());
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_qvalue_arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        for data in [&[][..], &[0xff, 0xff], &[0x12, 0x34, 0x56]] {
+            let q = QValue::arbitrary(&mut Unstructured::new(data)).unwrap();
+            assert!(q.as_millis() <= 1000);
+        }
+    }
+
+    #[test]
+    fn test_qvalue_from_f64_rounding_modes() {
+        let q = |millis| QValue::from_millis(millis).unwrap();
+
+        assert_eq!(Ok(q(899)), QValue::from_f64_truncating(0.8999));
+        assert_eq!(Ok(q(580)), QValue::from_f64_truncating(0.58));
+        assert_eq!(Ok(q(290)), QValue::from_f64_truncating(0.29));
+        assert_eq!(Ok(q(570)), QValue::from_f64_truncating(0.57));
+        assert_eq!(Ok(q(1)), QValue::from_f64_truncating(0.001));
+        assert_eq!(Ok(QValue::ONE), QValue::from_f64_truncating(1.0));
+        for millis in 0..=1000 {
+            assert_eq!(
+                Ok(q(millis)),
+                QValue::from_f64_truncating(f64::from(q(millis)))
+            );
+        }
+        assert_eq!(Ok(q(900)), QValue::from_f64_rounding(0.8999));
+        assert_eq!(Ok(q(900)), QValue::from_f64_rounding(0.8995));
+        assert_eq!(Ok(q(899)), QValue::from_f64_rounding(0.8994));
+        assert_eq!(
+            Err(InvalidQValueError::TooManyDigits),
+            QValue::from_f64_exact(0.8999)
+        );
+
+        assert_eq!(Ok(q(700)), QValue::from_f64_exact(0.7));
+        assert_eq!(Ok(q(580)), QValue::from_f64_exact(0.58));
+        assert_eq!(Ok(QValue::ONE), QValue::from_f64_exact(1.0));
+
+        for f in [
+            QValue::from_f64_truncating,
+            QValue::from_f64_rounding,
+            QValue::from_f64_exact,
+        ] {
+            assert_eq!(Err(InvalidQValueError::OutOfRange), f(-0.1));
+            assert_eq!(Err(InvalidQValueError::OutOfRange), f(f64::NAN));
+        }
+    }
+
+    #[test]
+    fn test_qvalue_cmp_f64() {
+        let q = QValue::from_millis(899).unwrap();
+        assert!(q == 0.899);
+        assert!(q != 0.8991);
+        assert!(q > 0.8989);
+        assert!(q >= 0.5);
+        assert!(q < 0.9);
+        assert!(q.partial_cmp(&f64::NAN).is_none());
+
+        assert!(0.899 == q);
+        assert!(0.5 <= q);
+        assert!(1.0 > q);
+        assert!(QValue::ONE == 1.0);
+        assert!(QValue::ZERO <= 0.0);
+    }
+
+    #[test]
+    fn test_qvalue_try_from_bytes() {
+        assert_eq!(Ok(QValue { millis: 500 }), QValue::try_from(&b"0.5"[..]));
+        assert_eq!(Ok(QValue { millis: 1000 }), QValue::try_from(&b"1.000"[..]));
+        assert_eq!(
+            Err(InvalidQValueError::BadDigit),
+            QValue::try_from(&b"0.\xff"[..])
+        );
+        assert_eq!(
+            Ok(QValue { millis: 999 }),
+            QValue::try_from_bytes_truncating(b"0.99999")
+        );
+        assert_eq!(
+            Err(InvalidQValueError::BadDigit),
+            QValue::try_from_bytes_truncating(b"0.999\xff")
+        );
+    }
+
+    #[test]
+    fn test_qvalue_u16_conversions() {
+        assert_eq!(Ok(QValue { millis: 300 }), QValue::try_from(300u16));
+        assert_eq!(
+            Err(InvalidQValueError::OutOfRange),
+            QValue::try_from(1001u16)
+        );
+        assert_eq!(300u16, u16::from(QValue { millis: 300 }));
     }
 
     #[test]
     fn test_f64_from_qvalue() {
         assert_eq!(0.1, f64::from(QValue::from_millis(100).unwrap()))
     }
+
+    #[test]
+    fn test_qvalue_write_to() {
+        for (millis, text) in [
+            (1000, "1"),
+            (0, "0"),
+            (500, "0.5"),
+            (250, "0.25"),
+            (7, "0.007"),
+        ] {
+            let mut out = Vec::new();
+            QValue::from_millis(millis).unwrap().write_to(&mut out);
+            assert_eq!(text.as_bytes(), &out[..]);
+            assert_eq!(
+                Ok(QValue::from_millis(millis).unwrap()),
+                QValue::try_from(text)
+            );
+        }
+    }
 }
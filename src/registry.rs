@@ -0,0 +1,111 @@
+use crate::byte_slice::bytes_eq_ignore_case;
+
+// https://www.iana.org/assignments/http-parameters/http-parameters.xhtml#content-coding
+#[cfg(test)]
+const CONTENT_CODINGS: &[&[u8]] = &[
+    b"aes128gcm",
+    b"br",
+    b"compress",
+    b"dcb",
+    b"dcz",
+    b"deflate",
+    b"exi",
+    b"gzip",
+    b"identity",
+    b"pack200-gzip",
+    b"x-compress",
+    b"x-gzip",
+    b"zstd",
+];
+
+// https://www.iana.org/assignments/top-level-media-types/top-level-media-types.xhtml
+#[cfg(test)]
+const TOP_LEVEL_MEDIA_TYPES: &[&[u8]] = &[
+    b"application",
+    b"audio",
+    b"example",
+    b"font",
+    b"haptics",
+    b"image",
+    b"message",
+    b"model",
+    b"multipart",
+    b"text",
+    b"video",
+];
+
+// The lists above bucketed by length, so a lookup compares at most three
+// names. The tests check that the buckets hold exactly the listed names.
+fn content_codings_of_len(len: usize) -> &'static [&'static [u8]] {
+    match len {
+        2 => &[b"br"],
+        3 => &[b"dcb", b"dcz", b"exi"],
+        4 => &[b"gzip", b"zstd"],
+        6 => &[b"x-gzip"],
+        7 => &[b"deflate"],
+        8 => &[b"compress", b"identity"],
+        9 => &[b"aes128gcm"],
+        10 => &[b"x-compress"],
+        12 => &[b"pack200-gzip"],
+        _ => &[],
+    }
+}
+
+fn top_level_media_types_of_len(len: usize) -> &'static [&'static [u8]] {
+    match len {
+        4 => &[b"font", b"text"],
+        5 => &[b"audio", b"image", b"model", b"video"],
+        7 => &[b"example", b"haptics", b"message"],
+        9 => &[b"multipart"],
+        11 => &[b"application"],
+        _ => &[],
+    }
+}
+
+pub fn is_registered_content_coding(token: &[u8]) -> bool {
+    content_codings_of_len(token.len())
+        .iter()
+        .any(|name| bytes_eq_ignore_case(token, name))
+}
+
+pub fn is_registered_top_level_media_type(token: &[u8]) -> bool {
+    top_level_media_types_of_len(token.len())
+        .iter()
+        .any(|name| bytes_eq_ignore_case(token, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_match_lists() {
+        for (list, of_len) in [
+            (CONTENT_CODINGS, content_codings_of_len as fn(usize) -> _),
+            (TOP_LEVEL_MEDIA_TYPES, top_level_media_types_of_len),
+        ] {
+            let mut bucketed: Vec<&[u8]> = (0..=32).flat_map(of_len).copied().collect();
+            bucketed.sort();
+            assert_eq!(list, bucketed);
+            assert!(bucketed
+                .iter()
+                .all(|name| of_len(name.len()).contains(name)));
+        }
+    }
+
+    #[test]
+    fn test_is_registered_content_coding() {
+        assert!(is_registered_content_coding(b"gzip"));
+        assert!(is_registered_content_coding(b"ZSTD"));
+        assert!(!is_registered_content_coding(b"*"));
+        assert!(!is_registered_content_coding(b"sdch"));
+    }
+
+    #[test]
+    fn test_is_registered_top_level_media_type() {
+        assert!(is_registered_top_level_media_type(b"image"));
+        assert!(is_registered_top_level_media_type(b"Text"));
+        assert!(!is_registered_top_level_media_type(b"*"));
+        assert!(!is_registered_top_level_media_type(b"img"));
+    }
+}
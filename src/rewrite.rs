@@ -0,0 +1,114 @@
+use crate::{
+    encoding_matcher::{explain_encoding, EncodingMatchType},
+    lexer::is_token,
+    options::MatchOptions,
+};
+
+/// Returns `input` without the entries accepting `encoding`, or its legacy
+/// alias, for a proxy that cannot apply that coding. Entries refusing it
+/// with q=0 and the other entries are kept as written, and if a "*" entry
+/// would still accept it, `encoding;q=0` is appended. Returns None if
+/// `input` is malformed.
+pub fn remove_encoding(input: &[u8], encoding: &[u8]) -> Option<Vec<u8>> {
+    let explanation = explain_encoding(input, encoding, &MatchOptions::default());
+    if explanation.error_offset.is_some() {
+        return None;
+    }
+    let mut output = Vec::with_capacity(input.len());
+    let mut refused = false;
+    let mut accepted_by_wildcard = false;
+    for entry in explanation.entries {
+        match entry.matched {
+            Some(m) if m.match_type == EncodingMatchType::Exact => {
+                if !m.q.is_zero() {
+                    continue;
+                }
+                refused = true;
+            }
+            Some(m) => accepted_by_wildcard |= !m.q.is_zero(),
+            None => {}
+        }
+        if !output.is_empty() {
+            output.extend_from_slice(b", ");
+        }
+        output.extend_from_slice(entry.text);
+    }
+    if accepted_by_wildcard && !refused {
+        output.extend_from_slice(b", ");
+        output.extend_from_slice(encoding);
+        output.extend_from_slice(b";q=0");
+    }
+    Some(output)
+}
+
+/// Returns `input` with `encoding` appended, unless an entry already names
+/// it. Returns None if `input` is malformed or `encoding` is not a token.
+pub fn add_encoding(input: &[u8], encoding: &[u8]) -> Option<Vec<u8>> {
+    if !is_token(encoding) {
+        return None;
+    }
+    let explanation = explain_encoding(input, encoding, &MatchOptions::default());
+    if explanation.error_offset.is_some() {
+        return None;
+    }
+    let mut output = input.to_vec();
+    if explanation.entries.iter().any(|e| {
+        e.matched
+            .is_some_and(|m| m.match_type == EncodingMatchType::Exact)
+    }) {
+        return Some(output);
+    }
+    if !explanation.entries.is_empty() {
+        output.extend_from_slice(b", ");
+    }
+    output.extend_from_slice(encoding);
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_encoding() {
+        assert_eq!(
+            Some(b"gzip;q=0.5, br, *;q=0".to_vec()),
+            remove_encoding(b"gzip;q=0.5 , br,zstd;q=0.1, *;q=0", b"ZSTD")
+        );
+        assert_eq!(
+            Some(b"br, gzip;q=0".to_vec()),
+            remove_encoding(b"x-gzip, br, gzip;q=0", b"gzip")
+        );
+        assert_eq!(
+            Some(b"gzip;q=0, *".to_vec()),
+            remove_encoding(b"gzip;q=0, *", b"gzip")
+        );
+        assert_eq!(
+            Some(b"br, *;q=0.5, gzip;q=0".to_vec()),
+            remove_encoding(b"gzip, br, *;q=0.5", b"gzip")
+        );
+        assert_eq!(
+            Some(b"*, gzip;q=0".to_vec()),
+            remove_encoding(b"*", b"gzip")
+        );
+        assert_eq!(Some(Vec::new()), remove_encoding(b"gzip", b"gzip"));
+        assert_eq!(Some(Vec::new()), remove_encoding(b"", b"gzip"));
+        assert_eq!(None, remove_encoding(b"gzip;q=2", b"br"));
+    }
+
+    #[test]
+    fn test_add_encoding() {
+        assert_eq!(
+            Some(b"gzip;q=0.5, zstd".to_vec()),
+            add_encoding(b"gzip;q=0.5", b"zstd")
+        );
+        assert_eq!(
+            Some(b"x-gzip;q=0".to_vec()),
+            add_encoding(b"x-gzip;q=0", b"gzip")
+        );
+        assert_eq!(Some(b"*, gzip".to_vec()), add_encoding(b"*", b"gzip"));
+        assert_eq!(Some(b"br".to_vec()), add_encoding(b"", b"br"));
+        assert_eq!(None, add_encoding(b"gzip", b"b r"));
+        assert_eq!(None, add_encoding(b"gzip;q=2", b"br"));
+    }
+}
@@ -0,0 +1,120 @@
+//! A Ruby extension for Rack middleware and other Ruby code, loaded with
+//! `require "accept_encoding"` in place of parsing Accept headers in Ruby.
+
+use magnus::{function, prelude::*, Error, RArray, RHash, RString, Ruby, Value};
+
+use crate::{
+    encoding_matcher::{match_for_encoding, EncodingMatchType, Target},
+    mime_type_matcher::{match_for_mime_type, MimeTypeMatchType},
+    options::MatchOptions,
+    q_value::QValue,
+    supported_encodings::negotiate,
+};
+
+/// Defines the `AcceptEncoding` module. Ruby calls this through the
+/// `Init_accept_encoding` function the attribute generates.
+#[magnus::init]
+fn init(ruby: &Ruby) -> Result<(), Error> {
+    let module = ruby.define_module("AcceptEncoding")?;
+    module.define_module_function("match_for_encoding", function!(rb_match_for_encoding, 2))?;
+    module.define_module_function("match_for_mime_type", function!(rb_match_for_mime_type, 2))?;
+    module.define_module_function("negotiate_encoding", function!(rb_negotiate_encoding, 2))?;
+    Ok(())
+}
+
+fn rb_match_for_encoding(
+    ruby: &Ruby,
+    header_value: RString,
+    encoding: RString,
+) -> Result<Option<RHash>, Error> {
+    // Nothing calls into Ruby while the bytes of the strings are borrowed.
+    let Some(m) = (unsafe { match_for_encoding(header_value.as_slice(), encoding.as_slice()) })
+    else {
+        return Ok(None);
+    };
+    let match_type = match m.match_type {
+        EncodingMatchType::Wildcard => "wildcard",
+        EncodingMatchType::Exact => "exact",
+    };
+    match_hash(ruby, match_type, m.q).map(Some)
+}
+
+fn rb_match_for_mime_type(
+    ruby: &Ruby,
+    header_value: RString,
+    mime_type: RString,
+) -> Result<Option<RHash>, Error> {
+    let Some(m) = (unsafe { match_for_mime_type(header_value.as_slice(), mime_type.as_slice()) })
+    else {
+        return Ok(None);
+    };
+    let match_type = match m.match_type {
+        MimeTypeMatchType::MainTypeWildcard => "main_type_wildcard",
+        MimeTypeMatchType::SubTypeWildcard => "subtype_wildcard",
+        MimeTypeMatchType::Exact => "exact",
+    };
+    match_hash(ruby, match_type, m.q).map(Some)
+}
+
+/// Returns the element of `supported` to respond with, or nil. A nil
+/// `header_value`, for a request without Accept-Encoding, accepts nothing.
+fn rb_negotiate_encoding(
+    header_value: Option<RString>,
+    supported: RArray,
+) -> Result<Option<Value>, Error> {
+    let Some(header_value) = header_value else {
+        return Ok(None);
+    };
+    // Copied out, since Ruby strings must not be kept in a Vec.
+    let codings = supported.to_vec::<String>()?;
+    let targets = codings.iter().map(|s| Target::new(s.as_bytes()));
+    let header_value = unsafe { header_value.as_slice() };
+    negotiate(header_value, targets, &MatchOptions::default())
+        .map(|(i, _)| supported.entry(i as isize))
+        .transpose()
+}
+
+/// Returns `{ match_type: :exact, q: 1.0 }`.
+fn match_hash(ruby: &Ruby, match_type: &str, q: QValue) -> Result<RHash, Error> {
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("match_type"), ruby.to_symbol(match_type))?;
+    hash.aset(ruby.to_symbol("q"), f64::from(q))?;
+    Ok(hash)
+}
+
+#[cfg(all(test, feature = "ruby-embed"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module() {
+        Ruby::init(|ruby| {
+            init(ruby)?;
+            let check = |code: &str| -> Result<(), Error> {
+                assert!(ruby.eval::<bool>(code)?, "{code}");
+                Ok(())
+            };
+            check(
+                r#"AcceptEncoding.match_for_encoding("gzip;q=0.5, *", "gzip") ==
+                    { match_type: :exact, q: 0.5 }"#,
+            )?;
+            check(r#"AcceptEncoding.match_for_encoding("gzip", "br").nil?"#)?;
+            check(r#"AcceptEncoding.match_for_encoding("gzip;q=2", "gzip").nil?"#)?;
+            check(
+                r#"AcceptEncoding.match_for_mime_type("text/html, image/*;q=0.8", "image/webp") ==
+                    { match_type: :subtype_wildcard, q: 0.8 }"#,
+            )?;
+            check(
+                r#"AcceptEncoding.negotiate_encoding("gzip;q=0.5, br", ["zstd", "gzip", "br"]) ==
+                    "br""#,
+            )?;
+            check(r#"AcceptEncoding.negotiate_encoding("gzip", ["br"]).nil?"#)?;
+            check(r#"AcceptEncoding.negotiate_encoding(nil, ["br"]).nil?"#)?;
+            assert!(ruby
+                .eval::<Value>(r#"AcceptEncoding.match_for_encoding(nil, "gzip")"#)
+                .is_err());
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+use crate::lexer::{is_qdtext, is_tchar};
+
+const LANES: usize = 16;
+
+/// Returns the length of the longest prefix of `input` made of tchar bytes.
+/// On x86_64 whole 16-byte chunks are classified with SSE2; the tail and
+/// other targets use the lookup table.
+pub(crate) fn tchar_prefix_len(input: &[u8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    let i = sse2::prefix_len(input, sse2::non_tchar_mask);
+    #[cfg(not(target_arch = "x86_64"))]
+    let i = 0;
+    i + scalar_prefix_len(&input[i..], is_tchar)
+}
+
+/// Returns the length of the longest prefix of `input` made of qdtext bytes.
+pub(crate) fn qdtext_prefix_len(input: &[u8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    let i = sse2::prefix_len(input, sse2::non_qdtext_mask);
+    #[cfg(not(target_arch = "x86_64"))]
+    let i = 0;
+    i + scalar_prefix_len(&input[i..], is_qdtext)
+}
+
+#[inline]
+fn scalar_prefix_len(input: &[u8], pred: fn(u8) -> bool) -> usize {
+    input.iter().position(|b| !pred(*b)).unwrap_or(input.len())
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use std::arch::x86_64::*;
+
+    use super::LANES;
+
+    /// Scans whole 16-byte chunks and returns where the first byte flagged
+    /// by `mask` is, or the start of the unscanned tail.
+    #[inline]
+    pub(super) fn prefix_len(input: &[u8], mask: unsafe fn(__m128i) -> i32) -> usize {
+        let mut i = 0;
+        while i + LANES <= input.len() {
+            // SAFETY: SSE2 is part of the x86_64 baseline and the load reads
+            // the 16 bytes at `i`, which are in bounds.
+            let m = unsafe { mask(_mm_loadu_si128(input.as_ptr().add(i) as *const __m128i)) };
+            if m != 0 {
+                return i + m.trailing_zeros() as usize;
+            }
+            i += LANES;
+        }
+        i
+    }
+
+    #[inline]
+    unsafe fn eq(v: __m128i, b: u8) -> __m128i {
+        _mm_cmpeq_epi8(v, _mm_set1_epi8(b as i8))
+    }
+
+    /// Signed compares put obs-text (0x80-0xFF) below 0x21, so it fails
+    /// the range check like the control characters do.
+    pub(super) unsafe fn non_tchar_mask(v: __m128i) -> i32 {
+        let visible = _mm_and_si128(
+            _mm_cmpgt_epi8(v, _mm_set1_epi8(0x20)),
+            _mm_cmplt_epi8(v, _mm_set1_epi8(0x7f)),
+        );
+        // ":" / ";" / "<" / "=" / ">" / "?" / "@"
+        let colon_to_at = _mm_and_si128(
+            _mm_cmpgt_epi8(v, _mm_set1_epi8(0x39)),
+            _mm_cmplt_epi8(v, _mm_set1_epi8(0x41)),
+        );
+        let mut delim = colon_to_at;
+        for b in [b'"', b'(', b')', b',', b'/', b'[', b'\\', b']', b'{', b'}'] {
+            delim = _mm_or_si128(delim, eq(v, b));
+        }
+        _mm_movemask_epi8(_mm_andnot_si128(
+            _mm_andnot_si128(delim, visible),
+            _mm_set1_epi8(-1),
+        ))
+    }
+
+    pub(super) unsafe fn non_qdtext_mask(v: __m128i) -> i32 {
+        // 0x00-0x1F except HTAB; obs-text is negative and fails this check.
+        let ctl = _mm_andnot_si128(
+            eq(v, b'\t'),
+            _mm_and_si128(
+                _mm_cmplt_epi8(v, _mm_set1_epi8(0x20)),
+                _mm_cmpgt_epi8(v, _mm_set1_epi8(-1)),
+            ),
+        );
+        let other = _mm_or_si128(_mm_or_si128(eq(v, b'"'), eq(v, b'\\')), eq(v, 0x7f));
+        _mm_movemask_epi8(_mm_or_si128(ctl, other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_len_matches_tables() {
+        for len in [0, 1, 15, 16, 17, 40] {
+            for pos in 0..len {
+                for b in 0..=255u8 {
+                    let mut input = vec![b'a'; len];
+                    input[pos] = b;
+                    assert_eq!(
+                        scalar_prefix_len(&input, is_tchar),
+                        tchar_prefix_len(&input),
+                        "len={len}, pos={pos}, b={b:#x}"
+                    );
+                    assert_eq!(
+                        scalar_prefix_len(&input, is_qdtext),
+                        qdtext_prefix_len(&input),
+                        "len={len}, pos={pos}, b={b:#x}"
+                    );
+                }
+            }
+        }
+    }
+}
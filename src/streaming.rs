@@ -0,0 +1,161 @@
+use std::mem;
+
+use crate::{
+    encoding_matcher::{EncodingMatch, EncodingMatchType, Parser, Target},
+    lexer::Cursor,
+    options::MatchOptions,
+    q_value::QValue,
+};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Feed {
+    /// The result depends on bytes not fed yet.
+    NeedMore,
+    /// The result is known; later bytes cannot change it.
+    Done(Option<EncodingMatch>),
+}
+
+/// Matches an Accept-Encoding value that arrives in pieces.
+///
+/// Complete list elements are parsed straight from the fed chunks. Only
+/// an element split across chunks is copied, so memory use is bounded by
+/// the longest element rather than the whole header value. The result is
+/// the same as `match_for_encoding_with_options` on the concatenated bytes.
+pub struct StreamingEncodingMatcher<'e> {
+    parser: Parser<'e>,
+    options: MatchOptions,
+    pending: Vec<u8>,
+    in_quoted_string: bool,
+    escaped: bool,
+    after_comma: bool,
+    done: Option<Option<EncodingMatch>>,
+}
+
+impl<'e> StreamingEncodingMatcher<'e> {
+    pub fn new(encoding: &'e [u8], options: MatchOptions) -> Self {
+        Self {
+            parser: Parser::new(Target::new(encoding), Cursor(0)),
+            options,
+            pending: Vec::new(),
+            in_quoted_string: false,
+            escaped: false,
+            after_comma: false,
+            done: None,
+        }
+    }
+
+    pub fn feed(&mut self, mut chunk: &[u8]) -> Feed {
+        loop {
+            if let Some(result) = self.done {
+                return Feed::Done(result);
+            }
+            if self.after_comma {
+                // The parser skips whitespace after a comma only within the
+                // bytes it is given, so drop it here as it arrives.
+                let n = chunk
+                    .iter()
+                    .take_while(|b| matches!(b, b' ' | b'\t'))
+                    .count();
+                chunk = &chunk[n..];
+                if chunk.is_empty() {
+                    return Feed::NeedMore;
+                }
+                self.after_comma = false;
+            }
+            let Some(i) = self.find_list_separator(chunk) else {
+                self.pending.extend_from_slice(chunk);
+                return Feed::NeedMore;
+            };
+            let (elements, rest) = chunk.split_at(i + 1);
+            if self.pending.is_empty() {
+                self.parse_elements(elements);
+            } else {
+                let mut pending = mem::take(&mut self.pending);
+                pending.extend_from_slice(elements);
+                self.parse_elements(&pending);
+                pending.clear();
+                self.pending = pending;
+            }
+            self.after_comma = true;
+            chunk = rest;
+        }
+    }
+
+    /// Ends the header value and returns the result.
+    pub fn finish(mut self) -> Option<EncodingMatch> {
+        if let Some(result) = self.done {
+            return result;
+        }
+        let pending = mem::take(&mut self.pending);
+        let mut c = Cursor(0);
+        match self.parser.run(&pending, &self.options, &mut c, &mut ()) {
+            Ok(()) => self.parser.finish(&mut ()),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the index of the first comma outside a quoted string,
+    /// keeping track of quoting across chunks.
+    fn find_list_separator(&mut self, chunk: &[u8]) -> Option<usize> {
+        for (i, b) in chunk.iter().enumerate() {
+            if self.escaped {
+                self.escaped = false;
+            } else if *b == b'"' {
+                self.in_quoted_string = !self.in_quoted_string;
+            } else if *b == b'\\' && self.in_quoted_string {
+                self.escaped = true;
+            } else if *b == b',' && !self.in_quoted_string {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Parses complete list elements ending with a comma.
+    fn parse_elements(&mut self, elements: &[u8]) {
+        let mut c = Cursor(0);
+        if self
+            .parser
+            .run(elements, &self.options, &mut c, &mut ())
+            .is_err()
+        {
+            self.done = Some(None);
+        } else if self.options.lenient && self.parser.has_unbeatable_match() {
+            self.done = Some(Some(EncodingMatch {
+                match_type: EncodingMatchType::Exact,
+                q: QValue::ONE,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding_matcher::match_for_encoding_with_options;
+
+    fn feed_split(input: &[u8], splits: &[usize], options: MatchOptions) -> Option<EncodingMatch> {
+        let mut matcher = StreamingEncodingMatcher::new(b"gzip", options);
+        let mut start = 0;
+        for &end in splits.iter().chain([input.len()].iter()) {
+            if let Feed::Done(result) = matcher.feed(&input[start..end]) {
+                return result;
+            }
+            start = end;
+        }
+        matcher.finish()
+    }
+
+    #[test]
+    fn test_streaming_encoding_matcher() {
+        let options = MatchOptions::default();
+        assert_eq!(
+            match_for_encoding_with_options(b"br, gzip;q=0.5", b"gzip", &options),
+            feed_split(b"br, gzip;q=0.5", &[1, 3, 4, 6, 12], options)
+        );
+
+        let mut matcher = StreamingEncodingMatcher::new(b"gzip", options);
+        assert_eq!(Feed::NeedMore, matcher.feed(b"gz"));
+        assert_eq!(Feed::Done(None), matcher.feed(b"ip;q=2, br"));
+    }
+}
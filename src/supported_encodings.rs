@@ -0,0 +1,164 @@
+use crate::{
+    encoding_matcher::{match_target, EncodingMatch, EncodingMatchType, Target},
+    lexer::{self, Cursor},
+    options::MatchOptions,
+    q_value::QValue,
+    InvalidEncodingNameError,
+};
+
+/// The content codings a server can produce, in its order of preference.
+/// Only the codings are validated and lowercased up front; the header value
+/// is still scanned once per supported coding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportedEncodings {
+    encodings: Vec<SupportedEncoding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SupportedEncoding {
+    encoding: Box<[u8]>,
+    alias: Option<&'static [u8]>,
+}
+
+impl SupportedEncodings {
+    pub fn new<I, S>(encodings: I) -> Result<Self, InvalidEncodingNameError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<[u8]>,
+    {
+        let encodings = encodings
+            .into_iter()
+            .map(|encoding| {
+                let encoding = encoding.as_ref();
+                if !lexer::is_token(encoding) {
+                    return Err(InvalidEncodingNameError);
+                }
+                let encoding: Box<[u8]> = encoding.to_ascii_lowercase().into();
+                let alias = Target::new(&encoding).alias;
+                Ok(SupportedEncoding { encoding, alias })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { encodings })
+    }
+
+    /// Returns the lowercased names in preference order.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.encodings.iter().map(|e| &e.encoding[..])
+    }
+
+    pub fn best_match(&self, input: &[u8]) -> Option<(&[u8], EncodingMatch)> {
+        self.best_match_with_options(input, &MatchOptions::default())
+    }
+
+    /// Picks the acceptable encoding with the highest q-value. Ties go to an
+    /// encoding the header names over one only covered by "*", then to the
+    /// one listed first here.
+    pub fn best_match_with_options(
+        &self,
+        input: &[u8],
+        options: &MatchOptions,
+    ) -> Option<(&[u8], EncodingMatch)> {
+        let targets = self.encodings.iter().map(|e| Target {
+            encoding: &e.encoding,
+            alias: e.alias,
+            any: false,
+        });
+        let (i, m) = negotiate(input, targets, options)?;
+        Some((&self.encodings[i].encoding, m))
+    }
+}
+
+/// The coding a middleware picked for a request, stored in its extensions
+/// for handlers and compression layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedEncoding {
+    /// The lowercased name from the supported list, or None if the request
+    /// accepts none of them.
+    pub encoding: Option<String>,
+}
+
+/// Returns the index of the best of `targets` for `input` by the rules of
+/// `SupportedEncodings::best_match_with_options`, with its match.
+pub(crate) fn negotiate<'e>(
+    input: &[u8],
+    targets: impl IntoIterator<Item = Target<'e>>,
+    options: &MatchOptions,
+) -> Option<(usize, EncodingMatch)> {
+    let mut best: Option<(usize, EncodingMatch)> = None;
+    for (i, target) in targets.into_iter().enumerate() {
+        let m = match match_target(input, target, options, &mut Cursor(0), &mut ()) {
+            Ok(Some(m)) if !m.q.is_zero() => m,
+            Ok(_) => continue,
+            // The header is rejected as a whole.
+            Err(_) => return None,
+        };
+        if best.is_none_or(|(_, b)| rank(m) > rank(b)) {
+            best = Some((i, m));
+        }
+    }
+    best
+}
+
+fn rank(m: EncodingMatch) -> (QValue, bool) {
+    (m.q, m.match_type == EncodingMatchType::Exact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_encodings_best_match() {
+        let supported = SupportedEncodings::new(["br", "GZIP", "deflate"]).unwrap();
+        assert_eq!(
+            vec![&b"br"[..], b"gzip", b"deflate"],
+            supported.iter().collect::<Vec<_>>()
+        );
+
+        let exact = |q| EncodingMatch {
+            match_type: EncodingMatchType::Exact,
+            q: QValue::from_millis(q).unwrap(),
+        };
+        assert_eq!(
+            Some((&b"br"[..], exact(1000))),
+            supported.best_match(b"gzip, deflate, br")
+        );
+        assert_eq!(
+            Some((&b"gzip"[..], exact(1000))),
+            supported.best_match(b"x-gzip, br;q=0.5")
+        );
+        assert_eq!(
+            Some((&b"deflate"[..], exact(1000))),
+            supported.best_match(b"*;q=0.5, deflate")
+        );
+        assert_eq!(
+            Some((&b"br"[..], exact(1000))),
+            supported.best_match(b"deflate, gzip, br")
+        );
+        assert_eq!(
+            Some((&b"gzip"[..], exact(500))),
+            supported.best_match(b"*;q=0.5, gzip;q=0.5, deflate;q=0")
+        );
+        assert_eq!(
+            Some((
+                &b"br"[..],
+                EncodingMatch {
+                    match_type: EncodingMatchType::Wildcard,
+                    q: QValue::from_millis(500).unwrap(),
+                }
+            )),
+            supported.best_match(b"*;q=0.5, deflate;q=0")
+        );
+        assert_eq!(None, supported.best_match(b"br;q=0, gzip;q=0, *;q=0"));
+        assert_eq!(None, supported.best_match(b"identity"));
+        assert_eq!(None, supported.best_match(b"gzip;q=2"));
+    }
+
+    #[test]
+    fn test_supported_encodings_invalid_name() {
+        assert_eq!(
+            Err(InvalidEncodingNameError),
+            SupportedEncodings::new(["gzip", "b r"])
+        );
+    }
+}
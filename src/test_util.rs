@@ -0,0 +1,18 @@
+use std::ops::RangeInclusive;
+
+/// Calls `f` with every string of bytes from `alphabet` whose length is in
+/// `lens`, for tests that check a property over all short inputs.
+pub(crate) fn for_each_input(alphabet: &[u8], lens: RangeInclusive<u32>, mut f: impl FnMut(&[u8])) {
+    let mut input = Vec::new();
+    for len in lens {
+        for n in 0..alphabet.len().pow(len) {
+            input.clear();
+            let mut n = n;
+            for _ in 0..len {
+                input.push(alphabet[n % alphabet.len()]);
+                n /= alphabet.len();
+            }
+            f(&input);
+        }
+    }
+}
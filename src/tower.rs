@@ -0,0 +1,185 @@
+//! A tower middleware that negotiates the response coding up front, for
+//! compression layers and handlers to read from the request extensions.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http::{header, HeaderMap, HeaderValue, Request, Response};
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::supported_encodings::{NegotiatedEncoding, SupportedEncodings};
+
+/// Wraps services in `NegotiateEncoding`.
+#[derive(Debug, Clone)]
+pub struct NegotiateEncodingLayer {
+    supported: Arc<SupportedEncodings>,
+}
+
+impl NegotiateEncodingLayer {
+    pub fn new(supported: SupportedEncodings) -> Self {
+        Self {
+            supported: Arc::new(supported),
+        }
+    }
+}
+
+impl<S> Layer<S> for NegotiateEncodingLayer {
+    type Service = NegotiateEncoding<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NegotiateEncoding {
+            inner,
+            supported: self.supported.clone(),
+        }
+    }
+}
+
+/// Stores a `NegotiatedEncoding` in each request by its Accept-Encoding
+/// fields, and adds Accept-Encoding to the Vary of each response, since
+/// the response may depend on it.
+#[derive(Debug, Clone)]
+pub struct NegotiateEncoding<S> {
+    inner: S,
+    supported: Arc<SupportedEncodings>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for NegotiateEncoding<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let encoding = self
+            .supported
+            .best_match_from_headers(request.headers())
+            .map(|(e, _)| String::from_utf8_lossy(e).into_owned());
+        request
+            .extensions_mut()
+            .insert(NegotiatedEncoding { encoding });
+        ResponseFuture {
+            inner: self.inner.call(request),
+        }
+    }
+}
+
+pin_project! {
+    /// The future of `NegotiateEncoding`.
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+    }
+}
+
+impl<F, ResBody, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut response = std::task::ready!(self.project().inner.poll(cx))?;
+        add_vary(response.headers_mut());
+        Poll::Ready(Ok(response))
+    }
+}
+
+/// Appends Accept-Encoding to Vary unless it is there already or Vary is
+/// "*".
+fn add_vary(headers: &mut HeaderMap) {
+    let covered = headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|v| v == "*" || v.eq_ignore_ascii_case("accept-encoding"));
+    if !covered {
+        headers.append(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, future::Ready, task::Waker};
+
+    use super::*;
+
+    /// Echoes the negotiated coding in the body.
+    struct Echo(Option<HeaderValue>);
+
+    impl Service<Request<()>> for Echo {
+        type Response = Response<Option<String>>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            let negotiated = request.extensions().get::<NegotiatedEncoding>().unwrap();
+            let mut response = Response::new(negotiated.encoding.clone());
+            if let Some(vary) = &self.0 {
+                response.headers_mut().insert(header::VARY, vary.clone());
+            }
+            std::future::ready(Ok(response))
+        }
+    }
+
+    fn call(vary: Option<&'static str>, accept_encoding: &'static str) -> Response<Option<String>> {
+        let layer = NegotiateEncodingLayer::new(SupportedEncodings::new(["br", "gzip"]).unwrap());
+        let mut service = layer.layer(Echo(vary.map(HeaderValue::from_static)));
+        let request = Request::builder()
+            .header(header::ACCEPT_ENCODING, accept_encoding)
+            .body(())
+            .unwrap();
+        let mut future = std::pin::pin!(service.call(request));
+        let mut cx = Context::from_waker(Waker::noop());
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(response)) => response,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        let response = call(None, "gzip, br;q=0.5");
+        assert_eq!(Some("gzip"), response.body().as_deref());
+        assert_eq!(
+            vec!["Accept-Encoding"],
+            response
+                .headers()
+                .get_all(header::VARY)
+                .iter()
+                .collect::<Vec<_>>()
+        );
+
+        let response = call(Some("Origin"), "zstd");
+        assert_eq!(None, response.body().as_deref());
+        assert_eq!(
+            vec!["Origin", "Accept-Encoding"],
+            response
+                .headers()
+                .get_all(header::VARY)
+                .iter()
+                .collect::<Vec<_>>()
+        );
+
+        let response = call(Some("origin, accept-encoding"), "br");
+        assert_eq!(Some("br"), response.body().as_deref());
+        assert_eq!(1, response.headers().get_all(header::VARY).iter().count());
+    }
+}
@@ -0,0 +1,23 @@
+use std::fmt::Debug;
+
+use crate::lexer::{Cursor, ParseError};
+
+pub(crate) fn outcome<M: Debug>(result: &Result<Option<M>, ParseError>, c: Cursor) {
+    match result {
+        Ok(Some(m)) => tracing::debug!(result = ?m, "matched"),
+        Ok(None) => tracing::debug!("no match"),
+        Err(_) => tracing::debug!(offset = c.0, "malformed header value"),
+    }
+}
+
+pub(crate) fn truncated_q_value(input: &[u8], start: Cursor, end: Cursor) {
+    tracing::debug!(
+        offset = start.0,
+        q_value = %String::from_utf8_lossy(start.slice(input, end)),
+        "truncated q-value"
+    );
+}
+
+pub(crate) fn skipped_entry(c: Cursor) {
+    tracing::debug!(offset = c.0, "skipped malformed list element");
+}
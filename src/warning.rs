@@ -0,0 +1,53 @@
+use crate::explain::{EntryVerdict, Observer};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WarningReason {
+    /// A malformed list element was skipped up to the next comma.
+    SkippedMalformedEntry,
+    /// A q-value had more than three fractional digits.
+    TruncatedQValue,
+    /// The header value ended with whitespace.
+    TrailingWhitespace,
+    /// A content coding or top-level media type is not registered with IANA.
+    UnregisteredToken,
+}
+
+/// Something lenient matching tolerated instead of rejecting the header.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Warning {
+    pub offset: usize,
+    pub reason: WarningReason,
+}
+
+pub(crate) struct WarningForwarder<F>(pub F);
+
+impl<M, F: FnMut(Warning)> Observer<M> for WarningForwarder<F> {
+    #[inline]
+    fn entry(&mut self, _start: usize, _end: usize, _matched: Option<M>, _verdict: EntryVerdict) {}
+
+    #[inline]
+    fn warning(&mut self, warning: Warning) {
+        (self.0)(warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_forwarder() {
+        let mut warnings = Vec::new();
+        let mut forwarder = WarningForwarder(|w| warnings.push(w));
+        let warning = Warning {
+            offset: 3,
+            reason: WarningReason::TruncatedQValue,
+        };
+        Observer::<()>::warning(&mut forwarder, warning);
+        assert_eq!(vec![warning], warnings);
+    }
+}
@@ -0,0 +1,112 @@
+//! warp filters that check or negotiate the response coding by the
+//! request's Accept-Encoding fields.
+
+use std::{convert::Infallible, sync::Arc};
+
+use warp::{
+    http::{header::ACCEPT_ENCODING, HeaderMap},
+    reject::{Reject, Rejection},
+    Filter,
+};
+
+use crate::{
+    byte_slice::join_field_values,
+    encoding_matcher::match_for_encoding,
+    supported_encodings::{NegotiatedEncoding, SupportedEncodings},
+};
+
+/// The rejection of `accepts_encoding`, for a `recover` handler to turn
+/// into 406 Not Acceptable.
+#[derive(Debug)]
+pub struct EncodingNotAccepted;
+
+impl Reject for EncodingNotAccepted {}
+
+/// Passes requests whose Accept-Encoding accepts `encoding` with a q-value
+/// above 0, and rejects the others with `EncodingNotAccepted`.
+pub fn accepts_encoding(
+    encoding: &'static str,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::headers_cloned()
+        .and_then(move |headers: HeaderMap| async move {
+            let accepted = match_for_encoding(&accept_encoding(&headers), encoding.as_bytes())
+                .is_some_and(|m| !m.q.is_zero());
+            if accepted {
+                Ok(())
+            } else {
+                Err(warp::reject::custom(EncodingNotAccepted))
+            }
+        })
+        .untuple_one()
+}
+
+/// Extracts the coding picked from `supported` for each request.
+pub fn negotiated_encoding(
+    supported: SupportedEncodings,
+) -> impl Filter<Extract = (NegotiatedEncoding,), Error = Infallible> + Clone {
+    let supported = Arc::new(supported);
+    warp::header::headers_cloned().map(move |headers: HeaderMap| {
+        let encoding = supported
+            .best_match(&accept_encoding(&headers))
+            .map(|(e, _)| String::from_utf8_lossy(e).into_owned());
+        NegotiatedEncoding { encoding }
+    })
+}
+
+fn accept_encoding(headers: &HeaderMap) -> std::borrow::Cow<'_, [u8]> {
+    join_field_values(
+        headers
+            .get_all(ACCEPT_ENCODING)
+            .iter()
+            .map(|v| v.as_bytes()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_accepts_encoding() {
+        let filter = accepts_encoding("br");
+        block_on(async {
+            assert!(
+                warp::test::request()
+                    .header("accept-encoding", "gzip")
+                    .header("accept-encoding", "br;q=0.5")
+                    .matches(&filter)
+                    .await
+            );
+            assert!(
+                !warp::test::request()
+                    .header("accept-encoding", "gzip, br;q=0")
+                    .matches(&filter)
+                    .await
+            );
+            let rejection = warp::test::request().filter(&filter).await.unwrap_err();
+            assert!(rejection.find::<EncodingNotAccepted>().is_some());
+        });
+    }
+
+    #[test]
+    fn test_negotiated_encoding() {
+        let filter = negotiated_encoding(SupportedEncodings::new(["br", "gzip"]).unwrap());
+        block_on(async {
+            let negotiated = warp::test::request()
+                .header("accept-encoding", "gzip, br;q=0.5")
+                .filter(&filter)
+                .await
+                .unwrap();
+            assert_eq!(Some("gzip"), negotiated.encoding.as_deref());
+            let negotiated = warp::test::request().filter(&filter).await.unwrap();
+            assert_eq!(None, negotiated.encoding);
+        });
+    }
+}
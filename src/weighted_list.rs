@@ -0,0 +1,170 @@
+use crate::q_value::QValue;
+
+/// Items with q-values in header order, as the Accept-* headers list them.
+/// The parsed header types keep their entries in one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedList<T> {
+    entries: Vec<(T, QValue)>,
+}
+
+impl<T> Default for WeightedList<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> WeightedList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, item: T, q: QValue) {
+        self.entries.push((item, q));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<(&T, QValue)> {
+        self.entries.get(i).map(|(item, q)| (item, *q))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, QValue)> {
+        self.entries.iter().map(|(item, q)| (item, *q))
+    }
+
+    /// Sorts by q-value, highest first, keeping the header order among
+    /// equal ones.
+    pub fn sort_by_q(&mut self) {
+        self.entries.sort_by(|(_, q1), (_, q2)| q2.cmp(q1));
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T, QValue) -> bool) {
+        self.entries.retain(|(item, q)| f(item, *q));
+    }
+
+    /// Drops the items with q=0, which mark them as not acceptable.
+    pub fn retain_acceptable(&mut self) {
+        self.retain(|_, q| !q.is_zero());
+    }
+
+    /// Returns the item with the highest q-value, the earlier one on a tie,
+    /// or None if the list is empty or every q-value is 0.
+    pub fn best(&self) -> Option<(&T, QValue)> {
+        let mut best: Option<(&T, QValue)> = None;
+        for (item, q) in self.iter() {
+            if !q.is_zero() && best.is_none_or(|(_, b)| q > b) {
+                best = Some((item, q));
+            }
+        }
+        best
+    }
+
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> WeightedList<U> {
+        self.entries
+            .into_iter()
+            .map(|(item, q)| (f(item), q))
+            .collect()
+    }
+}
+
+impl<T: AsRef<[u8]>> WeightedList<T> {
+    /// Writes the list as a header value, such as "br, gzip;q=0.5", leaving
+    /// out q=1.
+    pub fn to_header_value(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (i, (item, q)) in self.iter().enumerate() {
+            if i > 0 {
+                out.extend_from_slice(b", ");
+            }
+            out.extend_from_slice(item.as_ref());
+            if !q.is_max() {
+                out.extend_from_slice(b";q=");
+                q.write_to(&mut out);
+            }
+        }
+        out
+    }
+}
+
+impl<T> FromIterator<(T, QValue)> for WeightedList<T> {
+    fn from_iter<I: IntoIterator<Item = (T, QValue)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> IntoIterator for WeightedList<T> {
+    type Item = (T, QValue);
+    type IntoIter = std::vec::IntoIter<(T, QValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(millis: u16) -> QValue {
+        QValue::const_millis(millis)
+    }
+
+    #[test]
+    fn test_weighted_list() {
+        let mut list: WeightedList<&str> = [
+            ("gzip", q(500)),
+            ("br", q(1000)),
+            ("zstd", q(500)),
+            ("*", q(0)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(4, list.len());
+        assert_eq!(Some((&"br", q(1000))), list.best());
+        assert_eq!(Some((&"zstd", q(500))), list.get(2));
+
+        list.sort_by_q();
+        let items: Vec<_> = list.iter().map(|(item, _)| *item).collect();
+        assert_eq!(vec!["br", "gzip", "zstd", "*"], items);
+
+        list.retain_acceptable();
+        list.retain(|item, _| *item != "br");
+        assert_eq!(Some((&"gzip", q(500))), list.best());
+        let lengths = list.map(str::len);
+        assert_eq!(
+            vec![(4, q(500)), (4, q(500))],
+            lengths.into_iter().collect::<Vec<_>>()
+        );
+
+        let list: WeightedList<&[u8]> = [(&b"br"[..], q(1000)), (b"gzip", q(500)), (b"*", q(0))]
+            .into_iter()
+            .collect();
+        assert_eq!(&b"br, gzip;q=0.5, *;q=0"[..], list.to_header_value());
+
+        let mut list = WeightedList::new();
+        assert_eq!(None, list.best());
+        list.push("identity", QValue::ZERO);
+        assert_eq!(None, list.best());
+        assert!(!list.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_weighted_list_serde() {
+        let list: WeightedList<String> = [("br".to_string(), q(800))].into_iter().collect();
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(r#"{"entries":[["br",0.8]]}"#, json);
+        assert_eq!(list, serde_json::from_str(&json).unwrap());
+    }
+}
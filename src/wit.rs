@@ -0,0 +1,96 @@
+//! The `accept-encoding` world of wit/accept-encoding.wit, for building the
+//! crate as a WebAssembly component.
+
+use crate::{
+    encoding_matcher::{self, EncodingMatchType, Target},
+    mime_type_matcher::{self, MimeTypeMatchType},
+    options::MatchOptions,
+    supported_encodings::negotiate,
+};
+
+wit_bindgen::generate!({
+    world: "accept-encoding",
+    path: "wit",
+});
+
+use exports::hnakamur::accept_encoding::matcher::{self, EncodingMatch, Guest, MimeTypeMatch};
+
+struct Component;
+
+impl Guest for Component {
+    fn match_for_encoding(header_value: String, encoding: String) -> Option<EncodingMatch> {
+        let m = encoding_matcher::match_for_encoding(header_value.as_bytes(), encoding.as_bytes())?;
+        Some(EncodingMatch {
+            match_type: match m.match_type {
+                EncodingMatchType::Wildcard => matcher::EncodingMatchType::Wildcard,
+                EncodingMatchType::Exact => matcher::EncodingMatchType::Exact,
+            },
+            q: m.q.into(),
+        })
+    }
+
+    fn match_for_mime_type(header_value: String, mime_type: String) -> Option<MimeTypeMatch> {
+        let m =
+            mime_type_matcher::match_for_mime_type(header_value.as_bytes(), mime_type.as_bytes())?;
+        Some(MimeTypeMatch {
+            match_type: match m.match_type {
+                MimeTypeMatchType::MainTypeWildcard => matcher::MimeTypeMatchType::MainTypeWildcard,
+                MimeTypeMatchType::SubTypeWildcard => matcher::MimeTypeMatchType::SubtypeWildcard,
+                MimeTypeMatchType::Exact => matcher::MimeTypeMatchType::Exact,
+            },
+            q: m.q.into(),
+        })
+    }
+
+    fn negotiate_encoding(header_value: String, supported: Vec<String>) -> Option<u32> {
+        let targets = supported.iter().map(|s| Target::new(s.as_bytes()));
+        let (i, _) = negotiate(header_value.as_bytes(), targets, &MatchOptions::default())?;
+        Some(i as u32)
+    }
+
+    fn validate_accept_encoding(header_value: String) -> Result<(), u32> {
+        encoding_matcher::validate_accept_encoding(header_value.as_bytes()).map_err(|e| e as u32)
+    }
+}
+
+// Only exported when building for WebAssembly: the export names contain
+// ":", which the version script of the host cdylib cannot hold.
+#[cfg(target_arch = "wasm32")]
+export!(Component);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component() {
+        let m = Component::match_for_encoding("gzip;q=0.5, *".into(), "gzip".into()).unwrap();
+        assert_eq!(matcher::EncodingMatchType::Exact, m.match_type);
+        assert_eq!(0.5, m.q);
+        assert!(Component::match_for_encoding("gzip;q=2".into(), "gzip".into()).is_none());
+        let m =
+            Component::match_for_mime_type("text/html, image/*;q=0.8".into(), "image/png".into())
+                .unwrap();
+        assert_eq!(matcher::MimeTypeMatchType::SubtypeWildcard, m.match_type);
+        assert_eq!(0.8, m.q);
+        assert_eq!(
+            Some(2),
+            Component::negotiate_encoding(
+                "gzip;q=0.5, br".into(),
+                vec!["zstd".into(), "gzip".into(), "br".into()]
+            )
+        );
+        assert_eq!(
+            None,
+            Component::negotiate_encoding("gzip".into(), vec!["br".into()])
+        );
+        assert_eq!(
+            Ok(()),
+            Component::validate_accept_encoding("gzip, br".into())
+        );
+        assert_eq!(
+            Err(11),
+            Component::validate_accept_encoding("gzip, br;q=2".into())
+        );
+    }
+}